@@ -1,7 +1,9 @@
 //! Module for Return the accounts and list of sub-accounts related to the given token.
 //! This API accepts parameters for convenient search, sorting and pagination.
 
-use crate::{SendReq, SortOrder, MONITORING_API_URL};
+use crate::extras::impl_unknown_fields;
+use crate::pagination::MAX_PAGE_SIZE;
+use crate::{Paginated, SendReq, SolaredgeCredentials, SortOrder, MONITORING_API_URL};
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -13,6 +15,8 @@ pub struct Req {
     search_text: String,
     sort_property: String,
     sort_order: String,
+    page_size: u16,
+    first_index: u16,
 }
 
 /// A sorting option for this account list, based on one of its properties.
@@ -60,31 +64,53 @@ impl std::fmt::Display for SortProperty {
 
 /// accounts_list response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Resp {
     /// The sites matching the request.
     pub accounts: Accounts,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    accounts: "accounts",
+});
+
 /// The accounts matching the request.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Accounts {
     /// The count of matching accounts
     pub count: u16,
 
     /// Array of matching accounts
     pub list: Entries,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Accounts { list: "list" });
+
 /// Array of matching accounts
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(transparent)]
 pub struct Entries {
     /// Transparent list of accounts
     pub e: Vec<AccountDetails>,
 }
 
+impl_unknown_fields!(transparent Entries, e);
+
 /// Detailed information for a single account.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct AccountDetails {
     /// account ID
@@ -119,16 +145,27 @@ pub struct AccountDetails {
 
     /// Miscellaneous uris associated with the web page for the site.
     pub uris: HashMap<String, String>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(AccountDetails {
+    location: "location",
+});
+
 /// Location of the account.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct AccountLocation {
     /// Country of the SolarEdge inverter.
     pub country: String,
 
     /// State of the SolarEdge inverter.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub state: Option<String>, // seems US specific. should this be Option<String>? probably
 
     /// City of the SolarEdge inverter.
@@ -142,20 +179,27 @@ pub struct AccountLocation {
 
     /// Zip code 1 of the SolarEdge inverter. Used in UK, in EU?
     pub zip: String, // seems US specific. should this be Option<String>?
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(AccountLocation {});
+
 impl Req {
     /// Create a accounts_list request message that can be sent to SolarEdge.
     ///
     /// # Arguments
     ///
     /// * `size` - The maximum number of accounts returned by this call.
-    ///            If you have more than 100 accounts, just request another 100
-    ///            accounts with startIndex=100. This will fetch accounts 100-199.
+    ///   If you have more than 100 accounts, just request another 100
+    ///   accounts with startIndex=100. This will fetch accounts 100-199.
     /// * `start_index` - The first account index to be returned in the results
     /// * `search_text` - Search text for this account
     /// * `sort_property` - A sorting option for this account list, based on
-    ///                     one of its properties
+    ///   one of its properties
     /// * `sort_order` - Sort order for the sort property
     #[must_use]
     pub fn new(
@@ -165,9 +209,14 @@ impl Req {
         sort_property: Option<SortProperty>,
         sort_order: Option<SortOrder>,
     ) -> Self {
+        let page_size = match size {
+            Some(s) if s > 0 && s <= MAX_PAGE_SIZE => s,
+            _ => MAX_PAGE_SIZE,
+        };
+
         let size = match size {
             Some(s) => {
-                if s > 0 && s <= 100 {
+                if s > 0 && s <= MAX_PAGE_SIZE {
                     format!("size={s}&")
                 } else {
                     String::new()
@@ -176,6 +225,8 @@ impl Req {
             None => String::new(),
         };
 
+        let first_index = start_index.unwrap_or(0);
+
         let start_index = match start_index {
             Some(si) => format!("startIndex={si}&"),
             None => String::new(),
@@ -202,8 +253,37 @@ impl Req {
             search_text,
             sort_property,
             sort_order,
+            page_size,
+            first_index,
         }
     }
+
+    /// Iterate over every account matching this request's search, sort filters,
+    /// fetching further pages from SolarEdge as needed.
+    ///
+    /// Pages hold `size` accounts (100 if not given) and iteration begins at
+    /// `start_index` (0 if not given).
+    ///
+    /// # Arguments
+    ///
+    /// * `solaredge` - SolarEdge credentials to use for sending
+    ///
+    /// # Returns
+    /// An iterator yielding each account, or the error that stopped the iteration.
+    #[must_use]
+    pub fn iter<'a>(&self, solaredge: &'a SolaredgeCredentials) -> Paginated<'a, AccountDetails> {
+        let mut req = self.clone();
+        Paginated::new(
+            self.first_index,
+            self.page_size,
+            move |start_index, size| {
+                req.start_index = format!("startIndex={start_index}&");
+                req.size = format!("size={size}&");
+                let resp = req.send(solaredge)?;
+                Ok((resp.accounts.count, resp.accounts.list.e))
+            },
+        )
+    }
 }
 
 impl SendReq<Resp> for Req {
@@ -226,6 +306,38 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "accounts": {
+                "count": 1,
+                "list": [{
+                    "id": 12,
+                    "name": "Acme Solar",
+                    "location": {
+                        "country": "United States",
+                        "state": "Texas",
+                        "city": "Austin",
+                        "address": "1 Main St",
+                        "address2": "",
+                        "zip": "78701"
+                    },
+                    "companyWebSite": "https://example.com",
+                    "contactPerson": "Pat Doe",
+                    "email": "pat@example.com",
+                    "phoneNumber": "555-0100",
+                    "faxNumber": "",
+                    "notes": "",
+                    "parentId": 0,
+                    "uris": {"SITE_IMAGE": "/site/1/siteImage/image.jpg"}
+                }]
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
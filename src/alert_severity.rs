@@ -0,0 +1,79 @@
+//! Module for the alert severity of a site reported by the SolarEdge server monitoring API.
+
+use serde::Deserialize;
+
+/// Severity of the open alerts at a site.
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize),
+    serde(into = "String")
+)]
+#[serde(from = "String")]
+pub enum AlertSeverity {
+    /// Low impact alert
+    Low,
+
+    /// Medium impact alert
+    Medium,
+
+    /// High impact alert
+    High,
+
+    /// A severity not listed in the API documentation.
+    Unknown(String),
+}
+
+impl From<String> for AlertSeverity {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "LOW" => AlertSeverity::Low,
+            "MEDIUM" => AlertSeverity::Medium,
+            "HIGH" => AlertSeverity::High,
+            _ => AlertSeverity::Unknown(s),
+        }
+    }
+}
+
+impl From<AlertSeverity> for String {
+    fn from(a: AlertSeverity) -> Self {
+        a.to_string()
+    }
+}
+
+impl std::fmt::Display for AlertSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AlertSeverity::Low => write!(f, "LOW"),
+            AlertSeverity::Medium => write!(f, "MEDIUM"),
+            AlertSeverity::High => write!(f, "HIGH"),
+            AlertSeverity::Unknown(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_normal;
+
+    #[test]
+    fn alert_severity_fmt_unit_test() {
+        let t = AlertSeverity::High;
+        assert_eq!(format!("{t}"), "HIGH");
+    }
+
+    #[test]
+    fn alert_severity_from_unit_test() {
+        assert_eq!(AlertSeverity::from("LOW".to_string()), AlertSeverity::Low);
+        assert_eq!(
+            AlertSeverity::from("CRITICAL".to_string()),
+            AlertSeverity::Unknown("CRITICAL".to_string())
+        );
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<AlertSeverity>();
+    }
+}
@@ -0,0 +1,287 @@
+//! Module for summing a site's energy over billing periods, e.g. between the
+//! read dates of the utility meter.
+//!
+//! For each period the production, consumption, self-consumption, feed-in and
+//! purchased energy are totalled from daily energy details. Fetching splits the
+//! days covered by the periods into as few requests as the API allows, skipping
+//! the days between periods.
+
+use crate::{DataEndpoint, Error, MeterType, Series, SolaredgeCredentials, TimeUnit};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+/// A billing period of whole days.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BillingPeriod {
+    /// First day of the period
+    pub start: NaiveDate,
+
+    /// Last day of the period, inclusive
+    pub end: NaiveDate,
+}
+
+/// Energy of a site over one billing period.
+///
+/// A meter the site does not have (or that reported nothing in the fetched range)
+/// is None. Days the server reported no value for are counted as zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeriodEnergy {
+    /// The billing period
+    pub period: BillingPeriod,
+
+    /// Solar energy produced
+    pub production: Option<f64>,
+
+    /// Total energy consumed (solar + grid)
+    pub consumption: Option<f64>,
+
+    /// Solar energy consumed on site
+    pub self_consumption: Option<f64>,
+
+    /// Solar energy exported to the grid
+    pub feed_in: Option<f64>,
+
+    /// Energy purchased from the grid
+    pub purchased: Option<f64>,
+
+    /// Unit of the energy values, e.g. "Wh"
+    pub unit: String,
+}
+
+impl BillingPeriod {
+    /// Create a billing period.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - first day of the period
+    /// * `end` - last day of the period, inclusive
+    #[must_use]
+    pub fn new(start: NaiveDate, end: NaiveDate) -> Self {
+        BillingPeriod { start, end }
+    }
+
+    /// Billing periods between consecutive meter read dates. Each period starts on
+    /// a read date and ends the day before the next one.
+    ///
+    /// # Arguments
+    ///
+    /// * `read_dates` - the meter read dates, in any order
+    ///
+    /// # Returns
+    /// One period less than the number of distinct read dates, in order.
+    #[must_use]
+    pub fn from_read_dates(read_dates: &[NaiveDate]) -> Vec<Self> {
+        let mut dates = read_dates.to_vec();
+        dates.sort_unstable();
+        dates.dedup();
+
+        dates
+            .windows(2)
+            .filter_map(|d| Some(BillingPeriod::new(d[0], d[1].pred_opt()?)))
+            .collect()
+    }
+
+    /// Whether `t` falls on one of the days of the period.
+    #[must_use]
+    pub fn contains(&self, t: NaiveDateTime) -> bool {
+        (self.start..=self.end).contains(&t.date())
+    }
+}
+
+impl PeriodEnergy {
+    /// Total the values of `series` that fall within `period`.
+    ///
+    /// # Arguments
+    ///
+    /// * `period` - the billing period
+    /// * `series` - energy details series, one per meter type. Several series of
+    ///   the same meter type (e.g. from consecutive requests) are added up.
+    #[must_use]
+    pub fn from_series(period: BillingPeriod, series: &[Series]) -> Self {
+        let total = |meter_type: MeterType| {
+            let name = meter_type.to_string();
+            series
+                .iter()
+                .filter(|s| s.name == name)
+                .map(|s| {
+                    s.samples
+                        .iter()
+                        .filter(|v| period.contains(v.timestamp))
+                        .filter_map(|v| v.value)
+                        .sum::<f64>()
+                })
+                .reduce(|a, b| a + b)
+        };
+
+        PeriodEnergy {
+            period,
+            production: total(MeterType::Production),
+            consumption: total(MeterType::Consumption),
+            self_consumption: total(MeterType::SelfConsumption),
+            feed_in: total(MeterType::FeedIn),
+            purchased: total(MeterType::Purchased),
+            unit: series.first().map(|s| s.unit.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+/// Total energy details over billing periods.
+///
+/// # Arguments
+///
+/// * `series` - daily (or finer) energy details series covering the periods
+/// * `periods` - the billing periods
+///
+/// # Returns
+/// The energy of each period, in the order of `periods`.
+#[must_use]
+pub fn period_energy(series: &[Series], periods: &[BillingPeriod]) -> Vec<PeriodEnergy> {
+    periods
+        .iter()
+        .map(|p| PeriodEnergy::from_series(*p, series))
+        .collect()
+}
+
+/// Fetch daily energy details from SolarEdge and total them over billing periods.
+///
+/// Periods that overlap or follow each other are fetched as one range, split into
+/// windows of at most a year. Days between periods are not fetched.
+///
+/// # Arguments
+///
+/// * `solaredge` - SolarEdge credentials of the site
+/// * `periods` - the billing periods
+///
+/// # Returns
+/// The energy of each period, in the order of `periods`.
+///
+/// # Errors
+/// Returns an error if a request fails or a response date cannot be parsed.
+pub fn fetch_period_energy(
+    solaredge: &SolaredgeCredentials,
+    periods: &[BillingPeriod],
+) -> Result<Vec<PeriodEnergy>, Error> {
+    let mut series = Vec::new();
+    for (start, end) in day_ranges(periods) {
+        series.extend(DataEndpoint::EnergyDetails.fetch_range(
+            solaredge,
+            &TimeUnit::Day,
+            start.and_time(NaiveTime::MIN),
+            end.and_hms_opt(23, 59, 59).unwrap_or_default(),
+        )?);
+    }
+
+    Ok(period_energy(&series, periods))
+}
+
+/// The days covered by `periods`, as ranges of first and last day in date order.
+/// Periods that overlap or follow each other are joined into one range.
+fn day_ranges(periods: &[BillingPeriod]) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut days = periods
+        .iter()
+        .map(|p| (p.start, p.start.max(p.end)))
+        .collect::<Vec<_>>();
+    days.sort_unstable();
+
+    let mut ranges: Vec<(NaiveDate, NaiveDate)> = Vec::new();
+    for (start, end) in days {
+        match ranges.last_mut() {
+            Some((_, last)) if start <= last.succ_opt().unwrap_or(*last) => {
+                *last = end.max(*last);
+            }
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, SiteEnergyDetailedResp, TimeSeries};
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, crate::URL_DATE_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn from_read_dates_unit_test() {
+        let periods = BillingPeriod::from_read_dates(&[
+            day("2022-03-14"),
+            day("2022-01-12"),
+            day("2022-02-11"),
+            day("2022-02-11"),
+        ]);
+        assert_eq!(
+            periods,
+            vec![
+                BillingPeriod::new(day("2022-01-12"), day("2022-02-10")),
+                BillingPeriod::new(day("2022-02-11"), day("2022-03-13")),
+            ]
+        );
+        assert!(BillingPeriod::from_read_dates(&[day("2022-01-12")]).is_empty());
+    }
+
+    #[test]
+    fn period_energy_unit_test() {
+        let resp: SiteEnergyDetailedResp = serde_json::from_str(
+            r#"{"energyDetails": {"timeUnit": "DAY", "unit": "Wh", "meters": [
+                {"type": "Production", "values": [
+                    {"date": "2022-01-30 00:00:00", "value": 10000},
+                    {"date": "2022-01-31 00:00:00", "value": 12000},
+                    {"date": "2022-02-01 00:00:00", "value": 8000},
+                    {"date": "2022-02-02 00:00:00"}]},
+                {"type": "SelfConsumption", "values": [
+                    {"date": "2022-01-30 00:00:00", "value": 4000},
+                    {"date": "2022-01-31 00:00:00", "value": 5000.5},
+                    {"date": "2022-02-01 00:00:00", "value": 3000}]},
+                {"type": "FeedIn", "values": [
+                    {"date": "2022-01-31 00:00:00", "value": 6999.5}]}
+            ]}}"#,
+        )
+        .unwrap();
+        let series = resp.energy_details.time_series().unwrap();
+
+        let energy = period_energy(
+            &series,
+            &[
+                BillingPeriod::new(day("2022-01-30"), day("2022-01-31")),
+                BillingPeriod::new(day("2022-02-01"), day("2022-02-28")),
+            ],
+        );
+
+        assert_eq!(energy.len(), 2);
+        assert_eq!(energy[0].production, Some(22000.0));
+        assert_eq!(energy[0].self_consumption, Some(9000.5));
+        assert_eq!(energy[0].feed_in, Some(6999.5));
+        assert_eq!(energy[0].consumption, None);
+        assert_eq!(energy[0].purchased, None);
+        assert_eq!(energy[0].unit, "Wh");
+        assert_eq!(energy[1].production, Some(8000.0));
+        assert_eq!(energy[1].feed_in, Some(0.0));
+    }
+
+    #[test]
+    fn day_ranges_unit_test() {
+        let ranges = day_ranges(&[
+            BillingPeriod::new(day("2022-06-01"), day("2022-06-30")),
+            BillingPeriod::new(day("2022-01-01"), day("2022-01-31")),
+            BillingPeriod::new(day("2022-02-01"), day("2022-02-28")),
+            BillingPeriod::new(day("2022-01-15"), day("2022-01-20")),
+            BillingPeriod::new(day("2022-06-20"), day("2022-07-10")),
+        ]);
+        assert_eq!(
+            ranges,
+            vec![
+                (day("2022-01-01"), day("2022-02-28")),
+                (day("2022-06-01"), day("2022-07-10")),
+            ]
+        );
+        assert!(day_ranges(&[]).is_empty());
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<BillingPeriod>();
+        is_normal::<PeriodEnergy>();
+    }
+}
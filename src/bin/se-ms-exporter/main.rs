@@ -0,0 +1,218 @@
+//! se-ms-exporter - serve the current state of a SolarEdge site as Prometheus metrics.
+//!
+//! The site's power flow and overview (and, with `--storage`, its battery
+//! telemetry) are polled in the background and served at `/metrics`. The time
+//! between polls is derived from the daily request budget, so that the exporter
+//! alone never sends more requests per day than the budget allows.
+
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::doc_markdown)]
+
+mod metrics;
+
+use chrono::{Local, TimeDelta};
+use clap::Parser;
+use metrics::Snapshot;
+use se_ms_api::{
+    poll_interval, SendReq, SiteOverviewReq, SitePowerFlowReq, SiteStorageDataReq,
+    SolaredgeCredentials, DAILY_REQUEST_LIMIT,
+};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Library dependencies that this binary does not use directly.
+use lazy_static as _;
+use reqwest as _;
+use serde as _;
+
+/// Serve the current state of a SolarEdge site as Prometheus metrics
+#[derive(Debug, Parser)]
+#[command(name = "se-ms-exporter", version)]
+struct Cli {
+    /// Site id to poll
+    #[arg(long, env = "SE_MS_SITE_ID")]
+    site_id: String,
+
+    /// Site or account API key
+    #[arg(long, env = "SE_MS_API_KEY", hide_env_values = true)]
+    api_key: String,
+
+    /// Address to serve /metrics on
+    #[arg(long, default_value = "127.0.0.1:9721")]
+    listen: String,
+
+    /// Requests per day the exporter may send. Lower it to leave room for other
+    /// users of the same API key.
+    #[arg(long, default_value_t = DAILY_REQUEST_LIMIT)]
+    daily_budget: u32,
+
+    /// Also poll the storage data of the site's batteries
+    #[arg(long)]
+    storage: bool,
+}
+
+/// Send one round of requests and store the responses in `snapshot`.
+fn poll(
+    cred: &SolaredgeCredentials,
+    storage: bool,
+    interval: Duration,
+    snapshot: &Mutex<Snapshot>,
+) -> Result<(), se_ms_api::Error> {
+    let power_flow = SitePowerFlowReq::new().send(cred)?.site_current_power_flow;
+    let overview = SiteOverviewReq::new().send(cred)?.overview;
+
+    // Telemetry is reported every few minutes, so look back at least 15 minutes
+    // to find the latest one.
+    let storage_data = if storage {
+        let end = Local::now().naive_local();
+        let back = TimeDelta::from_std(interval)
+            .unwrap_or(TimeDelta::MAX)
+            .max(TimeDelta::minutes(15));
+        let req = SiteStorageDataReq::new(end - back, end, None);
+        Some(req.send(cred)?.storage_data)
+    } else {
+        None
+    };
+
+    let mut snapshot = snapshot
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    snapshot.power_flow = Some(power_flow);
+    snapshot.overview = Some(overview);
+    snapshot.storage = storage_data;
+    snapshot.updated = Some(Instant::now());
+    Ok(())
+}
+
+/// Answer one HTTP request: the metrics at `/metrics`, 404 for anything else.
+fn serve(mut stream: TcpStream, body: impl FnOnce() -> String) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Skip the headers.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            ("200 OK", "text/plain; version=0.0.4; charset=utf-8", body())
+        }
+        _ => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn run(cli: &Cli) -> Result<(), String> {
+    let cred = SolaredgeCredentials::new(&cli.site_id, &cli.api_key);
+    let requests_per_poll = if cli.storage { 3 } else { 2 };
+    let interval = poll_interval(cli.daily_budget, requests_per_poll).ok_or(format!(
+        "A daily budget of {} requests is too small for {requests_per_poll} requests per poll",
+        cli.daily_budget
+    ))?;
+
+    let listener = TcpListener::bind(&cli.listen)
+        .map_err(|e| format!("Cannot listen on {}: {e}", cli.listen))?;
+    eprintln!(
+        "se-ms-exporter: serving http://{}/metrics, polling every {}s",
+        cli.listen,
+        interval.as_secs()
+    );
+
+    let snapshot = Arc::new(Mutex::new(Snapshot::default()));
+    let storage = cli.storage;
+    let poller = Arc::clone(&snapshot);
+    thread::spawn(move || loop {
+        if let Err(e) = poll(&cred, storage, interval, &poller) {
+            eprintln!("se-ms-exporter: poll failed: {e}");
+            poller
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .errors += 1;
+        }
+        thread::sleep(interval);
+    });
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("se-ms-exporter: connection failed: {e}");
+                continue;
+            }
+        };
+        let body = || {
+            snapshot
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .render(&cli.site_id, interval, Instant::now())
+        };
+        if let Err(e) = serve(stream, body) {
+            eprintln!("se-ms-exporter: request failed: {e}");
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("se-ms-exporter: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use std::io::Read;
+
+    #[test]
+    fn cli_definition_unit_test() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn serve_unit_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        for (path, status, body) in [
+            ("/metrics", "200 OK", "metrics\n"),
+            ("/", "404 Not Found", "Not found\n"),
+        ] {
+            let client = thread::spawn(move || {
+                let mut stream = TcpStream::connect(addr).unwrap();
+                write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).unwrap();
+                response
+            });
+            let (stream, _) = listener.accept().unwrap();
+            serve(stream, || "metrics\n".to_string()).unwrap();
+
+            let response = client.join().unwrap();
+            assert!(response.starts_with(&format!("HTTP/1.1 {status}\r\n")));
+            assert!(response.ends_with(&format!("\r\n\r\n{body}")));
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! The values collected by the exporter and their rendering in the Prometheus
+//! text exposition format.
+
+use se_ms_api::{Overview, PowerFlowGraph, SiteCurrentPowerFlow, StorageData, StorageTelemetry};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// Latest responses received from SolarEdge.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    pub power_flow: Option<SiteCurrentPowerFlow>,
+    pub overview: Option<Overview>,
+    pub storage: Option<StorageData>,
+
+    /// When the last poll succeeded
+    pub updated: Option<Instant>,
+
+    /// Number of polls that failed since the exporter started
+    pub errors: u64,
+}
+
+/// Writes metric families, each with a single HELP and TYPE line.
+struct Exposition<'a> {
+    out: String,
+    site_id: &'a str,
+}
+
+impl Exposition<'_> {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {name} {help}");
+        let _ = writeln!(self.out, "# TYPE {name} {kind}");
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let _ = write!(self.out, "{name}{{site_id=\"{}\"", escape(self.site_id));
+        for (label, v) in labels {
+            let _ = write!(self.out, ",{label}=\"{}\"", escape(v));
+        }
+        let _ = writeln!(self.out, "}} {value}");
+    }
+
+    /// Latest telemetry of each battery.
+    fn batteries(&mut self, storage: &StorageData) {
+        let latest = storage
+            .batteries
+            .e
+            .iter()
+            .filter_map(|b| Some((b.serial_number.as_str(), b.telemetries.last()?)))
+            .collect::<Vec<_>>();
+
+        let fields: [BatteryField; 4] = [
+            (
+                "solaredge_battery_power_watts",
+                "Latest power of each battery, positive when charging.",
+                |t| t.power.map(f64::from),
+            ),
+            (
+                "solaredge_battery_state_of_charge_percent",
+                "Latest state of charge of each battery.",
+                |t| t.battery_percentage_state.map(f64::from),
+            ),
+            (
+                "solaredge_battery_charged_watt_hours",
+                "Lifetime energy charged into each battery.",
+                |t| t.life_time_energy_charged,
+            ),
+            (
+                "solaredge_battery_discharged_watt_hours",
+                "Lifetime energy discharged from each battery.",
+                |t| t.life_time_energy_discharged,
+            ),
+        ];
+        for (name, help, value) in fields {
+            self.family(name, "gauge", help);
+            for (serial, t) in &latest {
+                if let Some(v) = value(t) {
+                    self.sample(name, &[("serial", serial)], v);
+                }
+            }
+        }
+    }
+}
+
+/// Name, help and accessor of a battery metric.
+type BatteryField = (
+    &'static str,
+    &'static str,
+    fn(&StorageTelemetry) -> Option<f64>,
+);
+
+/// Escape a label value.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Current power of each element in watts: grid power is positive when importing
+/// and storage power is positive when discharging.
+fn element_power(flow: &SiteCurrentPowerFlow) -> Vec<(&'static str, f64)> {
+    let graph = PowerFlowGraph::new(flow);
+
+    let mut power = vec![("load", graph.load), ("grid", graph.grid)];
+    if let Some(pv) = graph.pv {
+        power.push(("pv", pv));
+    }
+    if let Some(storage) = graph.storage {
+        power.push(("storage", storage));
+    }
+    power
+}
+
+impl Snapshot {
+    /// Render the snapshot as Prometheus metrics.
+    ///
+    /// # Arguments
+    ///
+    /// * `site_id` - value of the site_id label of every metric
+    /// * `interval` - time between polls
+    /// * `now` - the current time, for the age of the last update
+    pub fn render(&self, site_id: &str, interval: Duration, now: Instant) -> String {
+        let mut e = Exposition {
+            out: String::new(),
+            site_id,
+        };
+
+        if let Some(flow) = &self.power_flow {
+            e.family(
+                "solaredge_power_watts",
+                "gauge",
+                "Current power of each site element. Grid power is positive when \
+                 importing, storage power is positive when discharging.",
+            );
+            for (element, watts) in element_power(flow) {
+                e.sample("solaredge_power_watts", &[("element", element)], watts);
+            }
+
+            if let Some(level) = flow.storage.as_ref().and_then(|s| s.charge_level) {
+                e.family(
+                    "solaredge_battery_charge_level_percent",
+                    "gauge",
+                    "Accumulated state of charge of all batteries.",
+                );
+                e.sample(
+                    "solaredge_battery_charge_level_percent",
+                    &[],
+                    f64::from(level),
+                );
+            }
+        }
+
+        if let Some(overview) = &self.overview {
+            e.family(
+                "solaredge_energy_watt_hours",
+                "gauge",
+                "Energy produced over the lifetime, the current year, month and day.",
+            );
+            for (period, data) in [
+                ("lifetime", &overview.life_time_data),
+                ("year", &overview.last_year_data),
+                ("month", &overview.last_month_data),
+                ("day", &overview.last_day_data),
+            ] {
+                e.sample(
+                    "solaredge_energy_watt_hours",
+                    &[("period", period)],
+                    data.energy,
+                );
+            }
+        }
+
+        if let Some(storage) = &self.storage {
+            e.batteries(storage);
+        }
+
+        if let Some(updated) = self.updated {
+            e.family(
+                "solaredge_last_update_age_seconds",
+                "gauge",
+                "Seconds since the last successful poll.",
+            );
+            e.sample(
+                "solaredge_last_update_age_seconds",
+                &[],
+                now.duration_since(updated).as_secs_f64(),
+            );
+        }
+
+        e.family(
+            "solaredge_poll_interval_seconds",
+            "gauge",
+            "Seconds between polls, derived from the daily request budget.",
+        );
+        e.sample(
+            "solaredge_poll_interval_seconds",
+            &[],
+            interval.as_secs_f64(),
+        );
+
+        e.family(
+            "solaredge_poll_errors_total",
+            "counter",
+            "Number of polls that failed.",
+        );
+        #[allow(clippy::cast_precision_loss)]
+        e.sample("solaredge_poll_errors_total", &[], self.errors as f64);
+
+        e.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use se_ms_api::{SiteOverviewResp, SitePowerFlowResp, SiteStorageDataResp};
+
+    fn snapshot() -> Snapshot {
+        let flow: SitePowerFlowResp = serde_json::from_str(
+            r#"{"siteCurrentPowerFlow": {"updateRefreshRate": 3, "unit": "kW",
+                "connections": [{"from": "PV", "to": "Load"}, {"from": "LOAD", "to": "Grid"},
+                    {"from": "PV", "to": "Storage"}],
+                "GRID": {"status": "Active", "currentPower": 1.5},
+                "LOAD": {"status": "Active", "currentPower": 2.0},
+                "PV": {"status": "Active", "currentPower": 4.5},
+                "STORAGE": {"status": "Charging", "currentPower": 1.0, "chargeLevel": 61,
+                    "critical": false}}}"#,
+        )
+        .unwrap();
+        let overview: SiteOverviewResp = serde_json::from_str(
+            r#"{"overview": {"lastUpdateTime": "2022-01-01 12:00:00",
+                "lifeTimeData": {"energy": 761985.75}, "lastYearData": {"energy": 361332.0},
+                "lastMonthData": {"energy": 78999.0}, "lastDayData": {"energy": 1500.0},
+                "currentPower": {"power": 4500.0}, "measuredBy": "INVERTER"}}"#,
+        )
+        .unwrap();
+        let storage: SiteStorageDataResp = serde_json::from_str(
+            r#"{"storageData": {"batteryCount": 1, "batteries": [{
+                "nameplate": 9800, "serialNumber": "BAT-1", "modelNumber": "M",
+                "telemetryCount": 2,
+                "telemetries": [
+                    {"timeStamp": "2022-01-01 11:55:00", "power": 900},
+                    {"timeStamp": "2022-01-01 12:00:00", "power": 1000,
+                        "batteryPercentageState": 61.5}]
+            }]}}"#,
+        )
+        .unwrap();
+
+        Snapshot {
+            power_flow: Some(flow.site_current_power_flow),
+            overview: Some(overview.overview),
+            storage: Some(storage.storage_data),
+            updated: None,
+            errors: 2,
+        }
+    }
+
+    #[test]
+    fn render_unit_test() {
+        let now = Instant::now();
+        let mut snapshot = snapshot();
+        snapshot.updated = Some(now);
+        let text = snapshot.render("1234", Duration::from_secs(576), now);
+
+        for line in [
+            "# TYPE solaredge_power_watts gauge",
+            r#"solaredge_power_watts{site_id="1234",element="pv"} 4500"#,
+            r#"solaredge_power_watts{site_id="1234",element="load"} 2000"#,
+            r#"solaredge_power_watts{site_id="1234",element="grid"} -1500"#,
+            r#"solaredge_power_watts{site_id="1234",element="storage"} -1000"#,
+            r#"solaredge_battery_charge_level_percent{site_id="1234"} 61"#,
+            r#"solaredge_energy_watt_hours{site_id="1234",period="lifetime"} 761985.75"#,
+            r#"solaredge_energy_watt_hours{site_id="1234",period="day"} 1500"#,
+            r#"solaredge_battery_power_watts{site_id="1234",serial="BAT-1"} 1000"#,
+            r#"solaredge_battery_state_of_charge_percent{site_id="1234",serial="BAT-1"} 61.5"#,
+            r#"solaredge_last_update_age_seconds{site_id="1234"} 0"#,
+            r#"solaredge_poll_interval_seconds{site_id="1234"} 576"#,
+            "# TYPE solaredge_poll_errors_total counter",
+            r#"solaredge_poll_errors_total{site_id="1234"} 2"#,
+        ] {
+            assert!(text.lines().any(|l| l == line), "{line} missing in\n{text}");
+        }
+        assert!(!text.contains("solaredge_battery_charged_watt_hours{"));
+    }
+
+    #[test]
+    fn render_empty_unit_test() {
+        let text = Snapshot::default().render("1234", Duration::from_secs(90), Instant::now());
+        assert!(!text.contains("solaredge_power_watts"));
+        assert!(!text.contains("solaredge_last_update_age_seconds"));
+        assert!(text.contains("solaredge_poll_errors_total{site_id=\"1234\"} 0\n"));
+    }
+
+    #[test]
+    fn escape_unit_test() {
+        assert_eq!(escape(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape("a\nb"), "a\\nb");
+    }
+}
@@ -0,0 +1,280 @@
+//! MQTT topics and payloads of the bridge, including the Home Assistant discovery
+//! config of each sensor.
+//!
+//! All values of a poll are published together as one JSON object on the state
+//! topic. Each Home Assistant sensor picks its value out of that object with a
+//! value template.
+
+use se_ms_api::{Overview, PowerFlowGraph, SiteCurrentPowerFlow};
+use serde_json::{json, Value};
+
+/// A Home Assistant entity made from one key of the state payload.
+#[derive(Debug, PartialEq)]
+pub struct Sensor {
+    /// Key in the state payload
+    pub key: &'static str,
+
+    /// Name shown in Home Assistant
+    pub name: &'static str,
+
+    /// Home Assistant component, sensor or binary_sensor
+    pub component: &'static str,
+
+    pub device_class: &'static str,
+    pub state_class: Option<&'static str>,
+    pub unit: Option<&'static str>,
+}
+
+const fn power(key: &'static str, name: &'static str) -> Sensor {
+    Sensor {
+        key,
+        name,
+        component: "sensor",
+        device_class: "power",
+        state_class: Some("measurement"),
+        unit: Some("W"),
+    }
+}
+
+const fn energy(key: &'static str, name: &'static str) -> Sensor {
+    Sensor {
+        key,
+        name,
+        component: "sensor",
+        device_class: "energy",
+        state_class: Some("total_increasing"),
+        unit: Some("Wh"),
+    }
+}
+
+/// Sensors every site has.
+const SITE_SENSORS: [Sensor; 6] = [
+    power("load_power", "Load power"),
+    power("grid_power", "Grid power"),
+    energy("lifetime_energy", "Lifetime energy"),
+    energy("year_energy", "Energy this year"),
+    energy("month_energy", "Energy this month"),
+    energy("day_energy", "Energy today"),
+];
+
+/// Sensors of sites with a PV array.
+const PV_SENSORS: [Sensor; 1] = [power("pv_power", "PV power")];
+
+/// Sensors of sites with storage.
+const STORAGE_SENSORS: [Sensor; 3] = [
+    power("battery_power", "Battery power"),
+    Sensor {
+        key: "battery_level",
+        name: "Battery level",
+        component: "sensor",
+        device_class: "battery",
+        state_class: Some("measurement"),
+        unit: Some("%"),
+    },
+    Sensor {
+        key: "battery_critical",
+        name: "Battery critical",
+        component: "binary_sensor",
+        device_class: "battery",
+        state_class: None,
+        unit: None,
+    },
+];
+
+/// The sensors of a site, depending on which elements its power flow has.
+pub fn sensors(flow: &SiteCurrentPowerFlow) -> Vec<&'static Sensor> {
+    let mut sensors: Vec<&Sensor> = SITE_SENSORS.iter().collect();
+    if flow.pv.is_some() {
+        sensors.extend(&PV_SENSORS);
+    }
+    if flow.storage.is_some() {
+        sensors.extend(&STORAGE_SENSORS);
+    }
+    sensors
+}
+
+/// Topics the bridge publishes to for one site.
+#[derive(Debug)]
+pub struct Topics {
+    pub prefix: String,
+    pub discovery_prefix: String,
+    pub site_id: String,
+}
+
+impl Topics {
+    /// Topic of the JSON object holding the latest values.
+    pub fn state(&self) -> String {
+        format!("{}/{}/state", self.prefix, self.site_id)
+    }
+
+    /// Topic holding "online" while the bridge is polling, "offline" otherwise.
+    pub fn availability(&self) -> String {
+        format!("{}/{}/availability", self.prefix, self.site_id)
+    }
+
+    fn object_id(&self, sensor: &Sensor) -> String {
+        format!("solaredge_{}_{}", self.site_id, sensor.key)
+    }
+
+    /// Discovery topic and config payload of a sensor.
+    pub fn discovery(&self, sensor: &Sensor) -> (String, Value) {
+        let object_id = self.object_id(sensor);
+        let topic = format!(
+            "{}/{}/{object_id}/config",
+            self.discovery_prefix, sensor.component
+        );
+
+        let mut config = json!({
+            "name": sensor.name,
+            "unique_id": object_id,
+            "object_id": object_id,
+            "state_topic": self.state(),
+            "availability_topic": self.availability(),
+            "device_class": sensor.device_class,
+            "device": {
+                "identifiers": [format!("solaredge_{}", self.site_id)],
+                "name": format!("SolarEdge {}", self.site_id),
+                "manufacturer": "SolarEdge",
+            },
+        });
+        if sensor.component == "binary_sensor" {
+            config["value_template"] =
+                format!("{{{{ 'ON' if value_json.{} else 'OFF' }}}}", sensor.key).into();
+        } else {
+            config["value_template"] = format!("{{{{ value_json.{} }}}}", sensor.key).into();
+        }
+        if let Some(state_class) = sensor.state_class {
+            config["state_class"] = state_class.into();
+        }
+        if let Some(unit) = sensor.unit {
+            config["unit_of_measurement"] = unit.into();
+        }
+
+        (topic, config)
+    }
+}
+
+/// The state payload: power in watts and energy in watt hours. Grid power is
+/// positive when importing and battery power is positive when discharging.
+pub fn state(flow: &SiteCurrentPowerFlow, overview: &Overview) -> Value {
+    let graph = PowerFlowGraph::new(flow);
+
+    let mut state = json!({
+        "load_power": graph.load,
+        "grid_power": graph.grid,
+        "lifetime_energy": overview.life_time_data.energy,
+        "year_energy": overview.last_year_data.energy,
+        "month_energy": overview.last_month_data.energy,
+        "day_energy": overview.last_day_data.energy,
+        "last_update_time": overview.last_update_time,
+    });
+    if let Some(pv) = graph.pv {
+        state["pv_power"] = pv.into();
+    }
+    if let (Some(storage), Some(power)) = (&flow.storage, graph.storage) {
+        state["battery_power"] = power.into();
+        state["battery_level"] = storage.charge_level.into();
+        state["battery_critical"] = storage.critical.unwrap_or(false).into();
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use se_ms_api::{SiteOverviewResp, SitePowerFlowResp};
+
+    fn topics() -> Topics {
+        Topics {
+            prefix: "solaredge".to_string(),
+            discovery_prefix: "homeassistant".to_string(),
+            site_id: "1234".to_string(),
+        }
+    }
+
+    fn power_flow(storage: bool) -> SiteCurrentPowerFlow {
+        let storage = if storage {
+            r#", "STORAGE": {"status": "Active", "currentPower": 0.5, "chargeLevel": 42,
+                "critical": false}"#
+        } else {
+            ""
+        };
+        let resp: SitePowerFlowResp = serde_json::from_str(&format!(
+            r#"{{"siteCurrentPowerFlow": {{"updateRefreshRate": 3, "unit": "kW",
+                "connections": [{{"from": "PV", "to": "Load"}}, {{"from": "GRID", "to": "Load"}},
+                    {{"from": "PV", "to": "Storage"}}],
+                "GRID": {{"status": "Active", "currentPower": 1.25}},
+                "LOAD": {{"status": "Active", "currentPower": 3.0}},
+                "PV": {{"status": "Active", "currentPower": 2.25}}{storage}}}}}"#
+        ))
+        .unwrap();
+        resp.site_current_power_flow
+    }
+
+    #[test]
+    fn discovery_unit_test() {
+        let topics = topics();
+
+        let (topic, config) = topics.discovery(&SITE_SENSORS[2]);
+        assert_eq!(
+            topic,
+            "homeassistant/sensor/solaredge_1234_lifetime_energy/config"
+        );
+        assert_eq!(config["device_class"], "energy");
+        assert_eq!(config["state_class"], "total_increasing");
+        assert_eq!(config["unit_of_measurement"], "Wh");
+        assert_eq!(config["state_topic"], "solaredge/1234/state");
+        assert_eq!(config["availability_topic"], "solaredge/1234/availability");
+        assert_eq!(config["value_template"], "{{ value_json.lifetime_energy }}");
+        assert_eq!(config["device"]["identifiers"][0], "solaredge_1234");
+
+        let (topic, config) = topics.discovery(&STORAGE_SENSORS[2]);
+        assert_eq!(
+            topic,
+            "homeassistant/binary_sensor/solaredge_1234_battery_critical/config"
+        );
+        assert_eq!(
+            config["value_template"],
+            "{{ 'ON' if value_json.battery_critical else 'OFF' }}"
+        );
+        assert!(config.get("unit_of_measurement").is_none());
+        assert!(config.get("state_class").is_none());
+    }
+
+    #[test]
+    fn sensors_unit_test() {
+        let keys = |flow| sensors(&flow).iter().map(|s| s.key).collect::<Vec<_>>();
+        assert_eq!(keys(power_flow(false)).len(), 7);
+        assert!(keys(power_flow(true)).contains(&"battery_level"));
+        assert!(!keys(power_flow(false)).contains(&"battery_level"));
+    }
+
+    #[test]
+    fn state_unit_test() {
+        let overview: SiteOverviewResp = serde_json::from_str(
+            r#"{"overview": {"lastUpdateTime": "2022-01-01 12:00:00",
+                "lifeTimeData": {"energy": 761985.75}, "lastYearData": {"energy": 361332.0},
+                "lastMonthData": {"energy": 78999.0}, "lastDayData": {"energy": 1500.0},
+                "currentPower": {"power": 2250.0}, "measuredBy": "INVERTER"}}"#,
+        )
+        .unwrap();
+
+        let state = state(&power_flow(true), &overview.overview);
+        assert_eq!(
+            state,
+            json!({
+                "load_power": 3000.0,
+                "grid_power": 1250.0,
+                "pv_power": 2250.0,
+                "battery_power": -500.0,
+                "battery_level": 42,
+                "battery_critical": false,
+                "lifetime_energy": 761_985.75,
+                "year_energy": 361_332.0,
+                "month_energy": 78_999.0,
+                "day_energy": 1_500.0,
+                "last_update_time": "2022-01-01 12:00:00",
+            })
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! se-ms-mqtt - publish the current state of a SolarEdge site to an MQTT broker.
+//!
+//! The site's power flow and overview are polled and published as one JSON
+//! object, together with Home Assistant discovery configs so the values show up
+//! as sensors without any configuration in Home Assistant. As with the exporter,
+//! the time between polls is derived from the daily request budget.
+
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::doc_markdown)]
+
+mod home_assistant;
+
+use clap::Parser;
+use home_assistant::Topics;
+use rumqttc::{Client, LastWill, MqttOptions, QoS};
+use se_ms_api::{
+    poll_interval, SendReq, SiteOverviewReq, SitePowerFlowReq, SolaredgeCredentials,
+    DAILY_REQUEST_LIMIT,
+};
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+// Library dependencies that this binary does not use directly.
+use chrono as _;
+use lazy_static as _;
+use reqwest as _;
+use serde as _;
+
+/// Requests sent on every poll: power flow and overview.
+const REQUESTS_PER_POLL: u32 = 2;
+
+/// Publish the current state of a SolarEdge site to an MQTT broker
+#[derive(Debug, Parser)]
+#[command(name = "se-ms-mqtt", version)]
+struct Cli {
+    /// Site id to poll
+    #[arg(long, env = "SE_MS_SITE_ID")]
+    site_id: String,
+
+    /// Site or account API key
+    #[arg(long, env = "SE_MS_API_KEY", hide_env_values = true)]
+    api_key: String,
+
+    /// Host name of the MQTT broker
+    #[arg(long, default_value = "localhost")]
+    host: String,
+
+    /// Port of the MQTT broker
+    #[arg(long, default_value_t = 1883)]
+    port: u16,
+
+    /// User name for the MQTT broker
+    #[arg(long, env = "SE_MS_MQTT_USERNAME")]
+    username: Option<String>,
+
+    /// Password for the MQTT broker
+    #[arg(long, env = "SE_MS_MQTT_PASSWORD", hide_env_values = true)]
+    password: Option<String>,
+
+    /// Prefix of the state and availability topics
+    #[arg(long, default_value = "solaredge")]
+    topic_prefix: String,
+
+    /// Home Assistant discovery prefix
+    #[arg(long, default_value = "homeassistant")]
+    discovery_prefix: String,
+
+    /// Do not publish Home Assistant discovery configs
+    #[arg(long)]
+    no_discovery: bool,
+
+    /// Requests per day the bridge may send. Lower it to leave room for other
+    /// users of the same API key.
+    #[arg(long, default_value_t = DAILY_REQUEST_LIMIT)]
+    daily_budget: u32,
+}
+
+fn publish(client: &Client, topic: String, payload: impl Into<Vec<u8>>) -> Result<(), String> {
+    client
+        .publish(topic, QoS::AtLeastOnce, true, payload)
+        .map_err(|e| format!("Cannot publish: {e}"))
+}
+
+fn run(cli: &Cli) -> Result<(), String> {
+    let cred = SolaredgeCredentials::new(&cli.site_id, &cli.api_key);
+    let interval = poll_interval(cli.daily_budget, REQUESTS_PER_POLL).ok_or(format!(
+        "A daily budget of {} requests is too small for {REQUESTS_PER_POLL} requests per poll",
+        cli.daily_budget
+    ))?;
+    let topics = Topics {
+        prefix: cli.topic_prefix.clone(),
+        discovery_prefix: cli.discovery_prefix.clone(),
+        site_id: cli.site_id.clone(),
+    };
+
+    let mut options = MqttOptions::new(
+        format!("se-ms-mqtt-{}", cli.site_id),
+        cli.host.clone(),
+        cli.port,
+    );
+    options.set_keep_alive(Duration::from_mins(1));
+    options.set_last_will(LastWill::new(
+        topics.availability(),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some(username) = &cli.username {
+        options.set_credentials(username, cli.password.clone().unwrap_or_default());
+    }
+
+    // The connection has to be polled for anything to be sent. It reconnects by
+    // itself after an error.
+    let (client, mut connection) = Client::new(options, 64);
+    thread::spawn(move || {
+        for event in connection.iter() {
+            if let Err(e) = event {
+                eprintln!("se-ms-mqtt: MQTT connection failed: {e}");
+                thread::sleep(Duration::from_secs(5));
+            }
+        }
+    });
+
+    eprintln!(
+        "se-ms-mqtt: publishing to {}:{}, polling every {}s",
+        cli.host,
+        cli.port,
+        interval.as_secs()
+    );
+
+    let mut discovered = cli.no_discovery;
+    loop {
+        let resp = SitePowerFlowReq::new().send(&cred).and_then(|flow| {
+            let overview = SiteOverviewReq::new().send(&cred)?;
+            Ok((flow.site_current_power_flow, overview.overview))
+        });
+
+        match resp {
+            Ok((flow, overview)) => {
+                if !discovered {
+                    for sensor in home_assistant::sensors(&flow) {
+                        let (topic, config) = topics.discovery(sensor);
+                        publish(&client, topic, config.to_string())?;
+                    }
+                    discovered = true;
+                }
+                let state = home_assistant::state(&flow, &overview);
+                publish(&client, topics.state(), state.to_string())?;
+                publish(&client, topics.availability(), "online")?;
+            }
+            Err(e) => {
+                eprintln!("se-ms-mqtt: poll failed: {e}");
+                publish(&client, topics.availability(), "offline")?;
+            }
+        }
+
+        thread::sleep(interval);
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("se-ms-mqtt: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_definition_unit_test() {
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,84 @@
+//! Credentials for the se-ms tool, taken from the command line, the environment
+//! or a config file.
+//!
+//! The config file holds `key = value` lines with the keys `site_id` and
+//! `api_key`. Blank lines and lines starting with `#` are ignored.
+
+use std::fs;
+use std::path::Path;
+
+/// Credentials read from a config file.
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    pub site_id: Option<String>,
+    pub api_key: Option<String>,
+}
+
+impl Config {
+    /// Read the config file at `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read config file {}: {e}", path.display()))?;
+        Self::parse(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Parse the text of a config file.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Config::default();
+
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected key = value", n + 1));
+            };
+            let value = value.trim().trim_matches('"').to_string();
+
+            match key.trim() {
+                "site_id" => config.site_id = Some(value),
+                "api_key" => config.api_key = Some(value),
+                k => return Err(format!("line {}: unknown key {k}", n + 1)),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_parse_unit_test() {
+        let config = Config::parse(
+            "# SolarEdge\n\
+             site_id = 1234\n\
+             \n\
+             api_key=\"ABCD\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                site_id: Some("1234".to_string()),
+                api_key: Some("ABCD".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn config_parse_error_unit_test() {
+        assert_eq!(
+            Config::parse("site_id = 1\napi_secret = 2\n"),
+            Err("line 2: unknown key api_secret".to_string())
+        );
+        assert_eq!(
+            Config::parse("site_id\n"),
+            Err("line 1: expected key = value".to_string())
+        );
+    }
+}
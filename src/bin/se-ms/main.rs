@@ -0,0 +1,586 @@
+//! se-ms - query the SolarEdge Monitoring Server API from the command line.
+//!
+//! Every request type of the se_ms_api library has a subcommand. Credentials are
+//! taken from `--site-id`/`--api-key`, the `SE_MS_SITE_ID`/`SE_MS_API_KEY`
+//! environment variables or a config file, in that order of preference.
+//! Responses are printed as a table (default), JSON or CSV.
+
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(clippy::doc_markdown)]
+
+mod config;
+mod output;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use clap::{Args, Parser, Subcommand};
+use config::Config;
+use output::Format;
+use se_ms_api::{
+    AccountsListReq, CurrentVersionReq, MeterType, SendReq, SiteDataPeriodReq, SiteDetailsReq,
+    SiteEnergyDetailedReq, SiteEnergyReq, SiteEnvironmentalBenefitsReq, SiteEquipmentChangeLogReq,
+    SiteEquipmentListReq, SiteGetMetersDataReq, SiteGetSensorListReq, SiteInventoryReq,
+    SiteInverterTechnicalDataReq, SiteListReq, SiteListStatus, SiteOverviewReq,
+    SitePowerDetailedReq, SitePowerFlowReq, SitePowerReq, SiteStorageDataReq,
+    SiteTimeFrameEnergyReq, SolaredgeCredentials, SupportedVersionsReq, SystemUnits, TimeUnit,
+};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+// Library dependencies that this binary does not use directly.
+use lazy_static as _;
+use reqwest as _;
+use serde_json as _;
+
+/// Query the SolarEdge Monitoring Server API
+#[derive(Debug, Parser)]
+#[command(name = "se-ms", version)]
+struct Cli {
+    /// Site id to query
+    #[arg(long, global = true, env = "SE_MS_SITE_ID")]
+    site_id: Option<String>,
+
+    /// Site or account API key
+    #[arg(long, global = true, env = "SE_MS_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    /// Config file holding `site_id = ...` and `api_key = ...` lines
+    #[arg(long, global = true, env = "SE_MS_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Site details
+    Details,
+
+    /// Site overview: current power and lifetime, yearly, monthly and daily energy
+    Overview,
+
+    /// Start and end dates of the site's energy production
+    DataPeriod,
+
+    /// Site energy over a range of days
+    Energy {
+        #[command(flatten)]
+        range: DateRange,
+
+        #[command(flatten)]
+        time_unit: TimeUnitArg,
+    },
+
+    /// Total site energy produced over a range of days
+    TimeFrameEnergy {
+        #[command(flatten)]
+        range: DateRange,
+    },
+
+    /// Detailed site energy per meter
+    EnergyDetails {
+        #[command(flatten)]
+        range: TimeRange,
+
+        #[command(flatten)]
+        time_unit: TimeUnitArg,
+
+        #[command(flatten)]
+        meters: MetersArg,
+    },
+
+    /// Site power in 15 minute resolution
+    Power {
+        #[command(flatten)]
+        range: TimeRange,
+    },
+
+    /// Detailed site power per meter
+    PowerDetails {
+        #[command(flatten)]
+        range: TimeRange,
+
+        #[command(flatten)]
+        meters: MetersArg,
+    },
+
+    /// Current power flow between the site's elements
+    PowerFlow,
+
+    /// Storage (battery) telemetry
+    Storage {
+        #[command(flatten)]
+        range: TimeRange,
+
+        /// Battery serial numbers to include, comma separated (default: all)
+        #[arg(long, value_delimiter = ',')]
+        serials: Option<Vec<String>>,
+    },
+
+    /// Inventory of the site's equipment
+    Inventory,
+
+    /// List of the site's inverters, SMIs and reporters
+    Equipment,
+
+    /// Replacement history of a component
+    ChangeLog {
+        /// Serial number of the component
+        #[arg(long)]
+        serial: String,
+    },
+
+    /// Technical data of an inverter
+    InverterData {
+        /// Serial number of the inverter
+        #[arg(long)]
+        serial: String,
+
+        #[command(flatten)]
+        range: TimeRange,
+    },
+
+    /// Lifetime energy readings of the site's meters
+    Meters {
+        #[command(flatten)]
+        range: TimeRange,
+
+        #[command(flatten)]
+        time_unit: TimeUnitArg,
+
+        #[command(flatten)]
+        meters: MetersArg,
+    },
+
+    /// Sensors connected to the site's gateways
+    Sensors,
+
+    /// Environmental benefits of the site
+    EnvBenefits {
+        /// Unit system of the response, Metrics or Imperial (default: the user's setting)
+        #[arg(long, value_parser = parse_system_units)]
+        system_units: Option<SystemUnits>,
+    },
+
+    /// Sites of the account
+    Sites {
+        #[command(flatten)]
+        list: ListArgs,
+
+        /// Site statuses to include, comma separated: Active, Pending, Disabled or All
+        #[arg(long, value_delimiter = ',', value_parser = parse_status)]
+        status: Option<Vec<SiteListStatus>>,
+    },
+
+    /// Sub-accounts of the account
+    Accounts {
+        #[command(flatten)]
+        list: ListArgs,
+    },
+
+    /// Current and supported API versions
+    Version {
+        /// List every supported version instead of just the current one
+        #[arg(long)]
+        supported: bool,
+    },
+}
+
+/// Range of days
+#[derive(Debug, Args)]
+struct DateRange {
+    /// First day, YYYY-MM-DD (default: today)
+    #[arg(long)]
+    start: Option<NaiveDate>,
+
+    /// Last day, YYYY-MM-DD (default: today)
+    #[arg(long)]
+    end: Option<NaiveDate>,
+}
+
+/// Range of date and times
+#[derive(Debug, Args)]
+struct TimeRange {
+    /// Start, "YYYY-MM-DD HH:MM:SS" or YYYY-MM-DD for the start of the day (default: today)
+    #[arg(long, value_parser = parse_start)]
+    start: Option<NaiveDateTime>,
+
+    /// End, "YYYY-MM-DD HH:MM:SS" or YYYY-MM-DD for the end of the day (default: now)
+    #[arg(long, value_parser = parse_end)]
+    end: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Args)]
+struct TimeUnitArg {
+    /// Aggregation granularity: QUARTER_OF_AN_HOUR, HOUR, DAY, WEEK, MONTH or YEAR
+    #[arg(long, value_parser = parse_time_unit)]
+    time_unit: Option<TimeUnit>,
+}
+
+#[derive(Debug, Args)]
+struct MetersArg {
+    /// Meters to include, comma separated: Production, Consumption,
+    /// SelfConsumption, FeedIn or Purchased (default: all)
+    #[arg(long, value_delimiter = ',', value_parser = parse_meter)]
+    meters: Option<Vec<MeterType>>,
+}
+
+#[derive(Debug, Args)]
+struct ListArgs {
+    /// Maximum number of entries, 1 to 100
+    #[arg(long)]
+    size: Option<u16>,
+
+    /// Index of the first entry
+    #[arg(long)]
+    start_index: Option<u16>,
+
+    /// Only include entries matching this text
+    #[arg(long)]
+    search: Option<String>,
+
+    /// Fetch every page instead of a single one
+    #[arg(long)]
+    all: bool,
+}
+
+impl DateRange {
+    fn dates(&self) -> (NaiveDate, NaiveDate) {
+        let today = chrono::Local::now().date_naive();
+        (self.start.unwrap_or(today), self.end.unwrap_or(today))
+    }
+}
+
+impl TimeRange {
+    fn times(&self) -> (NaiveDateTime, NaiveDateTime) {
+        let now = chrono::Local::now().naive_local();
+        (
+            self.start
+                .unwrap_or_else(|| now.date().and_time(NaiveTime::MIN)),
+            self.end.unwrap_or(now),
+        )
+    }
+}
+
+fn parse_date_time(s: &str, time_of_day: NaiveTime) -> Result<NaiveDateTime, String> {
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .map(|d| d.and_time(time_of_day))
+        })
+        .ok_or_else(|| format!("expected \"YYYY-MM-DD HH:MM:SS\" or YYYY-MM-DD, got {s}"))
+}
+
+fn parse_start(s: &str) -> Result<NaiveDateTime, String> {
+    parse_date_time(s, NaiveTime::MIN)
+}
+
+fn parse_end(s: &str) -> Result<NaiveDateTime, String> {
+    let end_of_day = NaiveTime::from_hms_opt(23, 59, 59).unwrap_or(NaiveTime::MIN);
+    parse_date_time(s, end_of_day)
+}
+
+fn parse_time_unit(s: &str) -> Result<TimeUnit, String> {
+    match TimeUnit::from(s.to_uppercase()) {
+        TimeUnit::Unknown(u) => Err(format!("unknown time unit {u}")),
+        t => Ok(t),
+    }
+}
+
+fn parse_meter(s: &str) -> Result<MeterType, String> {
+    match MeterType::from(s.to_string()) {
+        MeterType::Unknown(m) => Err(format!("unknown meter {m}")),
+        m => Ok(m),
+    }
+}
+
+fn parse_system_units(s: &str) -> Result<SystemUnits, String> {
+    match SystemUnits::from(s.to_string()) {
+        SystemUnits::Unknown(u) => Err(format!("unknown unit system {u}")),
+        u => Ok(u),
+    }
+}
+
+fn parse_status(s: &str) -> Result<SiteListStatus, String> {
+    match s {
+        "Active" => Ok(SiteListStatus::Active),
+        "Pending" => Ok(SiteListStatus::Pending),
+        "Disabled" => Ok(SiteListStatus::Disabled),
+        "All" => Ok(SiteListStatus::All),
+        _ => Err(format!("unknown site status {s}")),
+    }
+}
+
+fn credentials(cli: &Cli) -> Result<SolaredgeCredentials, String> {
+    let config = match &cli.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+
+    let api_key = cli
+        .api_key
+        .clone()
+        .or(config.api_key)
+        .ok_or("No API key given, use --api-key, SE_MS_API_KEY or a config file")?;
+
+    // Account wide requests (sites, accounts, version) do not need a site id.
+    let site_id = cli.site_id.clone().or(config.site_id).unwrap_or_default();
+    if site_id.is_empty() && cli.command.needs_site_id() {
+        return Err("No site id given, use --site-id, SE_MS_SITE_ID or a config file".to_string());
+    }
+
+    Ok(SolaredgeCredentials::new(&site_id, &api_key))
+}
+
+impl Command {
+    fn needs_site_id(&self) -> bool {
+        !matches!(
+            self,
+            Command::Sites { .. } | Command::Accounts { .. } | Command::Version { .. }
+        )
+    }
+}
+
+fn send<Req, Resp>(req: &Req, cred: &SolaredgeCredentials, format: Format) -> Result<(), String>
+where
+    Req: SendReq<Resp>,
+    Resp: serde::de::DeserializeOwned + Serialize,
+{
+    let resp = req.send(cred).map_err(|e| e.to_string())?;
+    print(&resp, format)
+}
+
+fn print<T: Serialize>(resp: &T, format: Format) -> Result<(), String> {
+    let mut out = io::stdout().lock();
+    output::write(&mut out, resp, format)
+        .and_then(|()| out.flush())
+        .map_err(|e| e.to_string())
+}
+
+fn run(cli: &Cli) -> Result<(), String> {
+    let cred = credentials(cli)?;
+    let format = cli.format;
+
+    match &cli.command {
+        Command::Details => send(&SiteDetailsReq::new(), &cred, format),
+        Command::Overview => send(&SiteOverviewReq::new(), &cred, format),
+        Command::DataPeriod => send(&SiteDataPeriodReq::new(), &cred, format),
+        Command::Energy { range, time_unit } => {
+            let (start, end) = range.dates();
+            let req = SiteEnergyReq::new(start, end, time_unit.time_unit.clone());
+            send(&req, &cred, format)
+        }
+        Command::TimeFrameEnergy { range } => {
+            let (start, end) = range.dates();
+            send(&SiteTimeFrameEnergyReq::new(start, end), &cred, format)
+        }
+        Command::EnergyDetails {
+            range,
+            time_unit,
+            meters,
+        } => {
+            let (start, end) = range.times();
+            let req = SiteEnergyDetailedReq::new(
+                start,
+                end,
+                time_unit.time_unit.clone(),
+                meters.meters.clone(),
+            );
+            send(&req, &cred, format)
+        }
+        Command::Power { range } => {
+            let (start, end) = range.times();
+            send(&SitePowerReq::new(start, end), &cred, format)
+        }
+        Command::PowerDetails { range, meters } => {
+            let (start, end) = range.times();
+            let req = SitePowerDetailedReq::new(start, end, meters.meters.clone());
+            send(&req, &cred, format)
+        }
+        Command::PowerFlow => send(&SitePowerFlowReq::new(), &cred, format),
+        Command::Storage { range, serials } => {
+            let (start, end) = range.times();
+            let req = SiteStorageDataReq::new(start, end, serials.clone());
+            send(&req, &cred, format)
+        }
+        Command::Inventory => send(&SiteInventoryReq::new(), &cred, format),
+        Command::Equipment => send(&SiteEquipmentListReq::new(), &cred, format),
+        Command::ChangeLog { serial } => {
+            send(&SiteEquipmentChangeLogReq::new(serial), &cred, format)
+        }
+        Command::InverterData { serial, range } => {
+            let (start, end) = range.times();
+            let req = SiteInverterTechnicalDataReq::new(serial, start, end);
+            send(&req, &cred, format)
+        }
+        Command::Meters {
+            range,
+            time_unit,
+            meters,
+        } => {
+            let (start, end) = range.times();
+            let req = SiteGetMetersDataReq::new(
+                start,
+                end,
+                time_unit.time_unit.clone(),
+                meters.meters.clone(),
+            );
+            send(&req, &cred, format)
+        }
+        Command::Sensors => send(&SiteGetSensorListReq::new(), &cred, format),
+        Command::EnvBenefits { system_units } => {
+            let req = SiteEnvironmentalBenefitsReq::new(system_units.clone());
+            send(&req, &cred, format)
+        }
+        Command::Sites { .. } | Command::Accounts { .. } => list(&cli.command, &cred, format),
+        Command::Version { supported } => {
+            if *supported {
+                send(&SupportedVersionsReq::new(), &cred, format)
+            } else {
+                send(&CurrentVersionReq::new(), &cred, format)
+            }
+        }
+    }
+}
+
+/// Send a site or account list request, fetching every page if `--all` is given.
+fn list(command: &Command, cred: &SolaredgeCredentials, format: Format) -> Result<(), String> {
+    match command {
+        Command::Sites { list, status } => {
+            let req = SiteListReq::new(
+                list.size,
+                list.start_index,
+                list.search.clone(),
+                None,
+                None,
+                status.clone(),
+            );
+            if list.all {
+                let sites = req.iter(cred).collect::<Result<Vec<_>, _>>();
+                print(&sites.map_err(|e| e.to_string())?, format)
+            } else {
+                send(&req, cred, format)
+            }
+        }
+        Command::Accounts { list } => {
+            let req =
+                AccountsListReq::new(list.size, list.start_index, list.search.clone(), None, None);
+            if list.all {
+                let accounts = req.iter(cred).collect::<Result<Vec<_>, _>>();
+                print(&accounts.map_err(|e| e.to_string())?, format)
+            } else {
+                send(&req, cred, format)
+            }
+        }
+        _ => unreachable!("not a list command"),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("se-ms: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    /// Parse the arguments with the environment variables turned off, so the
+    /// result does not depend on the environment the tests run in.
+    fn parse(args: &[&str]) -> Cli {
+        let command = Cli::command()
+            .mut_arg("site_id", |a| a.env(None))
+            .mut_arg("api_key", |a| a.env(None))
+            .mut_arg("config", |a| a.env(None));
+        let matches = command.try_get_matches_from(args).unwrap();
+        Cli::from_arg_matches(&matches).unwrap()
+    }
+
+    #[test]
+    fn cli_definition_unit_test() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parse_date_time_unit_test() {
+        let start = parse_start("2022-01-02").unwrap();
+        assert_eq!(start.to_string(), "2022-01-02 00:00:00");
+
+        let end = parse_end("2022-01-02").unwrap();
+        assert_eq!(end.to_string(), "2022-01-02 23:59:59");
+
+        let t = parse_start("2022-01-02T10:15:00").unwrap();
+        assert_eq!(t, parse_end("2022-01-02 10:15:00").unwrap());
+
+        assert!(parse_start("02/01/2022").is_err());
+    }
+
+    #[test]
+    fn parse_enums_unit_test() {
+        assert_eq!(parse_time_unit("day"), Ok(TimeUnit::Day));
+        assert!(parse_time_unit("fortnight").is_err());
+        assert_eq!(parse_meter("FeedIn"), Ok(MeterType::FeedIn));
+        assert!(parse_meter("Grid").is_err());
+        assert_eq!(parse_status("All"), Ok(SiteListStatus::All));
+    }
+
+    #[test]
+    fn cli_args_unit_test() {
+        let cli = parse(&[
+            "se-ms",
+            "--api-key",
+            "KEY",
+            "energy-details",
+            "--start",
+            "2022-01-01",
+            "--meters",
+            "Production,FeedIn",
+            "--format",
+            "csv",
+        ]);
+        assert_eq!(cli.format, Format::Csv);
+        assert_eq!(cli.api_key.as_deref(), Some("KEY"));
+        assert_eq!(cli.site_id, None);
+        assert_eq!(cli.config, None);
+        let Command::EnergyDetails { meters, .. } = &cli.command else {
+            panic!("unexpected command {:?}", cli.command);
+        };
+        assert_eq!(
+            meters.meters,
+            Some(vec![MeterType::Production, MeterType::FeedIn])
+        );
+
+        // Credentials come from the command line; only site requests need a site id.
+        let cli = parse(&["se-ms", "--api-key", "KEY", "sites"]);
+        assert_eq!(credentials(&cli).unwrap().site_id(), "");
+        let cli = parse(&["se-ms", "--api-key", "KEY", "details"]);
+        assert_eq!(
+            credentials(&cli).unwrap_err(),
+            "No site id given, use --site-id, SE_MS_SITE_ID or a config file"
+        );
+        let cli = parse(&["se-ms", "details", "--site-id", "1234", "--api-key", "KEY"]);
+        assert_eq!(credentials(&cli).unwrap().site_id(), "1234");
+        let cli = parse(&["se-ms", "sites"]);
+        assert_eq!(
+            credentials(&cli).unwrap_err(),
+            "No API key given, use --api-key, SE_MS_API_KEY or a config file"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Rendering of SolarEdge responses as JSON, CSV or a plain text table.
+//!
+//! For CSV and tables a response is flattened into rows. Nested objects become
+//! dotted column names, and each array of objects is expanded into one row per
+//! element, repeating the columns of its parent object.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::io::{self, Write};
+
+/// Output format of the response.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Aligned plain text table
+    Table,
+
+    /// Pretty printed JSON, as returned by the API
+    Json,
+
+    /// Comma separated values
+    Csv,
+}
+
+/// Table of flattened response values.
+#[derive(Debug, Default, PartialEq)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+type Row = Vec<(String, String)>;
+
+/// Write a response in the given format.
+pub fn write<T: Serialize, W: Write>(out: &mut W, resp: &T, format: Format) -> io::Result<()> {
+    let value = serde_json::to_value(resp).map_err(io::Error::other)?;
+
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, &value).map_err(io::Error::other)?;
+            writeln!(out)
+        }
+        Format::Csv => write_csv(out, &flatten(&value)),
+        Format::Table => write_table(out, &flatten(&value)),
+    }
+}
+
+/// Flatten a response into a table. The single member wrapper object that
+/// every response has (e.g. `{"details": {...}}`) is not part of the column names.
+pub fn flatten(value: &Value) -> Table {
+    let value = match value {
+        Value::Object(m) if m.len() == 1 => m.values().next().unwrap_or(value),
+        _ => value,
+    };
+
+    let rows = match value {
+        Value::Array(a) => a.iter().flat_map(|v| rows(v, "")).collect(),
+        _ => rows(value, ""),
+    };
+
+    let mut headers: Vec<String> = Vec::new();
+    for row in &rows {
+        for (k, _) in row {
+            if !headers.contains(k) {
+                headers.push(k.clone());
+            }
+        }
+    }
+
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            headers
+                .iter()
+                .map(|h| {
+                    row.iter()
+                        .find(|(k, _)| k == h)
+                        .map(|(_, v)| v.clone())
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect();
+
+    Table { headers, rows }
+}
+
+fn rows(value: &Value, prefix: &str) -> Vec<Row> {
+    match value {
+        Value::Object(m) => object_rows(m, prefix),
+        Value::Array(a) if a.iter().any(Value::is_object) => {
+            a.iter().flat_map(|v| rows(v, prefix)).collect()
+        }
+        _ => vec![vec![(column(prefix, "value"), cell(value))]],
+    }
+}
+
+fn object_rows(m: &Map<String, Value>, prefix: &str) -> Vec<Row> {
+    let mut scalars = Row::new();
+    let mut children: Vec<Vec<Row>> = Vec::new();
+
+    for (k, v) in m {
+        let name = column(prefix, k);
+        match v {
+            Value::Object(inner) => {
+                let mut inner_rows = object_rows(inner, &name);
+                if inner_rows.len() == 1 {
+                    scalars.append(&mut inner_rows[0]);
+                } else if !inner_rows.is_empty() {
+                    children.push(inner_rows);
+                }
+            }
+            Value::Array(a) if a.iter().any(Value::is_object) => {
+                children.push(a.iter().flat_map(|v| rows(v, &name)).collect());
+            }
+            _ => scalars.push((name, cell(v))),
+        }
+    }
+
+    if children.iter().all(Vec::is_empty) {
+        return vec![scalars];
+    }
+
+    // Each array is expanded separately, so sibling arrays do not multiply.
+    children
+        .into_iter()
+        .flatten()
+        .map(|child| {
+            let mut row = scalars.clone();
+            row.extend(child);
+            row
+        })
+        .collect()
+}
+
+fn column(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{prefix}.{name}")
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(a) => a.iter().map(cell).collect::<Vec<_>>().join(";"),
+        _ => value.to_string(),
+    }
+}
+
+fn write_csv<W: Write>(out: &mut W, table: &Table) -> io::Result<()> {
+    writeln!(out, "{}", csv_line(&table.headers))?;
+    for row in &table.rows {
+        writeln!(out, "{}", csv_line(row))?;
+    }
+    Ok(())
+}
+
+fn csv_line(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|c| {
+            if c.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", c.replace('"', "\"\""))
+            } else {
+                c.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn write_table<W: Write>(out: &mut W, table: &Table) -> io::Result<()> {
+    let mut widths: Vec<usize> = table.headers.iter().map(|h| h.chars().count()).collect();
+    for row in &table.rows {
+        for (w, c) in widths.iter_mut().zip(row) {
+            *w = (*w).max(c.chars().count());
+        }
+    }
+
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!("{c:<w$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    writeln!(out, "{}", line(&table.headers))?;
+    writeln!(
+        out,
+        "{}",
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("  ")
+    )?;
+    for row in &table.rows {
+        writeln!(out, "{}", line(row))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn flatten_single_object_unit_test() {
+        let table = flatten(&json!({
+            "details": {"id": 1, "name": "Home", "location": {"city": "Austin"}, "ptoDate": null}
+        }));
+        assert_eq!(
+            table.headers,
+            vec!["id", "name", "location.city", "ptoDate"]
+        );
+        assert_eq!(table.rows, vec![vec!["1", "Home", "Austin", ""]]);
+    }
+
+    #[test]
+    fn flatten_nested_arrays_unit_test() {
+        let table = flatten(&json!({
+            "energyDetails": {
+                "unit": "Wh",
+                "meters": [
+                    {"type": "FeedIn", "values": [
+                        {"date": "2022-01-01 00:00:00", "value": 1.5},
+                        {"date": "2022-01-02 00:00:00", "value": null}]},
+                    {"type": "Purchased", "values": [
+                        {"date": "2022-01-01 00:00:00", "value": 2.0}]}
+                ]
+            }
+        }));
+        assert_eq!(
+            table.headers,
+            vec![
+                "unit",
+                "meters.type",
+                "meters.values.date",
+                "meters.values.value"
+            ]
+        );
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(
+            table.rows[1],
+            vec!["Wh", "FeedIn", "2022-01-02 00:00:00", ""]
+        );
+        assert_eq!(
+            table.rows[2],
+            vec!["Wh", "Purchased", "2022-01-01 00:00:00", "2.0"]
+        );
+    }
+
+    #[test]
+    fn flatten_sibling_arrays_unit_test() {
+        let table = flatten(&json!({
+            "Inventory": {
+                "meters": [{"name": "M1"}, {"name": "M2"}],
+                "inverters": [{"name": "I1"}]
+            }
+        }));
+        assert_eq!(table.headers, vec!["meters.name", "inverters.name"]);
+        assert_eq!(
+            table.rows,
+            vec![vec!["M1", ""], vec!["M2", ""], vec!["", "I1"]]
+        );
+    }
+
+    #[test]
+    fn csv_unit_test() {
+        let mut out = Vec::new();
+        write(
+            &mut out,
+            &json!({"x": {"a": "one, two", "b": "say \"hi\""}}),
+            Format::Csv,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "a,b\n\"one, two\",\"say \"\"hi\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn table_unit_test() {
+        let mut out = Vec::new();
+        write(
+            &mut out,
+            &json!({"sites": [{"id": 1, "name": "Home"}, {"id": 22, "name": "Cabin"}]}),
+            Format::Table,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "id  name\n--  -----\n1   Home\n22  Cabin\n"
+        );
+    }
+}
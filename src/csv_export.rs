@@ -0,0 +1,349 @@
+//! Module for writing time-series responses as CSV, either tidy (one row per value)
+//! or wide (one row per timestamp, one column per series).
+
+use crate::date_value::LocalClock;
+use crate::Series;
+use chrono::{NaiveDateTime, TimeZone};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+/// Writes [Series] as CSV.
+///
+/// ```
+/// use se_ms_api::{CsvWriter, SiteEnergyDetailedResp, TimeSeries};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let resp: SiteEnergyDetailedResp = serde_json::from_str(
+/// #     r#"{"energyDetails": {"timeUnit": "DAY", "unit": "Wh", "meters": []}}"#)?;
+/// let series = resp.energy_details.time_series()?;
+///
+/// let csv = CsvWriter::new(Some(';'), Some("%d.%m.%Y %H:%M"), Some("NA"));
+/// csv.write_wide(&mut std::io::stdout(), &series, &chrono::Utc)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvWriter {
+    delimiter: char,
+    timestamp_format: String,
+    missing: String,
+}
+
+impl Default for CsvWriter {
+    fn default() -> Self {
+        CsvWriter::new(None, None, None)
+    }
+}
+
+impl CsvWriter {
+    /// Create a CSV writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `delimiter` - Field separator, default `,`
+    /// * `timestamp_format` - chrono format string for timestamps,
+    ///   default `%Y-%m-%d %H:%M:%S` (as returned by SolarEdge)
+    /// * `missing` - Text written for values the server did not report,
+    ///   default empty
+    #[must_use]
+    pub fn new(
+        delimiter: Option<char>,
+        timestamp_format: Option<&str>,
+        missing: Option<&str>,
+    ) -> Self {
+        CsvWriter {
+            delimiter: delimiter.unwrap_or(','),
+            timestamp_format: timestamp_format
+                .unwrap_or(crate::URL_DATE_TIME_FORMAT)
+                .to_string(),
+            missing: missing.unwrap_or_default().to_string(),
+        }
+    }
+
+    /// Write one row per value with the columns
+    /// `timestamp, name, serial, value, unit`.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - where to write the CSV
+    /// * `series` - the series to write, in order
+    ///
+    /// # Errors
+    /// Returns an error if writing fails or the timestamp format is invalid.
+    pub fn write_tidy<W: Write>(&self, out: &mut W, series: &[Series]) -> io::Result<()> {
+        self.write_row(out, ["timestamp", "name", "serial", "value", "unit"])?;
+
+        for s in series {
+            let serial = s.serial.as_deref().unwrap_or_default();
+            for sample in &s.samples {
+                let timestamp = self.timestamp(sample.timestamp)?;
+                let value = self.value(sample.value);
+                self.write_row(out, [&timestamp, &s.name, serial, &value, &s.unit])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write one row per timestamp, with a column per series headed by its
+    /// label and unit, e.g. `Production [Wh]`. Rows are in time order.
+    ///
+    /// A local time repeated when daylight saving ends gets a row for each
+    /// occurrence, the samples of each series taken in the order the server
+    /// returned them.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - where to write the CSV
+    /// * `series` - the series to write, one column each
+    /// * `time_zone` - time zone of the site, in which the timestamps are given
+    ///
+    /// # Errors
+    /// Returns an error if writing fails, the timestamp format is invalid, a
+    /// timestamp does not exist in `time_zone` or a series has more than one
+    /// sample at an instant (join repeated series first, e.g. with
+    /// [Resampler::resample_all](crate::Resampler::resample_all)). Nothing is
+    /// written in the last two cases.
+    pub fn write_wide<W: Write, Tz: TimeZone>(
+        &self,
+        out: &mut W,
+        series: &[Series],
+        time_zone: &Tz,
+    ) -> io::Result<()> {
+        // Each row holds the local timestamp and a cell per series.
+        let mut rows = BTreeMap::new();
+        for (column, s) in series.iter().enumerate() {
+            let mut clock = LocalClock::new(time_zone);
+            for sample in &s.samples {
+                let instant = clock.utc(sample.timestamp).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "{} has a sample at {}, which does not exist in the time zone",
+                            s.label(),
+                            sample.timestamp
+                        ),
+                    )
+                })?;
+                let cell = &mut rows
+                    .entry(instant)
+                    .or_insert_with(|| (sample.timestamp, vec![None; series.len()]))
+                    .1[column];
+                if cell.is_some() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "{} has more than one sample at {}",
+                            s.label(),
+                            sample.timestamp
+                        ),
+                    ));
+                }
+                *cell = Some(sample.value);
+            }
+        }
+
+        let header = std::iter::once("timestamp".to_string())
+            .chain(series.iter().map(|s| format!("{} [{}]", s.label(), s.unit)))
+            .collect::<Vec<_>>();
+        self.write_row(out, header.iter().map(String::as_str))?;
+
+        for (timestamp, values) in rows.into_values() {
+            let row = std::iter::once(self.timestamp(timestamp)?)
+                .chain(values.into_iter().map(|v| self.value(v.flatten())))
+                .collect::<Vec<_>>();
+            self.write_row(out, row.iter().map(String::as_str))?;
+        }
+        Ok(())
+    }
+
+    fn timestamp(&self, timestamp: NaiveDateTime) -> io::Result<String> {
+        let mut s = String::new();
+        write!(s, "{}", timestamp.format(&self.timestamp_format)).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid timestamp format {}", self.timestamp_format),
+            )
+        })?;
+        Ok(s)
+    }
+
+    fn value(&self, value: Option<f64>) -> String {
+        match value {
+            Some(v) => v.to_string(),
+            None => self.missing.clone(),
+        }
+    }
+
+    fn write_row<'a, W: Write>(
+        &self,
+        out: &mut W,
+        cells: impl IntoIterator<Item = &'a str>,
+    ) -> io::Result<()> {
+        let line = cells
+            .into_iter()
+            .map(|c| self.quote(c))
+            .collect::<Vec<_>>()
+            .join(&self.delimiter.to_string());
+        writeln!(out, "{line}")
+    }
+
+    fn quote(&self, cell: &str) -> String {
+        if cell.contains([self.delimiter, '"', '\n', '\r']) {
+            format!("\"{}\"", cell.replace('"', "\"\""))
+        } else {
+            cell.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, Sample};
+    use chrono::Utc;
+    use chrono_tz::Europe::Berlin;
+
+    fn at(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, crate::URL_DATE_TIME_FORMAT).unwrap()
+    }
+
+    fn series() -> Vec<Series> {
+        vec![
+            Series {
+                name: "Production".to_string(),
+                serial: None,
+                unit: "Wh".to_string(),
+                samples: vec![
+                    Sample {
+                        timestamp: at("2022-01-01 00:15:00"),
+                        value: Some(12.5),
+                    },
+                    Sample {
+                        timestamp: at("2022-01-01 00:00:00"),
+                        value: None,
+                    },
+                ],
+            },
+            Series {
+                name: "FeedIn".to_string(),
+                serial: Some("606".to_string()),
+                unit: "Wh".to_string(),
+                samples: vec![Sample {
+                    timestamp: at("2022-01-01 00:15:00"),
+                    value: Some(3.0),
+                }],
+            },
+        ]
+    }
+
+    fn to_string(f: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+        let mut out = Vec::new();
+        f(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn csv_tidy_unit_test() {
+        let csv = CsvWriter::default();
+        assert_eq!(
+            to_string(|out| csv.write_tidy(out, &series())),
+            "timestamp,name,serial,value,unit\n\
+             2022-01-01 00:15:00,Production,,12.5,Wh\n\
+             2022-01-01 00:00:00,Production,,,Wh\n\
+             2022-01-01 00:15:00,FeedIn,606,3,Wh\n"
+        );
+    }
+
+    #[test]
+    fn csv_wide_unit_test() {
+        let csv = CsvWriter::new(Some(';'), Some("%d.%m.%Y %H:%M"), Some("NA"));
+        assert_eq!(
+            to_string(|out| csv.write_wide(out, &series(), &Utc)),
+            "timestamp;Production [Wh];FeedIn 606 [Wh]\n\
+             01.01.2022 00:00;NA;NA\n\
+             01.01.2022 00:15;12.5;3\n"
+        );
+    }
+
+    #[test]
+    fn csv_wide_duplicate_unit_test() {
+        let mut series = series();
+        series[1].samples.push(Sample {
+            timestamp: at("2022-01-01 00:15:00"),
+            value: Some(4.0),
+        });
+        let mut out = Vec::new();
+        let e = CsvWriter::default()
+            .write_wide(&mut out, &series, &Utc)
+            .unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            e.to_string(),
+            "FeedIn 606 has more than one sample at 2022-01-01 00:15:00"
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn csv_wide_daylight_saving_unit_test() {
+        let quarters = ["01:45:00", "02:00:00", "02:15:00", "02:00:00", "02:15:00"];
+        let mut series = vec![Series {
+            name: "Production".to_string(),
+            serial: None,
+            unit: "Wh".to_string(),
+            samples: quarters
+                .iter()
+                .zip(1..)
+                .map(|(time, value)| Sample {
+                    timestamp: at(&format!("2022-10-30 {time}")),
+                    value: Some(f64::from(value)),
+                })
+                .collect(),
+        }];
+        assert_eq!(
+            to_string(|out| CsvWriter::default().write_wide(out, &series, &Berlin)),
+            "timestamp,Production [Wh]\n\
+             2022-10-30 01:45:00,1\n\
+             2022-10-30 02:00:00,2\n\
+             2022-10-30 02:15:00,3\n\
+             2022-10-30 02:00:00,4\n\
+             2022-10-30 02:15:00,5\n"
+        );
+
+        series[0].samples.push(Sample {
+            timestamp: at("2022-10-30 02:00:00"),
+            value: Some(6.0),
+        });
+        let mut out = Vec::new();
+        let e = CsvWriter::default()
+            .write_wide(&mut out, &series, &Berlin)
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Production has more than one sample at 2022-10-30 02:00:00"
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn csv_quote_unit_test() {
+        let csv = CsvWriter::default();
+        assert_eq!(csv.quote("a,b"), "\"a,b\"");
+        assert_eq!(csv.quote("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv.quote("a;b"), "a;b");
+    }
+
+    #[test]
+    fn csv_bad_timestamp_format_unit_test() {
+        let csv = CsvWriter::new(None, Some("%Q"), None);
+        let mut out = Vec::new();
+        let e = csv.write_tidy(&mut out, &series()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<CsvWriter>();
+    }
+}
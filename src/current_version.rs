@@ -1,5 +1,6 @@
 //! Module for querying the current API version of the SolarEdge monitoring server.
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, MONITORING_API_URL};
 use serde::Deserialize;
 
@@ -9,18 +10,34 @@ pub struct Req;
 
 /// Current version response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Resp {
     /// The API version running on the server
     pub version: Version,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp { version: "version" });
+
 /// The release version of the server
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Version {
     /// The release number running on the server in <major.minor.revision> format.
     pub release: String,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Version {});
+
 impl Req {
     /// Create a current version request message that can be sent to SolarEdge.
     #[must_use]
@@ -31,7 +48,7 @@ impl Req {
 
 impl SendReq<Resp> for Req {
     fn build_url(&self, _: &str, api_key: &str) -> String {
-        format!("{}version/current?{}", *MONITORING_API_URL, api_key,)
+        format!("{}version/current?{}", *MONITORING_API_URL, api_key)
     }
 }
 
@@ -46,6 +63,12 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(r#"{"version": {"release": "1.0.0"}}"#);
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
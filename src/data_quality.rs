@@ -0,0 +1,634 @@
+//! Module for checking the completeness of time series and filling their gaps.
+//!
+//! The server reports `null` for intervals an inverter or meter did not report,
+//! leaves some intervals out altogether and occasionally repeats a timestamp.
+//! Summed as they are, such series silently under- or over-count. A
+//! [QualityCheck] lists these problems, together with zero production in the
+//! middle of the day, and fills the gaps with a chosen [FillMethod], recording
+//! every value it made up and every sample it left out.
+//!
+//! Timestamps are in the site's local time. The local hour repeated when daylight
+//! saving ends holds real data, the first occurrence of a time being before the
+//! change and the second after it, and the local hour skipped when it starts is
+//! not expected.
+
+use crate::date_value::LocalClock;
+use crate::windows::next_period;
+use crate::{MeterType, Sample, Series, TimeUnit};
+use chrono::{DateTime, NaiveDateTime, NaiveTime, Offset, TimeDelta, TimeZone, Utc};
+use std::collections::BTreeMap;
+
+/// Kind of problem found in a series.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IssueKind {
+    /// The timestamp is present but has no value
+    MissingValue,
+
+    /// The timestamp is absent between the first and last timestamp of the series
+    MissingTimestamp,
+
+    /// The timestamp occurs more than once, or more than twice in the hour
+    /// repeated when daylight saving ends
+    DuplicateTimestamp,
+
+    /// The local time does not exist in the site's time zone, e.g. because it is
+    /// skipped when daylight saving starts
+    NonexistentTimestamp,
+
+    /// Production is zero in daylight, e.g. because the inverter was offline
+    DaylightZero,
+}
+
+/// A problem at one timestamp of a series.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Issue {
+    /// Site local date and time of the interval
+    pub timestamp: NaiveDateTime,
+
+    /// What is wrong with the interval
+    pub kind: IssueKind,
+}
+
+/// Problems found in one series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualityReport {
+    /// Name of the series, e.g. "Production"
+    pub name: String,
+
+    /// Serial number of the meter or device of the series, if known
+    pub serial: Option<String>,
+
+    /// Number of samples in the series, duplicates included
+    pub samples: usize,
+
+    /// The problems, in order of time
+    pub issues: Vec<Issue>,
+}
+
+/// How to fill a gap in a series.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FillMethod {
+    /// Assume nothing was produced or consumed
+    Zero,
+
+    /// Interpolate linearly in time between the reported values on either side.
+    /// Gaps before the first or after the last reported value stay unfilled.
+    Linear,
+
+    /// Repeat the last reported value. Gaps before the first reported value stay
+    /// unfilled.
+    Previous,
+}
+
+/// A value made up by filling a gap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fill {
+    /// Site local date and time of the interval
+    pub timestamp: NaiveDateTime,
+
+    /// The value filled in
+    pub value: f64,
+
+    /// Whether the timestamp was missing from the series, rather than present
+    /// without a value
+    pub inserted: bool,
+}
+
+/// A series with its gaps filled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FillReport {
+    /// The filled series, in order of time
+    pub series: Series,
+
+    /// The values that were filled in, in order of time
+    pub fills: Vec<Fill>,
+
+    /// Samples left out of the filled series because their time was already in
+    /// it, or because their local time does not exist, in the order given
+    pub dropped: Vec<Sample>,
+}
+
+/// Settings for checking and filling series of one aggregation granularity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualityCheck<Tz: TimeZone> {
+    /// Aggregation granularity of the series, which gives the expected timestamps
+    pub time_unit: TimeUnit,
+
+    /// Site local time from which zero production is suspicious
+    pub daylight_start: NaiveTime,
+
+    /// Site local time until which zero production is suspicious
+    pub daylight_end: NaiveTime,
+
+    /// Time zone of the site
+    pub time_zone: Tz,
+}
+
+impl IssueKind {
+    /// Short description of the kind of problem.
+    #[must_use]
+    pub fn description(&self) -> &'static str {
+        match self {
+            IssueKind::MissingValue => "missing value",
+            IssueKind::MissingTimestamp => "missing timestamp",
+            IssueKind::DuplicateTimestamp => "duplicate timestamp",
+            IssueKind::NonexistentTimestamp => "nonexistent local time",
+            IssueKind::DaylightZero => "zero in daylight",
+        }
+    }
+}
+
+impl std::fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl QualityReport {
+    /// Number of problems of the given kind.
+    #[must_use]
+    pub fn count(&self, kind: IssueKind) -> usize {
+        self.issues.iter().filter(|i| i.kind == kind).count()
+    }
+
+    /// Whether no problems were found.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl<Tz: TimeZone> QualityCheck<Tz> {
+    /// Create the settings for checking series.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_unit` - aggregation granularity of the series. Power series are
+    ///   [TimeUnit::QuarterOfAnHour].
+    /// * `daylight` - site local start and end of the hours in which zero
+    ///   production is reported, None for 10:00 to 16:00. Only applies to
+    ///   quarter-hour and hourly series; a zero day, week, month or year of
+    ///   production is always reported.
+    /// * `time_zone` - time zone of the site, e.g. a `chrono_tz::Tz`, for the
+    ///   daylight saving changes. `chrono::Utc` has none.
+    #[must_use]
+    pub fn new(
+        time_unit: TimeUnit,
+        daylight: Option<(NaiveTime, NaiveTime)>,
+        time_zone: Tz,
+    ) -> Self {
+        let (daylight_start, daylight_end) = daylight.unwrap_or((
+            NaiveTime::from_hms_opt(10, 0, 0).unwrap_or_default(),
+            NaiveTime::from_hms_opt(16, 0, 0).unwrap_or_default(),
+        ));
+        QualityCheck {
+            time_unit,
+            daylight_start,
+            daylight_end,
+            time_zone,
+        }
+    }
+
+    /// Check a series from the energy, power, energy details or power details
+    /// requests (or any other series of the configured granularity).
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - the series to check, in order of time as the server returns
+    ///   it, so the two occurrences of a repeated local time can be told apart
+    ///
+    /// # Returns
+    /// The problems found. Zeros are only reported for Production series.
+    #[must_use]
+    pub fn check(&self, series: &Series) -> QualityReport {
+        let is_production = series.name == MeterType::Production.to_string();
+        let mut issues: Vec<(DateTime<Utc>, Issue)> = Vec::new();
+        let mut issue =
+            |instant, timestamp, kind| issues.push((instant, Issue { timestamp, kind }));
+
+        let mut clock = LocalClock::new(&self.time_zone);
+        let mut seen: BTreeMap<DateTime<Utc>, usize> = BTreeMap::new();
+        for sample in &series.samples {
+            let timestamp = sample.timestamp;
+            let instant = if let Some(instant) = clock.utc(timestamp) {
+                let count = seen.entry(instant).or_default();
+                *count += 1;
+                if *count == 2 {
+                    issue(instant, timestamp, IssueKind::DuplicateTimestamp);
+                }
+                instant
+            } else {
+                let instant = self.approximate_utc(timestamp);
+                issue(instant, timestamp, IssueKind::NonexistentTimestamp);
+                instant
+            };
+
+            match sample.value {
+                None => issue(instant, timestamp, IssueKind::MissingValue),
+                Some(v) if v == 0.0 && is_production && self.in_daylight(timestamp) => {
+                    issue(instant, timestamp, IssueKind::DaylightZero);
+                }
+                Some(_) => {}
+            }
+        }
+
+        let instants: Vec<DateTime<Utc>> = seen.into_keys().collect();
+        for instant in self.missing_instants(&instants) {
+            issue(instant, self.local(instant), IssueKind::MissingTimestamp);
+        }
+        issues.sort_by_key(|(instant, _)| *instant);
+        let issues = issues.into_iter().map(|(_, issue)| issue).collect();
+
+        QualityReport {
+            name: series.name.clone(),
+            serial: series.serial.clone(),
+            samples: series.samples.len(),
+            issues,
+        }
+    }
+
+    /// Check every series of a response, see [check](Self::check).
+    #[must_use]
+    pub fn check_all(&self, series: &[Series]) -> Vec<QualityReport> {
+        series.iter().map(|s| self.check(s)).collect()
+    }
+
+    /// Fill the gaps of a series.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - the series to fill, in order of time as for
+    ///   [check](Self::check)
+    /// * `method` - how to fill the missing values and timestamps
+    ///
+    /// # Returns
+    /// The series in order of time with the missing timestamps inserted, the
+    /// values that were filled in, and the samples left out: the repeats of a
+    /// duplicate timestamp, of which only the first is kept, and local times that
+    /// do not exist. A gap the method cannot fill keeps a None value.
+    #[must_use]
+    pub fn fill(&self, series: &Series, method: FillMethod) -> FillReport {
+        let mut clock = LocalClock::new(&self.time_zone);
+        let mut values: BTreeMap<DateTime<Utc>, (NaiveDateTime, Option<f64>, bool)> =
+            BTreeMap::new();
+        let mut dropped = Vec::new();
+        for sample in &series.samples {
+            match clock.utc(sample.timestamp) {
+                Some(instant) if !values.contains_key(&instant) => {
+                    values.insert(instant, (sample.timestamp, sample.value, false));
+                }
+                _ => dropped.push(*sample),
+            }
+        }
+        let instants: Vec<DateTime<Utc>> = values.keys().copied().collect();
+        for instant in self.missing_instants(&instants) {
+            values.insert(instant, (self.local(instant), None, true));
+        }
+        let values: Vec<(DateTime<Utc>, NaiveDateTime, Option<f64>, bool)> = values
+            .into_iter()
+            .map(|(instant, (t, v, i))| (instant, t, v, i))
+            .collect();
+
+        // Nearest reported value after each position, for interpolation.
+        let mut next_known = vec![None; values.len()];
+        let mut next = None;
+        for (i, (instant, _, v, _)) in values.iter().enumerate().rev() {
+            next_known[i] = next;
+            if let Some(v) = v {
+                next = Some((*instant, *v));
+            }
+        }
+
+        let mut samples = Vec::with_capacity(values.len());
+        let mut fills = Vec::new();
+        let mut previous: Option<(DateTime<Utc>, f64)> = None;
+        for (i, (instant, timestamp, value, inserted)) in values.into_iter().enumerate() {
+            if let Some(v) = value {
+                previous = Some((instant, v));
+                samples.push(Sample { timestamp, value });
+                continue;
+            }
+
+            let filled = match method {
+                FillMethod::Zero => Some(0.0),
+                FillMethod::Previous => previous.map(|(_, v)| v),
+                FillMethod::Linear => previous.zip(next_known[i]).map(|((t0, v0), (t1, v1))| {
+                    #[allow(clippy::cast_precision_loss)]
+                    let fraction =
+                        (instant - t0).num_seconds() as f64 / (t1 - t0).num_seconds() as f64;
+                    v0 + (v1 - v0) * fraction
+                }),
+            };
+            if let Some(value) = filled {
+                fills.push(Fill {
+                    timestamp,
+                    value,
+                    inserted,
+                });
+            }
+            samples.push(Sample {
+                timestamp,
+                value: filled,
+            });
+        }
+
+        FillReport {
+            series: Series {
+                name: series.name.clone(),
+                serial: series.serial.clone(),
+                unit: series.unit.clone(),
+                samples,
+            },
+            fills,
+            dropped,
+        }
+    }
+
+    /// Whether zero production at `t` is suspicious.
+    fn in_daylight(&self, t: NaiveDateTime) -> bool {
+        match self.time_unit {
+            TimeUnit::QuarterOfAnHour | TimeUnit::Hour => {
+                (self.daylight_start..self.daylight_end).contains(&t.time())
+            }
+            _ => true,
+        }
+    }
+
+    /// The expected instants missing between consecutive `instants`, which are
+    /// sorted and distinct.
+    fn missing_instants(&self, instants: &[DateTime<Utc>]) -> Vec<DateTime<Utc>> {
+        let mut missing = Vec::new();
+        for pair in instants.windows(2) {
+            let mut expected = self.next_instant(pair[0]);
+            while let Some(t) = expected.filter(|t| *t < pair[1]) {
+                missing.push(t);
+                expected = self.next_instant(t);
+            }
+        }
+        missing
+    }
+
+    /// The start of the interval after the one starting at `instant`. Quarters
+    /// and hours follow each other in UTC, longer periods start at local midnight.
+    fn next_instant(&self, instant: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self.time_unit {
+            TimeUnit::QuarterOfAnHour => Some(instant + TimeDelta::minutes(15)),
+            TimeUnit::Hour => Some(instant + TimeDelta::hours(1)),
+            _ => next_period(self.local(instant), &self.time_unit)
+                .and_then(|t| self.time_zone.from_local_datetime(&t).earliest())
+                .map(|t| t.with_timezone(&Utc)),
+        }
+    }
+
+    /// Site local time of an instant.
+    fn local(&self, instant: DateTime<Utc>) -> NaiveDateTime {
+        instant.with_timezone(&self.time_zone).naive_local()
+    }
+
+    /// An instant near a local time that does not exist, for ordering it among
+    /// the others.
+    fn approximate_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let offset = self.time_zone.offset_from_utc_datetime(&local).fix();
+        (local - TimeDelta::seconds(i64::from(offset.local_minus_utc()))).and_utc()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, SitePowerDetailedResp, TimeSeries};
+    use chrono_tz::Europe::Berlin;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::URL_DATE_TIME_FORMAT).unwrap()
+    }
+
+    fn series() -> Vec<Series> {
+        let resp: SitePowerDetailedResp = serde_json::from_str(
+            r#"{"powerDetails": {"timeUnit": "QUARTER_OF_AN_HOUR", "unit": "W", "meters": [
+                {"type": "Production", "values": [
+                    {"date": "2022-06-01 11:00:00", "value": 1000},
+                    {"date": "2022-06-01 11:15:00"},
+                    {"date": "2022-06-01 11:30:00", "value": 2000},
+                    {"date": "2022-06-01 12:15:00", "value": 5000},
+                    {"date": "2022-06-01 12:15:00", "value": 7000},
+                    {"date": "2022-06-01 12:30:00", "value": 0},
+                    {"date": "2022-06-01 20:00:00", "value": 0}]},
+                {"type": "Consumption", "values": [
+                    {"date": "2022-06-01 11:00:00", "value": 0},
+                    {"date": "2022-06-01 11:15:00", "value": 300}]}
+            ]}}"#,
+        )
+        .unwrap();
+        resp.power_details.time_series().unwrap()
+    }
+
+    #[test]
+    fn check_unit_test() {
+        let check = QualityCheck::new(TimeUnit::QuarterOfAnHour, None, Utc);
+        let reports = check.check_all(&series());
+
+        let production = &reports[0];
+        assert_eq!(production.samples, 7);
+        assert_eq!(production.count(IssueKind::MissingValue), 1);
+        assert_eq!(production.count(IssueKind::DuplicateTimestamp), 1);
+        assert_eq!(production.count(IssueKind::DaylightZero), 1);
+        // 11:45 and 12:00, then 12:45 until 19:45.
+        assert_eq!(production.count(IssueKind::MissingTimestamp), 2 + 29);
+        assert_eq!(
+            production.issues[..4],
+            [
+                Issue {
+                    timestamp: at("2022-06-01 11:15:00"),
+                    kind: IssueKind::MissingValue
+                },
+                Issue {
+                    timestamp: at("2022-06-01 11:45:00"),
+                    kind: IssueKind::MissingTimestamp
+                },
+                Issue {
+                    timestamp: at("2022-06-01 12:00:00"),
+                    kind: IssueKind::MissingTimestamp
+                },
+                Issue {
+                    timestamp: at("2022-06-01 12:15:00"),
+                    kind: IssueKind::DuplicateTimestamp
+                },
+            ]
+        );
+        assert!(!production.is_clean());
+
+        // Zero consumption is not suspicious.
+        assert!(reports[1].is_clean());
+    }
+
+    #[test]
+    fn fill_unit_test() {
+        let check = QualityCheck::new(TimeUnit::QuarterOfAnHour, None, Utc);
+        let mut production = series().remove(0);
+        production.samples.truncate(5);
+
+        let FillReport {
+            series: filled,
+            fills,
+            dropped,
+        } = check.fill(&production, FillMethod::Linear);
+        let values: Vec<Option<f64>> = filled.samples.iter().map(|s| s.value).collect();
+        assert_eq!(
+            values,
+            [
+                Some(1000.0),
+                Some(1500.0),
+                Some(2000.0),
+                Some(3000.0),
+                Some(4000.0),
+                Some(5000.0)
+            ]
+        );
+        assert_eq!(fills.len(), 3);
+        assert!(!fills[0].inserted);
+        assert!(fills[1].inserted);
+        assert_eq!(filled.samples[3].timestamp, at("2022-06-01 11:45:00"));
+        // The second 12:15 value is left out, and reported.
+        assert_eq!(dropped, [production.samples[4]]);
+
+        let FillReport {
+            series: filled,
+            fills,
+            ..
+        } = check.fill(&production, FillMethod::Previous);
+        assert_eq!(filled.samples[1].value, Some(1000.0));
+        assert_eq!(filled.samples[4].value, Some(2000.0));
+        assert_eq!(fills.len(), 3);
+        assert!(check.check(&filled).is_clean());
+
+        let filled = check.fill(&production, FillMethod::Zero).series;
+        assert_eq!(filled.samples[3].value, Some(0.0));
+        assert_eq!(filled.samples.len(), 6);
+
+        // Nothing to interpolate from before the first value.
+        production.samples[0].value = None;
+        let report = check.fill(&production, FillMethod::Linear);
+        assert_eq!(report.series.samples[0].value, None);
+        assert_eq!(report.fills.len(), 2);
+    }
+
+    fn quarters(timestamps: &[&str]) -> Series {
+        Series {
+            name: MeterType::Consumption.to_string(),
+            serial: None,
+            unit: "W".to_string(),
+            samples: timestamps
+                .iter()
+                .enumerate()
+                .map(|(i, t)| Sample {
+                    timestamp: at(t),
+                    value: Some(f64::from(u32::try_from(i).unwrap())),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn daylight_saving_unit_test() {
+        let check = QualityCheck::new(TimeUnit::QuarterOfAnHour, None, Berlin);
+
+        // 02:00 to 02:59 is skipped when daylight saving starts.
+        let spring = quarters(&["2022-03-27 01:45:00", "2022-03-27 03:00:00"]);
+        assert!(check.check(&spring).is_clean());
+
+        // 02:00 to 02:59 is repeated when it ends, the second 02:15 is missing.
+        let autumn = quarters(&[
+            "2022-10-30 02:00:00",
+            "2022-10-30 02:15:00",
+            "2022-10-30 02:30:00",
+            "2022-10-30 02:45:00",
+            "2022-10-30 02:00:00",
+            "2022-10-30 02:30:00",
+            "2022-10-30 02:45:00",
+            "2022-10-30 03:00:00",
+        ]);
+        let report = check.check(&autumn);
+        assert_eq!(
+            report.issues,
+            [Issue {
+                timestamp: at("2022-10-30 02:15:00"),
+                kind: IssueKind::MissingTimestamp
+            }]
+        );
+
+        let report = check.fill(&autumn, FillMethod::Linear);
+        assert!(report.dropped.is_empty());
+        let values: Vec<Option<f64>> = report.series.samples.iter().map(|s| s.value).collect();
+        assert_eq!(
+            values,
+            [
+                Some(0.0),
+                Some(1.0),
+                Some(2.0),
+                Some(3.0),
+                Some(4.0),
+                Some(4.5),
+                Some(5.0),
+                Some(6.0),
+                Some(7.0)
+            ]
+        );
+        assert_eq!(
+            report.series.samples[5].timestamp,
+            at("2022-10-30 02:15:00")
+        );
+
+        // A third 02:00 is a duplicate, and 02:30 on the day it is skipped does
+        // not exist.
+        let mut repeated = autumn.clone();
+        repeated.samples.push(autumn.samples[0]);
+        let report = check.check(&repeated);
+        assert_eq!(report.count(IssueKind::DuplicateTimestamp), 1);
+        assert_eq!(check.fill(&repeated, FillMethod::Zero).dropped.len(), 1);
+        let skipped = quarters(&["2022-03-27 01:45:00", "2022-03-27 02:30:00"]);
+        let report = check.check(&skipped);
+        assert_eq!(report.count(IssueKind::NonexistentTimestamp), 1);
+        assert_eq!(check.fill(&skipped, FillMethod::Zero).dropped.len(), 1);
+    }
+
+    #[test]
+    fn daily_zero_unit_test() {
+        let check = QualityCheck::new(TimeUnit::Day, None, Utc);
+        let series = Series {
+            name: MeterType::Production.to_string(),
+            serial: None,
+            unit: "Wh".to_string(),
+            samples: vec![
+                Sample {
+                    timestamp: at("2022-06-01 00:00:00"),
+                    value: Some(0.0),
+                },
+                Sample {
+                    timestamp: at("2022-06-03 00:00:00"),
+                    value: Some(12000.0),
+                },
+            ],
+        };
+        let report = check.check(&series);
+        assert_eq!(report.count(IssueKind::DaylightZero), 1);
+        assert_eq!(
+            report.issues[1],
+            Issue {
+                timestamp: at("2022-06-02 00:00:00"),
+                kind: IssueKind::MissingTimestamp
+            }
+        );
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<IssueKind>();
+        is_normal::<Issue>();
+        is_normal::<QualityReport>();
+        is_normal::<FillMethod>();
+        is_normal::<Fill>();
+        is_normal::<FillReport>();
+        is_normal::<QualityCheck<Utc>>();
+    }
+}
@@ -1,16 +1,81 @@
 //! Module for handling generic date / value pairs returned by the SolarEdge server monitoring API.
 
+use crate::extras::impl_unknown_fields;
+use crate::{Error, Kind, URL_DATE_TIME_FORMAT};
+use chrono::{DateTime, LocalResult, NaiveDateTime, TimeZone, Utc};
 use serde::Deserialize;
+use std::collections::HashSet;
 
 /// A date and value pair returned from the monitoring API. The value units are specified by the unit
 /// field elsewhere in the response.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct DateValue {
     /// YYYY-mm-dd HH:MM:SS
     pub date: String,
 
     /// Often an integer, but can be float too. Meaning defined by the context of the response.
-    pub value: Option<f32>,
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    pub value: Option<f64>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
+}
+
+impl_unknown_fields!(DateValue {});
+
+impl DateValue {
+    /// Parse the date of this value.
+    ///
+    /// # Returns
+    /// The site local date and time, or an error if the date is not in the
+    /// `YYYY-mm-dd HH:MM:SS` format.
+    ///
+    /// # Errors
+    /// Returns [Kind::InvalidTimestamp] with the offending date.
+    pub fn timestamp(&self) -> Result<chrono::NaiveDateTime, Error> {
+        parse_timestamp(&self.date)
+    }
+}
+
+/// Parse a `YYYY-mm-dd HH:MM:SS` date from a response.
+pub(crate) fn parse_timestamp(date: &str) -> Result<chrono::NaiveDateTime, Error> {
+    chrono::NaiveDateTime::parse_from_str(date, URL_DATE_TIME_FORMAT)
+        .map_err(|_| Error::new(Kind::InvalidTimestamp(date.to_string())))
+}
+
+/// Converts site local times to UTC, taking repeated local times in order.
+pub(crate) struct LocalClock<'a, Tz: TimeZone> {
+    time_zone: &'a Tz,
+    seen: HashSet<NaiveDateTime>,
+}
+
+impl<'a, Tz: TimeZone> LocalClock<'a, Tz> {
+    pub(crate) fn new(time_zone: &'a Tz) -> Self {
+        LocalClock {
+            time_zone,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// The instant of `local`, None if the time does not exist (skipped when
+    /// daylight saving starts).
+    pub(crate) fn utc(&mut self, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        let t = match self.time_zone.from_local_datetime(&local) {
+            LocalResult::Single(t) => t,
+            LocalResult::Ambiguous(earliest, latest) => {
+                if self.seen.insert(local) {
+                    earliest
+                } else {
+                    latest
+                }
+            }
+            LocalResult::None => return None,
+        };
+        Some(t.with_timezone(&Utc))
+    }
 }
 
 #[cfg(test)]
@@ -18,6 +83,32 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[test]
+    fn date_value_precision_unit_test() {
+        let dv: DateValue =
+            serde_json::from_str(r#"{"date": "2022-01-01 00:00:00", "value": 123456789.125}"#)
+                .unwrap();
+        assert_eq!(dv.value, Some(123_456_789.125));
+    }
+
+    #[test]
+    fn date_value_timestamp_unit_test() {
+        let dv = DateValue {
+            date: "2022-03-04 05:15:00".to_string(),
+            ..DateValue::default()
+        };
+        assert_eq!(dv.timestamp().unwrap().to_string(), "2022-03-04 05:15:00");
+
+        let dv = DateValue {
+            date: "2022-03-04".to_string(),
+            ..DateValue::default()
+        };
+        assert!(matches!(
+            dv.timestamp().unwrap_err().kind(),
+            Kind::InvalidTimestamp(d) if d == "2022-03-04"
+        ));
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<DateValue>();
@@ -31,6 +31,26 @@ pub enum Kind {
 
     /// HTTP error from sending a request.
     HttpErrorStatus(String, String),
+
+    /// The request was not sent because the daily request quota is used up.
+    QuotaExceeded,
+
+    /// A date in a response was not in the expected `YYYY-mm-dd HH:MM:SS` format.
+    InvalidTimestamp(String),
+
+    /// An error writing output.
+    IoError(std::io::Error),
+
+    /// An argument or input file was not valid for the operation.
+    InvalidArgument(String),
+
+    /// An error converting to Arrow or writing Parquet.
+    #[cfg(feature = "parquet")]
+    ParquetError(parquet::errors::ParquetError),
+
+    /// An error reading or writing the local history store.
+    #[cfg(feature = "store")]
+    StoreError(rusqlite::Error),
 }
 
 impl error::Error for Error {
@@ -38,6 +58,14 @@ impl error::Error for Error {
         match self.kind {
             Kind::ReqwestError(_) => "Reqwest error",
             Kind::HttpErrorStatus(_, _) => "HTTP error",
+            Kind::QuotaExceeded => "Quota exceeded",
+            Kind::InvalidTimestamp(_) => "Invalid timestamp",
+            Kind::IoError(_) => "I/O error",
+            Kind::InvalidArgument(_) => "Invalid argument",
+            #[cfg(feature = "parquet")]
+            Kind::ParquetError(_) => "Parquet error",
+            #[cfg(feature = "store")]
+            Kind::StoreError(_) => "Store error",
         }
     }
 }
@@ -47,6 +75,14 @@ impl fmt::Display for Error {
         match &self.kind {
             Kind::ReqwestError(s) => write!(f, "Reqwest Error: HTTP status-code {s}"),
             Kind::HttpErrorStatus(s, t) => write!(f, "HTTP error: {s}: {t}"),
+            Kind::QuotaExceeded => write!(f, "Daily request quota exceeded"),
+            Kind::InvalidTimestamp(s) => write!(f, "Invalid timestamp: {s}"),
+            Kind::IoError(e) => write!(f, "I/O error: {e}"),
+            Kind::InvalidArgument(s) => write!(f, "Invalid argument: {s}"),
+            #[cfg(feature = "parquet")]
+            Kind::ParquetError(e) => write!(f, "Parquet error: {e}"),
+            #[cfg(feature = "store")]
+            Kind::StoreError(e) => write!(f, "Store error: {e}"),
         }
     }
 }
@@ -56,3 +92,30 @@ impl From<reqwest::Error> for Error {
         Error::new(Kind::ReqwestError(e))
     }
 }
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::new(Kind::IoError(e))
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(e: parquet::errors::ParquetError) -> Self {
+        Error::new(Kind::ParquetError(e))
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<arrow_schema::ArrowError> for Error {
+    fn from(e: arrow_schema::ArrowError) -> Self {
+        Error::new(Kind::ParquetError(e.into()))
+    }
+}
+
+#[cfg(feature = "store")]
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::new(Kind::StoreError(e))
+    }
+}
@@ -0,0 +1,192 @@
+//! Module for capturing response fields that are not part of the documented API schema.
+//!
+//! With the `extras` feature enabled, every response struct has an `extras` map that
+//! collects the fields the library does not recognize, and [unknown_fields] lists the
+//! JSON path of each of them so schema changes can be reported precisely.
+
+#[cfg(feature = "extras")]
+use std::collections::HashMap;
+
+/// Unrecognized fields of a response object, keyed by their JSON name.
+#[cfg(feature = "extras")]
+pub type Extras = HashMap<String, serde_json::Value>;
+
+/// Implemented by the response types so the unrecognized fields collected in their
+/// [Extras] maps can be reported.
+#[cfg(feature = "extras")]
+pub trait UnknownFields {
+    /// Append the JSON path of every unrecognized field found in this value, and in
+    /// the values it contains, to `paths`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`  - JSON path of this value within the response
+    /// * `paths` - list the unknown field paths are appended to
+    fn unknown_fields_at(&self, path: &str, paths: &mut Vec<String>);
+}
+
+/// List the JSON path of every unrecognized field seen in a response.
+///
+/// Paths use dots for object members and brackets for array indices,
+/// e.g. `sites.site[0].newField`.
+///
+/// # Arguments
+///
+/// * `resp` - response (or part of a response) to search
+///
+/// # Returns
+/// The paths of the unknown fields, in the order they appear in the response model.
+#[cfg(feature = "extras")]
+#[must_use]
+pub fn unknown_fields<T: UnknownFields>(resp: &T) -> Vec<String> {
+    let mut paths = Vec::new();
+    resp.unknown_fields_at("", &mut paths);
+    paths
+}
+
+#[cfg(feature = "extras")]
+impl<T: UnknownFields> UnknownFields for Vec<T> {
+    fn unknown_fields_at(&self, path: &str, paths: &mut Vec<String>) {
+        for (i, v) in self.iter().enumerate() {
+            v.unknown_fields_at(&format!("{path}[{i}]"), paths);
+        }
+    }
+}
+
+#[cfg(feature = "extras")]
+impl<T: UnknownFields> UnknownFields for Option<T> {
+    fn unknown_fields_at(&self, path: &str, paths: &mut Vec<String>) {
+        if let Some(v) = self {
+            v.unknown_fields_at(path, paths);
+        }
+    }
+}
+
+#[cfg(feature = "extras")]
+pub(crate) fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+#[cfg(feature = "extras")]
+pub(crate) fn push_extras(path: &str, extras: &Extras, paths: &mut Vec<String>) {
+    let mut names = extras.keys().collect::<Vec<_>>();
+    names.sort();
+    paths.extend(names.into_iter().map(|name| join(path, name)));
+}
+
+/// Implement [UnknownFields] for a response struct with an `extras` field.
+/// The struct's fields that may themselves contain unknown fields are listed
+/// along with their JSON names. Transparent list wrappers delegate to their
+/// single field.
+macro_rules! impl_unknown_fields {
+    ($t:ty { $($field:ident : $name:literal),* $(,)? }) => {
+        #[cfg(feature = "extras")]
+        impl $crate::UnknownFields for $t {
+            fn unknown_fields_at(&self, path: &str, paths: &mut Vec<String>) {
+                $crate::extras::push_extras(path, &self.extras, paths);
+                $(
+                    $crate::UnknownFields::unknown_fields_at(
+                        &self.$field,
+                        &$crate::extras::join(path, $name),
+                        paths,
+                    );
+                )*
+            }
+        }
+    };
+    (transparent $t:ty, $field:ident) => {
+        #[cfg(feature = "extras")]
+        impl $crate::UnknownFields for $t {
+            fn unknown_fields_at(&self, path: &str, paths: &mut Vec<String>) {
+                $crate::UnknownFields::unknown_fields_at(&self.$field, path, paths);
+            }
+        }
+    };
+}
+
+pub(crate) use impl_unknown_fields;
+
+#[cfg(all(test, feature = "extras"))]
+mod tests {
+    use super::*;
+    use crate::{SiteDetailsResp, SiteListResp};
+
+    #[test]
+    fn unknown_fields_unit_test() {
+        let json = r#"{
+            "details": {
+                "id": 1,
+                "name": "Home",
+                "accountId": 2,
+                "status": "Active",
+                "peakPower": 9.8,
+                "lastUpdateTime": "2022-01-01 12:00:00",
+                "currency": "USD",
+                "installationDate": "2018-02-08",
+                "ptoDate": null,
+                "notes": "",
+                "type": "Optimizers & Inverters",
+                "location": {
+                    "country": "United States",
+                    "city": "Austin",
+                    "address": "1 Main St",
+                    "address2": "",
+                    "zip": "78701",
+                    "timeZone": "America/Chicago",
+                    "countryCode": "US",
+                    "latitude": 30.27
+                },
+                "primaryModule": {
+                    "manufacturerName": "LG",
+                    "modelName": "LG335N1C-A5",
+                    "maximumPower": 335.0,
+                    "temperatureCoef": -0.36
+                },
+                "uris": {},
+                "publicSettings": {"isPublic": false},
+                "zipCodeRegion": "South",
+                "batteryCount": 0
+            }
+        }"#;
+
+        let resp: SiteDetailsResp = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            resp.details.extras.get("zipCodeRegion"),
+            Some(&serde_json::Value::from("South"))
+        );
+        assert_eq!(
+            unknown_fields(&resp),
+            vec![
+                "details.batteryCount",
+                "details.zipCodeRegion",
+                "details.location.latitude",
+            ]
+        );
+
+        let json = format!(r#"{{"sites": {{"count": 1, "site": [{}], "page": 0}}}}"#, {
+            let v: serde_json::Value = serde_json::from_str(json).unwrap();
+            v["details"].to_string()
+        });
+        let resp: SiteListResp = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            unknown_fields(&resp),
+            vec![
+                "sites.page",
+                "sites.site[0].batteryCount",
+                "sites.site[0].zipCodeRegion",
+                "sites.site[0].location.latitude",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_fields_none_unit_test() {
+        let resp: crate::CurrentVersionResp =
+            serde_json::from_str(r#"{"version": {"release": "1.0.0"}}"#).unwrap();
+        assert!(unknown_fields(&resp).is_empty());
+    }
+}
@@ -0,0 +1,416 @@
+//! Module for sending the same request to every site of an account, using an
+//! account level api key.
+//!
+//! SolarEdge allows at most three concurrent requests per api key and a daily
+//! quota of requests, so sites are fetched at most three at a time and no more
+//! requests are sent once the quota is used up. Requests that have a bulk version
+//! in the API are sent for up to 100 sites at a time.
+
+use crate::{
+    Error, Kind, SendReq, SiteDataPeriodReq, SiteDataPeriodResp, SiteDetailsReq, SiteDetailsResp,
+    SiteEnergyDetailedReq, SiteEnergyDetailedResp, SiteEnvironmentalBenefitsReq,
+    SiteEnvironmentalBenefitsResp, SiteEquipmentChangeLogReq, SiteEquipmentChangeLogResp,
+    SiteEquipmentListReq, SiteEquipmentListResp, SiteGetMetersDataReq, SiteGetMetersDataResp,
+    SiteGetSensorListReq, SiteGetSensorListResp, SiteInventoryReq, SiteInventoryResp,
+    SiteInverterTechnicalDataReq, SiteInverterTechnicalDataResp, SiteListReq, SitePowerDetailedReq,
+    SitePowerDetailedResp, SitePowerFlowReq, SitePowerFlowResp, SitePowerReq, SitePowerResp,
+    SiteStorageDataReq, SiteStorageDataResp, SiteTimeFrameEnergyReq, SiteTimeFrameEnergyResp,
+    SolaredgeCredentials,
+};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+/// Maximum number of concurrent requests SolarEdge accepts per api key.
+pub const MAX_CONCURRENT_REQUESTS: usize = 3;
+
+/// Number of requests SolarEdge accepts per api key per day.
+pub const DAILY_REQUEST_LIMIT: u32 = 300;
+
+/// Maximum number of sites in one bulk request.
+pub(crate) const MAX_BULK_SITES: usize = 100;
+
+/// Requests that can be sent to every site of a fleet.
+///
+/// Requests with a bulk version in the API override `send_bulk`.
+pub trait FleetReq<Resp>: SendReq<Resp> {
+    #[doc(hidden)]
+    fn send_bulk(
+        &self,
+        _site_ids: &[u32],
+        _solaredge: &SolaredgeCredentials,
+    ) -> Option<Result<Vec<(u32, Resp)>, Error>> {
+        None
+    }
+}
+
+impl FleetReq<SiteDataPeriodResp> for SiteDataPeriodReq {}
+impl FleetReq<SiteDetailsResp> for SiteDetailsReq {}
+impl FleetReq<SiteEnergyDetailedResp> for SiteEnergyDetailedReq {}
+impl FleetReq<SiteEnvironmentalBenefitsResp> for SiteEnvironmentalBenefitsReq {}
+impl FleetReq<SiteEquipmentChangeLogResp> for SiteEquipmentChangeLogReq {}
+impl FleetReq<SiteEquipmentListResp> for SiteEquipmentListReq {}
+impl FleetReq<SiteGetMetersDataResp> for SiteGetMetersDataReq {}
+impl FleetReq<SiteGetSensorListResp> for SiteGetSensorListReq {}
+impl FleetReq<SiteInventoryResp> for SiteInventoryReq {}
+impl FleetReq<SiteInverterTechnicalDataResp> for SiteInverterTechnicalDataReq {}
+impl FleetReq<SitePowerDetailedResp> for SitePowerDetailedReq {}
+impl FleetReq<SitePowerFlowResp> for SitePowerFlowReq {}
+impl FleetReq<SitePowerResp> for SitePowerReq {}
+impl FleetReq<SiteStorageDataResp> for SiteStorageDataReq {}
+impl FleetReq<SiteTimeFrameEnergyResp> for SiteTimeFrameEnergyReq {}
+
+/// Progress of a request being sent to a fleet of sites.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// Site whose response was just received.
+    pub site_id: u32,
+
+    /// Number of sites with a result so far.
+    pub completed: usize,
+
+    /// Number of those sites whose result is an error.
+    pub failed: usize,
+
+    /// Number of sites the request is being sent to.
+    pub total: usize,
+
+    /// Number of bulk requests that failed so far. Their sites are fetched one
+    /// by one instead, each taking a request from the quota.
+    pub bulk_failed: usize,
+}
+
+/// The sites of an account, reached with the account's api key.
+#[derive(Debug)]
+pub struct Fleet {
+    account: SolaredgeCredentials,
+    site_ids: Vec<u32>,
+    remaining_quota: AtomicU32,
+}
+
+impl Fleet {
+    /// Create a fleet from a list of site IDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `account`     - credentials holding the account level api key
+    /// * `site_ids`    - sites to send requests to
+    /// * `daily_quota` - number of requests this fleet may send,
+    ///   defaults to SolarEdge's daily limit of 300
+    #[must_use]
+    pub fn new(
+        account: &SolaredgeCredentials,
+        site_ids: Vec<u32>,
+        daily_quota: Option<u32>,
+    ) -> Self {
+        Fleet {
+            account: account.clone(),
+            site_ids,
+            remaining_quota: AtomicU32::new(daily_quota.unwrap_or(DAILY_REQUEST_LIMIT)),
+        }
+    }
+
+    /// Create a fleet from the sites returned by a site list request.
+    /// Each page of the site list is one request taken from the quota.
+    ///
+    /// # Arguments
+    ///
+    /// * `account`     - credentials holding the account level api key
+    /// * `req`         - site list request selecting the sites of the fleet
+    /// * `daily_quota` - number of requests this fleet may send,
+    ///   defaults to SolarEdge's daily limit of 300
+    ///
+    /// # Errors
+    /// Errors can occur on sending any of the site list requests. Returns
+    /// [Kind::QuotaExceeded] if the quota runs out before every page is fetched.
+    pub fn from_site_list(
+        account: &SolaredgeCredentials,
+        req: &SiteListReq,
+        daily_quota: Option<u32>,
+    ) -> Result<Self, Error> {
+        let fleet = Fleet::new(account, Vec::new(), daily_quota);
+
+        let mut site_ids = Vec::new();
+        let sites = req.iter_with(account, || {
+            if fleet.use_quota(1) {
+                Ok(())
+            } else {
+                Err(Error::new(Kind::QuotaExceeded))
+            }
+        });
+        for site in sites {
+            site_ids.push(site?.id);
+        }
+
+        Ok(Fleet { site_ids, ..fleet })
+    }
+
+    /// The sites requests are sent to.
+    #[must_use]
+    pub fn site_ids(&self) -> &[u32] {
+        &self.site_ids
+    }
+
+    /// Number of requests that may still be sent.
+    #[must_use]
+    pub fn remaining_quota(&self) -> u32 {
+        self.remaining_quota.load(Ordering::SeqCst)
+    }
+
+    /// Send a request to every site of the fleet.
+    ///
+    /// Bulk requests are used when the API has them; sites missing from a bulk
+    /// response, or in a bulk request that failed, are then fetched one by one.
+    /// A failed bulk request is counted in [Progress::bulk_failed], as fetching
+    /// its sites one by one takes up to 100 requests from the quota instead of
+    /// one. Sites are fetched at most three at a time. Once the quota is used up,
+    /// the remaining sites get a [Kind::QuotaExceeded] error.
+    ///
+    /// # Arguments
+    ///
+    /// * `req`      - request to send to each site
+    /// * `progress` - called each time a site's result is available
+    ///
+    /// # Returns
+    /// The response or error of each site, keyed by site ID.
+    pub fn send<R, Resp, P>(&self, req: &R, mut progress: P) -> HashMap<u32, Result<Resp, Error>>
+    where
+        R: FleetReq<Resp> + Sync,
+        for<'de> Resp: Deserialize<'de> + Send,
+        P: FnMut(&Progress),
+    {
+        let total = self.site_ids.len();
+        let mut results = HashMap::new();
+        let mut tracker = Progress {
+            total,
+            ..Progress::default()
+        };
+
+        let mut pending = Vec::new();
+        for chunk in self.site_ids.chunks(MAX_BULK_SITES) {
+            // The request is taken from the quota up front and given back if
+            // the request has no bulk version.
+            let bulk = if self.use_quota(1) {
+                let bulk = req.send_bulk(chunk, &self.account);
+                if bulk.is_none() {
+                    self.remaining_quota.fetch_add(1, Ordering::SeqCst);
+                }
+                bulk
+            } else {
+                None
+            };
+            match bulk {
+                Some(Ok(responses)) => {
+                    for (site_id, resp) in responses {
+                        if chunk.contains(&site_id) && !results.contains_key(&site_id) {
+                            tracker.site_id = site_id;
+                            tracker.completed += 1;
+                            progress(&tracker);
+                            results.insert(site_id, Ok(resp));
+                        }
+                    }
+                    pending.extend(chunk.iter().filter(|id| !results.contains_key(id)));
+                }
+                Some(Err(_)) => {
+                    tracker.bulk_failed += 1;
+                    pending.extend_from_slice(chunk);
+                }
+                None => pending.extend_from_slice(chunk),
+            }
+        }
+
+        let fetch = |site_id: u32| {
+            if self.use_quota(1) {
+                req.send(&self.account.for_site(&site_id.to_string()))
+            } else {
+                Err(Error::new(Kind::QuotaExceeded))
+            }
+        };
+
+        fan_out(
+            pending,
+            MAX_CONCURRENT_REQUESTS,
+            fetch,
+            |site_id, result| {
+                tracker.site_id = site_id;
+                tracker.completed += 1;
+                if result.is_err() {
+                    tracker.failed += 1;
+                }
+                progress(&tracker);
+                results.insert(site_id, result);
+            },
+        );
+
+        results
+    }
+
+    /// Take `n` requests from the quota, if that many remain.
+    fn use_quota(&self, n: u32) -> bool {
+        self.remaining_quota
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |q| q.checked_sub(n))
+            .is_ok()
+    }
+}
+
+/// Call `fetch` for each site on at most `concurrency` threads, handing each
+/// result to `done` on the calling thread as it arrives.
+pub(crate) fn fan_out<Resp, F, D>(site_ids: Vec<u32>, concurrency: usize, fetch: F, mut done: D)
+where
+    Resp: Send,
+    F: Fn(u32) -> Result<Resp, Error> + Sync,
+    D: FnMut(u32, Result<Resp, Error>),
+{
+    let workers = concurrency.min(site_ids.len()).max(1);
+    let queue = Mutex::new(VecDeque::from(site_ids));
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let queue = &queue;
+            let fetch = &fetch;
+            scope.spawn(move || loop {
+                let next = match queue.lock() {
+                    Ok(mut q) => q.pop_front(),
+                    Err(_) => None,
+                };
+                let Some(site_id) = next else {
+                    break;
+                };
+                if tx.send((site_id, fetch(site_id))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        for (site_id, result) in rx {
+            done(site_id, result);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_normal;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    #[test]
+    fn fan_out_concurrency_unit_test() {
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let mut results = HashMap::new();
+        fan_out(
+            (1..=20).collect(),
+            MAX_CONCURRENT_REQUESTS,
+            |site_id| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(5));
+                running.fetch_sub(1, Ordering::SeqCst);
+                if site_id % 5 == 0 {
+                    Err(Error::new(Kind::QuotaExceeded))
+                } else {
+                    Ok(site_id * 10)
+                }
+            },
+            |site_id, result| {
+                results.insert(site_id, result);
+            },
+        );
+
+        assert_eq!(results.len(), 20);
+        assert!(peak.load(Ordering::SeqCst) <= MAX_CONCURRENT_REQUESTS);
+        assert_eq!(results[&3].as_ref().unwrap(), &30);
+        assert!(results[&5].is_err());
+    }
+
+    #[test]
+    fn fleet_quota_unit_test() {
+        let fleet = Fleet::new(&SolaredgeCredentials::new("", "key"), vec![1, 2], Some(2));
+        assert!(fleet.use_quota(1));
+        assert!(fleet.use_quota(1));
+        assert!(!fleet.use_quota(1));
+        assert_eq!(fleet.remaining_quota(), 0);
+    }
+
+    #[test]
+    fn fleet_quota_exceeded_unit_test() {
+        let fleet = Fleet::new(
+            &SolaredgeCredentials::new("", "key"),
+            vec![1, 2, 3],
+            Some(0),
+        );
+        let mut updates = Vec::new();
+        let results = fleet.send(&SiteDetailsReq::new(), |p| updates.push(p.clone()));
+
+        assert_eq!(results.len(), 3);
+        for result in results.values() {
+            match result {
+                Err(e) => assert!(matches!(e.kind(), Kind::QuotaExceeded)),
+                Ok(_) => panic!("request sent without quota"),
+            }
+        }
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates[2].completed, 3);
+        assert_eq!(updates[2].failed, 3);
+        assert_eq!(updates[2].total, 3);
+    }
+
+    struct FailingBulkReq;
+
+    impl SendReq<SiteDetailsResp> for FailingBulkReq {
+        fn build_url(&self, _site_id: &str, _api_key: &str) -> String {
+            String::new()
+        }
+    }
+
+    impl FleetReq<SiteDetailsResp> for FailingBulkReq {
+        fn send_bulk(
+            &self,
+            _site_ids: &[u32],
+            _solaredge: &SolaredgeCredentials,
+        ) -> Option<Result<Vec<(u32, SiteDetailsResp)>, Error>> {
+            Some(Err(Error::new(Kind::HttpErrorStatus(
+                "403".to_string(),
+                "Forbidden".to_string(),
+            ))))
+        }
+    }
+
+    #[test]
+    fn fleet_bulk_failed_unit_test() {
+        let fleet = Fleet::new(&SolaredgeCredentials::new("", "key"), vec![1, 2], Some(1));
+        let mut updates = Vec::new();
+        let results = fleet.send(&FailingBulkReq, |p| updates.push(p.clone()));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(fleet.remaining_quota(), 0);
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[1].bulk_failed, 1);
+        assert_eq!(updates[1].failed, 2);
+    }
+
+    #[test]
+    fn from_site_list_quota_unit_test() {
+        let e = Fleet::from_site_list(
+            &SolaredgeCredentials::new("", "key"),
+            &SiteListReq::new(None, None, None, None, None, None),
+            Some(0),
+        )
+        .unwrap_err();
+        assert!(matches!(e.kind(), Kind::QuotaExceeded));
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<Fleet>();
+        is_normal::<Progress>();
+    }
+}
@@ -0,0 +1,473 @@
+//! Module for writing responses as InfluxDB line protocol.
+//!
+//! SolarEdge reports dates in the site's local time. A [LineProtocol] converts them
+//! to nanosecond UTC timestamps using the site's time zone, which can be any
+//! chrono [TimeZone] (e.g. `chrono::FixedOffset` or a `chrono_tz::Tz`). When
+//! daylight saving ends, the first occurrence of a repeated local time is taken
+//! to be before the change and the second after it.
+
+use crate::date_value::LocalClock;
+use crate::{
+    EnergyDetails, Error, Kind, Parameters, PowerDetails, Series, SiteCurrentPowerFlow, Telemetry,
+    TimeSeries, REQWEST_CLIENT,
+};
+use chrono::{NaiveDateTime, TimeZone};
+use std::fmt::Display;
+use std::io::Write;
+
+/// Writes line protocol for a site.
+///
+/// Lines for [Series] have the form
+/// `<measurement>,site_id=<id>,meter_type=<name>[,serial=<serial>],unit=<unit> value=<value> <ns>`.
+/// Values the server did not report, and values that are not finite, are skipped.
+#[derive(Clone, Debug)]
+pub struct LineProtocol<Tz: TimeZone> {
+    site_id: String,
+    time_zone: Tz,
+}
+
+impl<Tz: TimeZone> LineProtocol<Tz> {
+    /// Create a line protocol writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `site_id` - written as the `site_id` tag of every line
+    /// * `time_zone` - time zone of the site, for converting the site local dates
+    #[must_use]
+    pub fn new(site_id: &str, time_zone: Tz) -> Self {
+        LineProtocol {
+            site_id: site_id.to_string(),
+            time_zone,
+        }
+    }
+
+    /// Write a line for each value of the series.
+    ///
+    /// # Returns
+    /// The number of lines written.
+    ///
+    /// # Errors
+    /// Returns [Kind::InvalidTimestamp] if a date does not exist in the site's time
+    /// zone (e.g. skipped by a daylight saving change), or [Kind::IoError] if
+    /// writing fails.
+    pub fn write_series<W: Write>(
+        &self,
+        out: &mut W,
+        measurement: &str,
+        series: &[Series],
+    ) -> Result<usize, Error> {
+        let mut lines = 0;
+        for s in series {
+            let mut tags = vec![
+                escape_measurement(measurement),
+                format!("site_id={}", escape_tag(&self.site_id)),
+                format!("meter_type={}", escape_tag(&s.name)),
+            ];
+            if let Some(serial) = &s.serial {
+                tags.push(format!("serial={}", escape_tag(serial)));
+            }
+            tags.push(format!("unit={}", escape_tag(&s.unit)));
+            let tags = tags.join(",");
+
+            let mut clock = LocalClock::new(&self.time_zone);
+            for sample in &s.samples {
+                // Every sample goes through the clock, so a repeated local time is
+                // recognised even if its first occurrence has no value.
+                let ns = timestamp(&mut clock, sample.timestamp);
+                if let Some(value) = sample.value.and_then(|v| number_field("value", v)) {
+                    writeln!(out, "{tags} {value} {}", ns?)?;
+                    lines += 1;
+                }
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Write the meters of a power details response with the measurement `power`.
+    ///
+    /// # Errors
+    /// See [write_series](Self::write_series).
+    pub fn write_power_details<W: Write>(
+        &self,
+        out: &mut W,
+        power_details: &PowerDetails,
+    ) -> Result<usize, Error> {
+        self.write_series(out, "power", &power_details.time_series()?)
+    }
+
+    /// Write the meters of an energy details response with the measurement `energy`.
+    ///
+    /// # Errors
+    /// See [write_series](Self::write_series).
+    pub fn write_energy_details<W: Write>(
+        &self,
+        out: &mut W,
+        energy_details: &EnergyDetails,
+    ) -> Result<usize, Error> {
+        self.write_series(out, "energy", &energy_details.time_series()?)
+    }
+
+    /// Write a `power_flow` line for each element (GRID, LOAD, PV, STORAGE) of the
+    /// site's current power flow, tagged with the element and the response's unit.
+    /// Fields are `current_power`, `status` and, for storage, `charge_level` (%)
+    /// and `critical`.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - where to write the lines
+    /// * `power_flow` - the current power flow
+    /// * `at` - site local time the power flow was requested
+    ///
+    /// # Errors
+    /// See [write_series](Self::write_series).
+    pub fn write_power_flow<W: Write>(
+        &self,
+        out: &mut W,
+        power_flow: &SiteCurrentPowerFlow,
+        at: NaiveDateTime,
+    ) -> Result<usize, Error> {
+        let ns = timestamp(&mut LocalClock::new(&self.time_zone), at)?;
+        let elements = [
+            ("GRID", Some(&power_flow.grid)),
+            ("LOAD", Some(&power_flow.load)),
+            ("PV", power_flow.pv.as_ref()),
+            ("STORAGE", power_flow.storage.as_ref()),
+        ];
+
+        let mut lines = 0;
+        for (element, parameters) in elements {
+            let Some(p) = parameters else { continue };
+            writeln!(
+                out,
+                "power_flow,site_id={},element={element},unit={} {} {ns}",
+                escape_tag(&self.site_id),
+                escape_tag(&power_flow.unit),
+                power_flow_fields(p)
+            )?;
+            lines += 1;
+        }
+        Ok(lines)
+    }
+
+    /// Write an `inverter` line for each telemetry, tagged with the inverter's serial
+    /// number. The field names carry their unit: `total_active_power_W`,
+    /// `dc_voltage_V`, `power_limit_pct`, `total_energy_Wh`, `temperature_C`, along
+    /// with `inverter_mode` and `operation_mode`. The phase data of `L1Data` is
+    /// written as `l1_ac_current_A`, `l1_ac_voltage_V`, `l1_ac_frequency_Hz`,
+    /// `l1_apparent_power_VA`, `l1_active_power_W`, `l1_reactive_power_VAR` and
+    /// `l1_cos_phi`.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - where to write the lines
+    /// * `serial` - serial number of the inverter the telemetries were requested for
+    /// * `telemetries` - the telemetries
+    ///
+    /// # Errors
+    /// See [write_series](Self::write_series).
+    pub fn write_telemetries<W: Write>(
+        &self,
+        out: &mut W,
+        serial: &str,
+        telemetries: &[Telemetry],
+    ) -> Result<usize, Error> {
+        let mut clock = LocalClock::new(&self.time_zone);
+        for t in telemetries {
+            let ns = timestamp(&mut clock, crate::date_value::parse_timestamp(&t.date)?)?;
+
+            let l1 = &t.l1_data;
+            let numbers = [
+                ("total_active_power_W", t.total_active_power),
+                ("dc_voltage_V", t.dc_voltage),
+                ("power_limit_pct", t.power_limit),
+                ("temperature_C", Some(t.temperature)),
+                ("l1_ac_current_A", Some(l1.ac_current)),
+                ("l1_ac_voltage_V", Some(l1.ac_voltage)),
+                ("l1_ac_frequency_Hz", Some(l1.ac_frequency)),
+                ("l1_apparent_power_VA", Some(l1.apparent_power)),
+                ("l1_active_power_W", Some(l1.active_power)),
+                ("l1_reactive_power_VAR", Some(l1.reactive_power)),
+                ("l1_cos_phi", Some(l1.cos_phi)),
+            ];
+            let mut fields: Vec<String> = numbers
+                .into_iter()
+                .filter_map(|(name, value)| number_field(name, value?))
+                .collect();
+            fields.extend(number_field("total_energy_Wh", t.total_energy));
+            fields.push(format!(
+                "inverter_mode={}",
+                escape_string(&t.inverter_mode.to_string())
+            ));
+            fields.push(format!(
+                "operation_mode={}i",
+                u16::from(t.operation_mode.clone())
+            ));
+
+            writeln!(
+                out,
+                "inverter,site_id={},serial={} {} {ns}",
+                escape_tag(&self.site_id),
+                escape_tag(serial),
+                fields.join(",")
+            )?;
+        }
+        Ok(telemetries.len())
+    }
+}
+
+/// Nanoseconds since the Unix epoch of a site local date.
+fn timestamp<Tz: TimeZone>(clock: &mut LocalClock<Tz>, local: NaiveDateTime) -> Result<i64, Error> {
+    clock
+        .utc(local)
+        .and_then(|utc| utc.timestamp_nanos_opt())
+        .ok_or_else(|| Error::new(Kind::InvalidTimestamp(local.to_string())))
+}
+
+/// A numeric field, None if the value is not finite.
+fn number_field<T: Into<f64> + Display + Copy>(name: &str, value: T) -> Option<String> {
+    value.into().is_finite().then(|| format!("{name}={value}"))
+}
+
+fn power_flow_fields(p: &Parameters) -> String {
+    let mut fields: Vec<String> = number_field("current_power", p.current_power)
+        .into_iter()
+        .collect();
+    fields.push(format!("status={}", escape_string(&p.status.to_string())));
+    if let Some(level) = p.charge_level {
+        fields.push(format!("charge_level={level}i"));
+    }
+    if let Some(critical) = p.critical {
+        fields.push(format!("critical={critical}"));
+    }
+    fields.join(",")
+}
+
+fn escape_measurement(s: &str) -> String {
+    s.replace(',', "\\,").replace(' ', "\\ ")
+}
+
+fn escape_tag(s: &str) -> String {
+    s.replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+fn escape_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Sends line protocol to an InfluxDB server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InfluxDb {
+    url: String,
+    token: Option<String>,
+}
+
+impl InfluxDb {
+    /// Create a client for an InfluxDB write endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - full write URL, e.g.
+    ///   `http://localhost:8086/api/v2/write?org=home&bucket=solar&precision=ns`
+    ///   or `http://localhost:8086/write?db=solar` for InfluxDB 1.x
+    /// * `token` - API token, sent as `Authorization: Token <token>`
+    #[must_use]
+    pub fn new(url: &str, token: Option<&str>) -> Self {
+        InfluxDb {
+            url: url.to_string(),
+            token: token.map(str::to_string),
+        }
+    }
+
+    /// POST line protocol to the server.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the server does not accept the lines.
+    pub fn post(&self, lines: Vec<u8>) -> Result<(), Error> {
+        let mut req = REQWEST_CLIENT
+            .post(&self.url)
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .body(lines);
+        if let Some(token) = &self.token {
+            req = req.header("Authorization", format!("Token {token}"));
+        }
+
+        let res = req.send()?;
+        if res.status().is_success() {
+            Ok(())
+        } else {
+            let status = res.status().to_string();
+            let text = res.text().unwrap_or_default();
+            Err(Error::new(Kind::HttpErrorStatus(status, text)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        is_normal, SiteInverterTechnicalDataResp, SitePowerDetailedResp, SitePowerFlowResp,
+    };
+    use chrono::FixedOffset;
+    use chrono_tz::America::Chicago;
+
+    fn central() -> FixedOffset {
+        FixedOffset::west_opt(6 * 3600).unwrap()
+    }
+
+    fn to_string(f: impl FnOnce(&mut Vec<u8>) -> Result<usize, Error>) -> String {
+        let mut out = Vec::new();
+        f(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn power_details_line_protocol_unit_test() {
+        let resp: SitePowerDetailedResp = serde_json::from_str(
+            r#"{"powerDetails": {"timeUnit": "QUARTER_OF_AN_HOUR", "unit": "W", "meters": [
+                {"type": "Production", "values": [
+                    {"date": "2022-01-01 12:00:00", "value": 1520.5},
+                    {"date": "2022-01-01 12:15:00"}]}
+            ]}}"#,
+        )
+        .unwrap();
+
+        let lp = LineProtocol::new("1234", central());
+        assert_eq!(
+            to_string(|out| lp.write_power_details(out, &resp.power_details)),
+            "power,site_id=1234,meter_type=Production,unit=W value=1520.5 1641060000000000000\n"
+        );
+    }
+
+    #[test]
+    fn power_flow_line_protocol_unit_test() {
+        let resp: SitePowerFlowResp = serde_json::from_str(
+            r#"{"siteCurrentPowerFlow": {"updateRefreshRate": 3, "unit": "kW",
+                "connections": [{"from": "PV", "to": "Load"}],
+                "GRID": {"status": "Active", "currentPower": 0.5},
+                "LOAD": {"status": "Active", "currentPower": 2.5},
+                "STORAGE": {"status": "Idle", "currentPower": 0, "chargeLevel": 85, "critical": false}
+            }}"#,
+        )
+        .unwrap();
+
+        let lp = LineProtocol::new("1234", chrono::Utc);
+        let at = NaiveDateTime::parse_from_str("2022-01-01 00:00:00", crate::URL_DATE_TIME_FORMAT)
+            .unwrap();
+        assert_eq!(
+            to_string(|out| lp.write_power_flow(out, &resp.site_current_power_flow, at)),
+            "power_flow,site_id=1234,element=GRID,unit=kW current_power=0.5,status=\"Active\" 1640995200000000000\n\
+             power_flow,site_id=1234,element=LOAD,unit=kW current_power=2.5,status=\"Active\" 1640995200000000000\n\
+             power_flow,site_id=1234,element=STORAGE,unit=kW current_power=0,status=\"Idle\",charge_level=85i,critical=false 1640995200000000000\n"
+        );
+    }
+
+    #[test]
+    fn telemetry_line_protocol_unit_test() {
+        let resp: SiteInverterTechnicalDataResp = serde_json::from_str(
+            r#"{"data": {"count": 1, "telemetries": [{
+                "date": "2022-01-01 08:05:12",
+                "totalActivePower": 1520.5,
+                "powerLimit": 100.0,
+                "totalEnergy": 31234.0,
+                "temperature": 31.5,
+                "inverterMode": "MPPT",
+                "operationMode": 0,
+                "L1Data": {"acCurrent": 6.25, "acVoltage": 245.5, "acFrequency": 60.0,
+                    "apparentPower": 1530.5, "activePower": 1520.5, "reactivePower": 12.5,
+                    "cosPhi": 1.0}
+            }]}}"#,
+        )
+        .unwrap();
+
+        let lp = LineProtocol::new("1234", chrono::Utc);
+        assert_eq!(
+            to_string(|out| lp.write_telemetries(out, "7E1 A", &resp.data.telemetries.t)),
+            "inverter,site_id=1234,serial=7E1\\ A total_active_power_W=1520.5,power_limit_pct=100,\
+             temperature_C=31.5,l1_ac_current_A=6.25,l1_ac_voltage_V=245.5,l1_ac_frequency_Hz=60,\
+             l1_apparent_power_VA=1530.5,l1_active_power_W=1520.5,l1_reactive_power_VAR=12.5,\
+             l1_cos_phi=1,total_energy_Wh=31234,inverter_mode=\"MPPT\",operation_mode=0i \
+             1641024312000000000\n"
+        );
+    }
+
+    #[test]
+    fn escape_unit_test() {
+        assert_eq!(escape_tag("a b,c=d"), "a\\ b\\,c\\=d");
+        assert_eq!(escape_measurement("a b,c=d"), "a\\ b\\,c=d");
+        assert_eq!(escape_string("say \"hi\"\\"), "\"say \\\"hi\\\"\\\\\"");
+    }
+
+    #[test]
+    fn timestamp_time_zone_unit_test() {
+        let t = NaiveDateTime::parse_from_str("2022-03-13 02:30:00", crate::URL_DATE_TIME_FORMAT)
+            .unwrap();
+        let central = central();
+        let mut clock = LocalClock::new(&central);
+        assert_eq!(timestamp(&mut clock, t).unwrap(), 1_647_160_200_000_000_000);
+    }
+
+    fn production(values: &[(&str, f64)]) -> Vec<Series> {
+        vec![Series {
+            name: "Production".to_string(),
+            serial: None,
+            unit: "W".to_string(),
+            samples: values
+                .iter()
+                .map(|(t, v)| crate::Sample {
+                    timestamp: NaiveDateTime::parse_from_str(t, crate::URL_DATE_TIME_FORMAT)
+                        .unwrap(),
+                    value: Some(*v),
+                })
+                .collect(),
+        }]
+    }
+
+    #[test]
+    fn daylight_saving_line_protocol_unit_test() {
+        let lp = LineProtocol::new("1", Chicago);
+
+        // 01:30 happens twice when daylight saving ends, first at UTC-5 and then
+        // at UTC-6.
+        let series = production(&[
+            ("2022-11-06 01:30:00", 1.0),
+            ("2022-11-06 01:30:00", 2.0),
+            ("2022-11-06 02:00:00", 3.0),
+        ]);
+        assert_eq!(
+            to_string(|out| lp.write_series(out, "power", &series)),
+            "power,site_id=1,meter_type=Production,unit=W value=1 1667716200000000000\n\
+             power,site_id=1,meter_type=Production,unit=W value=2 1667719800000000000\n\
+             power,site_id=1,meter_type=Production,unit=W value=3 1667721600000000000\n"
+        );
+
+        // 02:30 does not exist when daylight saving starts.
+        let series = production(&[("2022-03-13 02:30:00", 1.0)]);
+        let e = lp
+            .write_series(&mut Vec::new(), "power", &series)
+            .unwrap_err();
+        assert!(matches!(e.kind(), Kind::InvalidTimestamp(_)));
+    }
+
+    #[test]
+    fn non_finite_line_protocol_unit_test() {
+        let lp = LineProtocol::new("1", chrono::Utc);
+        let series = production(&[
+            ("2022-01-01 00:00:00", f64::NAN),
+            ("2022-01-01 00:15:00", f64::INFINITY),
+            ("2022-01-01 00:30:00", 5.0),
+        ]);
+        assert_eq!(
+            to_string(|out| lp.write_series(out, "power", &series)),
+            "power,site_id=1,meter_type=Production,unit=W value=5 1640997000000000000\n"
+        );
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<LineProtocol<FixedOffset>>();
+        is_normal::<InfluxDb>();
+    }
+}
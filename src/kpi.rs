@@ -0,0 +1,406 @@
+//! Module for the key performance indicators of a site with a consumption meter:
+//! how much of its production it uses itself, how much of its consumption it
+//! covers itself, and the reverse of both.
+//!
+//! The indicators are ratios of the five meters of the energy details and power
+//! details requests. Over a period they are the ratio of the totals, not the
+//! average of the ratios of the intervals, so a quiet interval weighs less than
+//! a busy one. Only intervals both meters of a ratio reported count towards the
+//! totals, so a gap in one meter does not skew the ratio.
+
+use crate::resample::Aggregation;
+use crate::time_series::kwh_factor;
+use crate::windows::period_start;
+use crate::{Error, Kind, MeterType, Series, TimeUnit};
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+
+/// Values of one meter per timestamp.
+pub(crate) type MeterValues = BTreeMap<NaiveDateTime, Option<f64>>;
+
+/// One indicator of one interval or period.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kpi {
+    /// The ratio, usually between 0 and 1
+    Ratio(f64),
+
+    /// The site has no such meter, e.g. no consumption meter is installed
+    MissingMeter(MeterType),
+
+    /// The meter did not report a value for the interval or period
+    MissingValue,
+
+    /// The denominator is zero, e.g. no production at night
+    ZeroDenominator,
+}
+
+/// The indicators of one interval or period.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SiteKpis {
+    /// Site local start of the interval or period
+    pub timestamp: NaiveDateTime,
+
+    /// Share of production consumed on site: SelfConsumption / Production
+    pub self_consumption_ratio: Kpi,
+
+    /// Share of consumption covered by production, also called autarky:
+    /// SelfConsumption / Consumption
+    pub self_sufficiency: Kpi,
+
+    /// Share of production exported to the grid: FeedIn / Production
+    pub export_ratio: Kpi,
+
+    /// Share of consumption bought from the grid: Purchased / Consumption
+    pub grid_dependency: Kpi,
+}
+
+impl Kpi {
+    /// The ratio, if there is one.
+    #[must_use]
+    pub fn ratio(&self) -> Option<f64> {
+        match self {
+            Kpi::Ratio(r) => Some(*r),
+            _ => None,
+        }
+    }
+
+    /// The ratio of two totals.
+    fn ratio_of(numerator: f64, denominator: f64) -> Self {
+        if denominator == 0.0 {
+            Kpi::ZeroDenominator
+        } else {
+            Kpi::Ratio(numerator / denominator)
+        }
+    }
+
+    /// The ratio of two meters at one timestamp.
+    fn of(
+        numerator: &(MeterType, Option<MeterValues>),
+        denominator: &(MeterType, Option<MeterValues>),
+        timestamp: NaiveDateTime,
+    ) -> Self {
+        let value = |(meter_type, values): &(MeterType, Option<MeterValues>)| match values {
+            None => Err(Kpi::MissingMeter(meter_type.clone())),
+            Some(values) => values
+                .get(&timestamp)
+                .copied()
+                .flatten()
+                .ok_or(Kpi::MissingValue),
+        };
+
+        match (value(numerator), value(denominator)) {
+            (Err(e), _) | (_, Err(e)) => e,
+            (Ok(n), Ok(d)) => Kpi::ratio_of(n, d),
+        }
+    }
+
+    /// The ratio of two meters over every period, from the totals of the
+    /// intervals both meters reported.
+    fn per_period(
+        numerator: &(MeterType, Option<MeterValues>),
+        denominator: &(MeterType, Option<MeterValues>),
+        time_unit: &TimeUnit,
+    ) -> Result<BTreeMap<NaiveDateTime, (f64, f64)>, Self> {
+        let (Some(n), Some(d)) = (&numerator.1, &denominator.1) else {
+            let missing = if numerator.1.is_none() {
+                &numerator.0
+            } else {
+                &denominator.0
+            };
+            return Err(Kpi::MissingMeter(missing.clone()));
+        };
+
+        let mut totals: BTreeMap<NaiveDateTime, (f64, f64)> = BTreeMap::new();
+        for (timestamp, n) in n {
+            if let (Some(n), Some(Some(d))) = (n, d.get(timestamp)) {
+                let total = totals
+                    .entry(period_start(*timestamp, time_unit))
+                    .or_default();
+                total.0 += n;
+                total.1 += d;
+            }
+        }
+        Ok(totals)
+    }
+
+    /// The ratio of a period, see [per_period](Self::per_period).
+    fn for_period(
+        totals: &Result<BTreeMap<NaiveDateTime, (f64, f64)>, Kpi>,
+        period: NaiveDateTime,
+    ) -> Self {
+        match totals {
+            Err(e) => e.clone(),
+            Ok(totals) => match totals.get(&period) {
+                Some((n, d)) => Kpi::ratio_of(*n, *d),
+                None => Kpi::MissingValue,
+            },
+        }
+    }
+}
+
+/// The values of a meter per timestamp, in Wh for energy (or W for power). Values
+/// of the meter at the same timestamp, e.g. in the hour repeated when daylight
+/// saving ends, are combined as [Aggregation::for_unit] gives for their unit: energy
+/// is added up and power averaged.
+///
+/// # Returns
+/// None if none of the series is of the meter type.
+pub(crate) fn meter_values(series: &[Series], meter_type: &MeterType) -> Option<MeterValues> {
+    let name = meter_type.to_string();
+    let mut totals: Option<BTreeMap<NaiveDateTime, (Aggregation, f64, u32)>> = None;
+    for s in series.iter().filter(|s| s.name == name) {
+        let totals = totals.get_or_insert_with(BTreeMap::new);
+        let aggregation = Aggregation::for_unit(&s.unit);
+        let factor = kwh_factor(&s.unit) * 1000.0;
+        for sample in &s.samples {
+            let total = totals
+                .entry(sample.timestamp)
+                .or_insert((aggregation, 0.0, 0));
+            if let Some(v) = sample.value {
+                total.1 += v * factor;
+                total.2 += 1;
+            }
+        }
+    }
+
+    totals.map(|totals| {
+        totals
+            .into_iter()
+            .map(|(timestamp, (aggregation, total, count))| {
+                let value = match (aggregation, count) {
+                    (_, 0) => None,
+                    (Aggregation::Sum, _) => Some(total),
+                    (Aggregation::Mean, _) => Some(total / f64::from(count)),
+                };
+                (timestamp, value)
+            })
+            .collect()
+    })
+}
+
+/// The values of the five meters of the indicators.
+fn meters(series: &[Series]) -> [(MeterType, Option<MeterValues>); 5] {
+    [
+        MeterType::Production,
+        MeterType::Consumption,
+        MeterType::SelfConsumption,
+        MeterType::FeedIn,
+        MeterType::Purchased,
+    ]
+    .map(|meter_type| {
+        let values = meter_values(series, &meter_type);
+        (meter_type, values)
+    })
+}
+
+/// The indicators of every interval.
+///
+/// # Arguments
+///
+/// * `series` - energy details or power details series, e.g. from
+///   [SiteEnergyDetailedResp](crate::SiteEnergyDetailedResp) or
+///   [SitePowerDetailedResp](crate::SitePowerDetailedResp)
+///
+/// # Returns
+/// The indicators of every timestamp any of the meters has, in order.
+#[must_use]
+pub fn interval_kpis(series: &[Series]) -> Vec<SiteKpis> {
+    let meters = meters(series);
+    let mut timestamps: Vec<NaiveDateTime> = meters
+        .iter()
+        .filter_map(|(_, values)| values.as_ref())
+        .flat_map(|values| values.keys().copied())
+        .collect();
+    timestamps.sort_unstable();
+    timestamps.dedup();
+
+    let [production, consumption, self_consumption, feed_in, purchased] = &meters;
+
+    timestamps
+        .into_iter()
+        .map(|timestamp| SiteKpis {
+            timestamp,
+            self_consumption_ratio: Kpi::of(self_consumption, production, timestamp),
+            self_sufficiency: Kpi::of(self_consumption, consumption, timestamp),
+            export_ratio: Kpi::of(feed_in, production, timestamp),
+            grid_dependency: Kpi::of(purchased, consumption, timestamp),
+        })
+        .collect()
+}
+
+/// The indicators of every period.
+///
+/// # Arguments
+///
+/// * `series` - energy details or power details series, see [interval_kpis]
+/// * `time_unit` - granularity of the periods, e.g. [TimeUnit::Month]
+///
+/// # Returns
+/// The indicators of every period any of the meters has timestamps in, in order.
+/// An interval either meter of a ratio did not report is left out of both of its
+/// totals; a period without any interval both reported has [Kpi::MissingValue].
+///
+/// # Errors
+/// Returns [Kind::InvalidArgument] if the granularity is unknown.
+pub fn period_kpis(series: &[Series], time_unit: &TimeUnit) -> Result<Vec<SiteKpis>, Error> {
+    if let TimeUnit::Unknown(unit) = time_unit {
+        return Err(Error::new(Kind::InvalidArgument(format!(
+            "cannot total over unknown time unit {unit}"
+        ))));
+    }
+    let meters = meters(series);
+    let mut periods: Vec<NaiveDateTime> = meters
+        .iter()
+        .filter_map(|(_, values)| values.as_ref())
+        .flat_map(|values| values.keys().map(|t| period_start(*t, time_unit)))
+        .collect();
+    periods.sort_unstable();
+    periods.dedup();
+
+    let [production, consumption, self_consumption, feed_in, purchased] = &meters;
+    let self_consumption_ratio = Kpi::per_period(self_consumption, production, time_unit);
+    let self_sufficiency = Kpi::per_period(self_consumption, consumption, time_unit);
+    let export_ratio = Kpi::per_period(feed_in, production, time_unit);
+    let grid_dependency = Kpi::per_period(purchased, consumption, time_unit);
+
+    Ok(periods
+        .into_iter()
+        .map(|timestamp| SiteKpis {
+            timestamp,
+            self_consumption_ratio: Kpi::for_period(&self_consumption_ratio, timestamp),
+            self_sufficiency: Kpi::for_period(&self_sufficiency, timestamp),
+            export_ratio: Kpi::for_period(&export_ratio, timestamp),
+            grid_dependency: Kpi::for_period(&grid_dependency, timestamp),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, SiteEnergyDetailedResp, SitePowerDetailedResp, TimeSeries};
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::URL_DATE_TIME_FORMAT).unwrap()
+    }
+
+    fn series() -> Vec<Series> {
+        let resp: SiteEnergyDetailedResp = serde_json::from_str(
+            r#"{"energyDetails": {"timeUnit": "DAY", "unit": "Wh", "meters": [
+                {"type": "Production", "values": [
+                    {"date": "2022-06-01 00:00:00", "value": 10000},
+                    {"date": "2022-06-02 00:00:00", "value": 0},
+                    {"date": "2022-06-03 00:00:00", "value": 30000}]},
+                {"type": "Consumption", "values": [
+                    {"date": "2022-06-01 00:00:00", "value": 8000},
+                    {"date": "2022-06-02 00:00:00", "value": 4000},
+                    {"date": "2022-06-03 00:00:00"}]},
+                {"type": "SelfConsumption", "values": [
+                    {"date": "2022-06-01 00:00:00", "value": 6000},
+                    {"date": "2022-06-02 00:00:00", "value": 0},
+                    {"date": "2022-06-03 00:00:00", "value": 9000}]},
+                {"type": "FeedIn", "values": [
+                    {"date": "2022-06-01 00:00:00", "value": 4000},
+                    {"date": "2022-06-02 00:00:00", "value": 0},
+                    {"date": "2022-06-03 00:00:00", "value": 21000}]}
+            ]}}"#,
+        )
+        .unwrap();
+        resp.energy_details.time_series().unwrap()
+    }
+
+    #[test]
+    fn interval_kpis_unit_test() {
+        let kpis = interval_kpis(&series());
+        assert_eq!(kpis.len(), 3);
+
+        assert_eq!(kpis[0].timestamp, at("2022-06-01 00:00:00"));
+        assert_eq!(kpis[0].self_consumption_ratio, Kpi::Ratio(0.6));
+        assert_eq!(kpis[0].self_sufficiency, Kpi::Ratio(0.75));
+        assert_eq!(kpis[0].export_ratio, Kpi::Ratio(0.4));
+        assert_eq!(
+            kpis[0].grid_dependency,
+            Kpi::MissingMeter(MeterType::Purchased)
+        );
+
+        assert_eq!(kpis[1].self_consumption_ratio, Kpi::ZeroDenominator);
+        assert_eq!(kpis[1].self_sufficiency, Kpi::Ratio(0.0));
+
+        assert_eq!(kpis[2].self_sufficiency, Kpi::MissingValue);
+        assert_eq!(kpis[2].export_ratio.ratio(), Some(0.7));
+    }
+
+    #[test]
+    fn period_kpis_unit_test() {
+        let kpis = period_kpis(&series(), &TimeUnit::Month).unwrap();
+        assert_eq!(kpis.len(), 1);
+        assert_eq!(kpis[0].timestamp, at("2022-06-01 00:00:00"));
+        // 15 kWh of 40 kWh self-consumed.
+        assert_eq!(kpis[0].self_consumption_ratio, Kpi::Ratio(0.375));
+        // Consumption was not reported on the 3rd, so its 9 kWh self-consumption
+        // is left out: 6 kWh of 12 kWh.
+        assert_eq!(kpis[0].self_sufficiency, Kpi::Ratio(0.5));
+        assert_eq!(kpis[0].export_ratio, Kpi::Ratio(0.625));
+        assert_eq!(
+            kpis[0].grid_dependency,
+            Kpi::MissingMeter(MeterType::Purchased)
+        );
+
+        let kpis = period_kpis(&series(), &TimeUnit::Day).unwrap();
+        assert_eq!(kpis.len(), 3);
+        assert_eq!(kpis[1].self_consumption_ratio, Kpi::ZeroDenominator);
+        assert_eq!(kpis[2].self_sufficiency, Kpi::MissingValue);
+
+        let e = period_kpis(&series(), &TimeUnit::Unknown("DECADE".to_string())).unwrap_err();
+        assert!(matches!(e.kind(), Kind::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn power_kpis_unit_test() {
+        let resp: SitePowerDetailedResp = serde_json::from_str(
+            r#"{"powerDetails": {"timeUnit": "QUARTER_OF_AN_HOUR", "unit": "W", "meters": [
+                {"type": "Consumption", "values": [
+                    {"date": "2022-06-01 12:00:00", "value": 2000}]},
+                {"type": "Purchased", "values": [
+                    {"date": "2022-06-01 12:00:00", "value": 500}]}
+            ]}}"#,
+        )
+        .unwrap();
+        let kpis = interval_kpis(&resp.power_details.time_series().unwrap());
+        assert_eq!(kpis[0].grid_dependency, Kpi::Ratio(0.25));
+        assert_eq!(
+            kpis[0].self_consumption_ratio,
+            Kpi::MissingMeter(MeterType::SelfConsumption)
+        );
+    }
+
+    #[test]
+    fn power_repeated_timestamp_unit_test() {
+        // 02:00 occurs twice when daylight saving ends, both averaged rather than
+        // added up as energy would be.
+        let resp: SitePowerDetailedResp = serde_json::from_str(
+            r#"{"powerDetails": {"timeUnit": "QUARTER_OF_AN_HOUR", "unit": "W", "meters": [
+                {"type": "Consumption", "values": [
+                    {"date": "2022-10-30 02:00:00", "value": 2000},
+                    {"date": "2022-10-30 02:00:00", "value": 3000}]},
+                {"type": "Purchased", "values": [
+                    {"date": "2022-10-30 02:00:00", "value": 500},
+                    {"date": "2022-10-30 02:00:00", "value": 1500}]}
+            ]}}"#,
+        )
+        .unwrap();
+        let series = resp.power_details.time_series().unwrap();
+
+        let consumption = meter_values(&series, &MeterType::Consumption).unwrap();
+        assert_eq!(consumption[&at("2022-10-30 02:00:00")], Some(2500.0));
+        let kpis = interval_kpis(&series);
+        assert_eq!(kpis.len(), 1);
+        assert_eq!(kpis[0].grid_dependency, Kpi::Ratio(0.4));
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<Kpi>();
+        is_normal::<SiteKpis>();
+    }
+}
@@ -0,0 +1,171 @@
+//! Module for tolerant parsing of the numeric values in SolarEdge server monitoring API responses.
+//!
+//! The API is not consistent about value types. The same field can come back as a
+//! number, as a number inside a string, or as null. These functions are used with
+//! `#[serde(deserialize_with = "...")]` to accept all of them. Values that are not
+//! finite, such as "NaN" or "inf", are rejected.
+
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Numeric types that can be parsed leniently.
+pub(crate) trait Lenient {
+    fn from_f64(v: f64) -> Self;
+}
+
+impl Lenient for f32 {
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_f64(v: f64) -> Self {
+        v as f32
+    }
+}
+
+impl Lenient for f64 {
+    fn from_f64(v: f64) -> Self {
+        v
+    }
+}
+
+struct NumberVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Lenient> Visitor<'de> for NumberVisitor<T> {
+    type Value = Option<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a number, a numeric string or null")
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Some(T::from_f64(v)))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Some(T::from_f64(v as f64)))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Some(T::from_f64(v as f64)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let trimmed = v.trim();
+        if trimmed.is_empty() {
+            return Ok(None);
+        }
+        match trimmed.parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(Some(T::from_f64(n))),
+            _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+        }
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        d.deserialize_any(self)
+    }
+}
+
+/// Deserialize a number that may be sent as a number or a numeric string.
+pub(crate) fn number<'de, D, T>(d: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Lenient,
+{
+    match d.deserialize_any(NumberVisitor(PhantomData))? {
+        Some(n) => Ok(n),
+        None => Err(de::Error::invalid_type(Unexpected::Unit, &"a number")),
+    }
+}
+
+/// Deserialize an optional number that may be sent as a number, a numeric string,
+/// an empty string or null. Fields using this also need `#[serde(default)]` so a
+/// missing field is read as `None`.
+pub(crate) fn option_number<'de, D, T>(d: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Lenient,
+{
+    d.deserialize_any(NumberVisitor(PhantomData))
+}
+
+/// Serialize an optional number as a numeric string, for fields the API documents
+/// as strings. Whole numbers keep their decimal point, e.g. "9800.0".
+#[cfg(feature = "serialize")]
+#[allow(clippy::ref_option)] // serde passes the field by reference
+pub(crate) fn option_number_string<S, T>(v: &Option<T>, s: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    T: fmt::Debug,
+{
+    match v {
+        Some(n) => s.serialize_str(&format!("{n:?}")),
+        None => s.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Sample {
+        #[serde(deserialize_with = "number")]
+        required: f32,
+
+        #[serde(default, deserialize_with = "option_number")]
+        optional: Option<f64>,
+    }
+
+    #[test]
+    fn number_unit_test() {
+        let s: Sample = serde_json::from_str(r#"{"required": 1.5, "optional": 2}"#).unwrap();
+        assert_eq!(
+            s,
+            Sample {
+                required: 1.5,
+                optional: Some(2.0)
+            }
+        );
+
+        let s: Sample =
+            serde_json::from_str(r#"{"required": " 9800.0", "optional": "-3.25"}"#).unwrap();
+        assert_eq!(
+            s,
+            Sample {
+                required: 9800.0,
+                optional: Some(-3.25)
+            }
+        );
+    }
+
+    #[test]
+    fn option_number_unit_test() {
+        let s: Sample = serde_json::from_str(r#"{"required": 0, "optional": null}"#).unwrap();
+        assert_eq!(s.optional, None);
+
+        let s: Sample = serde_json::from_str(r#"{"required": 0, "optional": ""}"#).unwrap();
+        assert_eq!(s.optional, None);
+
+        let s: Sample = serde_json::from_str(r#"{"required": 0}"#).unwrap();
+        assert_eq!(s.optional, None);
+    }
+
+    #[test]
+    fn number_error_unit_test() {
+        assert!(serde_json::from_str::<Sample>(r#"{"required": null}"#).is_err());
+        assert!(serde_json::from_str::<Sample>(r#"{"required": "n/a"}"#).is_err());
+        assert!(serde_json::from_str::<Sample>(r#"{"required": true}"#).is_err());
+        assert!(serde_json::from_str::<Sample>(r#"{"required": "NaN"}"#).is_err());
+        assert!(serde_json::from_str::<Sample>(r#"{"required": 0, "optional": "inf"}"#).is_err());
+    }
+}
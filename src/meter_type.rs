@@ -4,6 +4,12 @@ use serde::Deserialize;
 
 /// Meters supported by SolarEdge.
 #[derive(Clone, Deserialize, Debug, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize),
+    serde(into = "String")
+)]
+#[serde(from = "String")]
 pub enum MeterType {
     /// Solar energy produced.
     #[default]
@@ -20,16 +26,39 @@ pub enum MeterType {
 
     /// Energy purchased from grid.
     Purchased,
+
+    /// A meter type not listed in the API documentation.
+    Unknown(String),
+}
+
+impl From<String> for MeterType {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "Production" => MeterType::Production,
+            "Consumption" => MeterType::Consumption,
+            "SelfConsumption" => MeterType::SelfConsumption,
+            "FeedIn" => MeterType::FeedIn,
+            "Purchased" => MeterType::Purchased,
+            _ => MeterType::Unknown(s),
+        }
+    }
+}
+
+impl From<MeterType> for String {
+    fn from(m: MeterType) -> Self {
+        m.to_string()
+    }
 }
 
 impl std::fmt::Display for MeterType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match *self {
+        match self {
             MeterType::Production => write!(f, "Production"),
             MeterType::Consumption => write!(f, "Consumption"),
             MeterType::SelfConsumption => write!(f, "SelfConsumption"),
             MeterType::FeedIn => write!(f, "FeedIn"),
             MeterType::Purchased => write!(f, "Purchased"),
+            MeterType::Unknown(s) => write!(f, "{s}"),
         }
     }
 }
@@ -45,6 +74,15 @@ mod tests {
         assert_eq!(format!("{t}"), "Production");
     }
 
+    #[test]
+    fn meter_type_from_unit_test() {
+        assert_eq!(MeterType::from("FeedIn".to_string()), MeterType::FeedIn);
+        assert_eq!(
+            MeterType::from("StorageDischarge".to_string()),
+            MeterType::Unknown("StorageDischarge".to_string())
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<MeterType>();
@@ -1,10 +1,12 @@
 //! Module for holding values for a specified meter type in SolarEdge server monitoring API responses.
 
+use crate::extras::impl_unknown_fields;
 use crate::{DateValue, MeterType};
 use serde::Deserialize;
 
 /// Values for the meter type over a range of dates.
 #[derive(Clone, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct MeterValue {
     /// The meter type of the associated values.
     #[serde(rename = "type")]
@@ -12,8 +14,15 @@ pub struct MeterValue {
 
     /// Meter readings for each date.
     pub values: Vec<DateValue>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(MeterValue { values: "values" });
+
 #[cfg(test)]
 mod tests {
     use super::*;
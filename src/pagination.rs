@@ -0,0 +1,152 @@
+//! Module for walking the pages of list requests (sites, accounts) that the
+//! SolarEdge server monitoring API returns at most 100 entries at a time.
+
+use crate::Error;
+use std::collections::VecDeque;
+
+/// Largest page size accepted by the list requests.
+pub(crate) const MAX_PAGE_SIZE: u16 = 100;
+
+type FetchPage<'a, T> = Box<dyn FnMut(u16, u16) -> Result<(u16, Vec<T>), Error> + 'a>;
+
+/// Iterator over every entry of a list request, fetching further pages from
+/// SolarEdge as needed.
+///
+/// Iteration stops once the total count reported by the server has been reached,
+/// or a page comes back short or empty. If a page fails to load, the error is
+/// yielded and iteration stops.
+pub struct Paginated<'a, T> {
+    fetch: FetchPage<'a, T>,
+    page_size: u16,
+    next_index: u16,
+    buffer: VecDeque<T>,
+    done: bool,
+}
+
+impl<'a, T> Paginated<'a, T> {
+    /// `fetch` is given the start index and size of a page and returns the total
+    /// count of entries reported by the server along with the entries of the page.
+    pub(crate) fn new<F>(first_index: u16, page_size: u16, fetch: F) -> Self
+    where
+        F: FnMut(u16, u16) -> Result<(u16, Vec<T>), Error> + 'a,
+    {
+        Paginated {
+            fetch: Box::new(fetch),
+            page_size,
+            next_index: first_index,
+            buffer: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn fetch_next_page(&mut self) -> Result<(), Error> {
+        let (count, entries) = (self.fetch)(self.next_index, self.page_size)?;
+
+        let received = u16::try_from(entries.len()).unwrap_or(u16::MAX);
+        if received < self.page_size {
+            self.done = true;
+        }
+        match self.next_index.checked_add(received) {
+            Some(next) if next < count => self.next_index = next,
+            _ => self.done = true,
+        }
+
+        self.buffer.extend(entries);
+        Ok(())
+    }
+}
+
+impl<T> Iterator for Paginated<'_, T> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() && !self.done {
+            if let Err(e) = self.fetch_next_page() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
+}
+
+impl<T> std::fmt::Debug for Paginated<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Paginated")
+            .field("page_size", &self.page_size)
+            .field("next_index", &self.next_index)
+            .field("buffered", &self.buffer.len())
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Kind;
+    use std::cell::RefCell;
+
+    fn server(total: u16) -> impl Fn(u16, u16) -> Result<(u16, Vec<u16>), Error> {
+        move |start, size| {
+            let end = total.min(start.saturating_add(size));
+            Ok((total, (start..end).collect()))
+        }
+    }
+
+    #[test]
+    fn paginated_walks_all_pages_unit_test() {
+        let calls = RefCell::new(Vec::new());
+        let fetch = server(250);
+        let all = Paginated::new(0, 100, |start, size| {
+            calls.borrow_mut().push(start);
+            fetch(start, size)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+        assert_eq!(all, (0..250).collect::<Vec<_>>());
+        assert_eq!(*calls.borrow(), vec![0, 100, 200]);
+    }
+
+    #[test]
+    fn paginated_stops_at_count_unit_test() {
+        let calls = RefCell::new(0);
+        let fetch = server(200);
+        let all = Paginated::new(0, 100, |start, size| {
+            *calls.borrow_mut() += 1;
+            fetch(start, size)
+        })
+        .count();
+
+        assert_eq!(all, 200);
+        assert_eq!(*calls.borrow(), 2);
+    }
+
+    #[test]
+    fn paginated_stops_on_short_page_unit_test() {
+        let calls = RefCell::new(0);
+        let all = Paginated::new(10, 5, |start, _size| {
+            *calls.borrow_mut() += 1;
+            // Server claims more entries than it actually returns.
+            Ok((1000, (start..start + 3).collect::<Vec<u16>>()))
+        })
+        .count();
+
+        assert_eq!(all, 3);
+        assert_eq!(*calls.borrow(), 1);
+    }
+
+    #[test]
+    fn paginated_error_unit_test() {
+        let mut pages = Paginated::<u16>::new(0, 100, |_, _| {
+            Err(Error::new(Kind::HttpErrorStatus(
+                "Forbidden".to_string(),
+                String::new(),
+            )))
+        });
+
+        assert!(pages.next().unwrap().is_err());
+        assert!(pages.next().is_none());
+    }
+}
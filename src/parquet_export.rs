@@ -0,0 +1,181 @@
+//! Module for converting time series to Apache Arrow record batches and writing
+//! them as Parquet files. Enabled by the `parquet` feature.
+//!
+//! Every batch has the schema returned by [time_series_schema]: one row per value,
+//! with the site id, meter type (or telemetry field), device serial and unit
+//! alongside. Values the server did not report are stored as nulls.
+
+use crate::{Error, Series};
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, TimestampSecondArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit as ArrowTimeUnit};
+use parquet::arrow::ArrowWriter;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Schema of the record batches made by [to_record_batch].
+///
+/// * `timestamp` - site local date and time, without a time zone, in seconds
+/// * `site_id` - the site the values belong to
+/// * `meter_type` - meter type (e.g. "Production") or telemetry field
+///   (e.g. "totalActivePower")
+/// * `serial` - serial number of the reporting device, null if not known
+/// * `value` - the value, null if the server did not report one
+/// * `unit` - unit of the value, e.g. "Wh"
+#[must_use]
+pub fn time_series_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(ArrowTimeUnit::Second, None),
+            false,
+        ),
+        Field::new("site_id", DataType::Utf8, false),
+        Field::new("meter_type", DataType::Utf8, false),
+        Field::new("serial", DataType::Utf8, true),
+        Field::new("value", DataType::Float64, true),
+        Field::new("unit", DataType::Utf8, false),
+    ]))
+}
+
+/// Convert time series to a record batch with one row per value.
+///
+/// # Arguments
+///
+/// * `site_id` - the site the series belong to
+/// * `series` - the series, e.g. from [TimeSeries::time_series](crate::TimeSeries::time_series)
+///
+/// # Returns
+/// A record batch with the [time_series_schema].
+///
+/// # Errors
+/// Returns [Kind::ParquetError](crate::Kind::ParquetError) if the batch cannot be built.
+pub fn to_record_batch(site_id: &str, series: &[Series]) -> Result<RecordBatch, Error> {
+    let rows = series
+        .iter()
+        .flat_map(|s| s.samples.iter().map(move |v| (s, v)));
+
+    let timestamps: TimestampSecondArray = rows
+        .clone()
+        .map(|(_, v)| v.timestamp.and_utc().timestamp())
+        .collect::<Vec<_>>()
+        .into();
+    let site_ids: StringArray = rows.clone().map(|_| Some(site_id)).collect();
+    let meter_types: StringArray = rows.clone().map(|(s, _)| Some(s.name.as_str())).collect();
+    let serials: StringArray = rows.clone().map(|(s, _)| s.serial.as_deref()).collect();
+    let values: Float64Array = rows.clone().map(|(_, v)| v.value).collect();
+    let units: StringArray = rows.map(|(s, _)| Some(s.unit.as_str())).collect();
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(timestamps),
+        Arc::new(site_ids),
+        Arc::new(meter_types),
+        Arc::new(serials),
+        Arc::new(values),
+        Arc::new(units),
+    ];
+    Ok(RecordBatch::try_new(time_series_schema(), columns)?)
+}
+
+/// Write record batches as a Parquet file.
+///
+/// # Arguments
+///
+/// * `out` - where to write the file
+/// * `batches` - batches with the [time_series_schema], e.g. one per site or per day
+///
+/// # Returns
+/// The writer, after the file has been completed.
+///
+/// # Errors
+/// Returns [Kind::ParquetError](crate::Kind::ParquetError) if a batch does not
+/// match the schema or writing fails.
+pub fn write_parquet<W: Write + Send>(out: W, batches: &[RecordBatch]) -> Result<W, Error> {
+    let mut writer = ArrowWriter::try_new(out, time_series_schema(), None)?;
+    for batch in batches {
+        writer.write(batch)?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SiteEnergyDetailedResp, TimeSeries};
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn batch() -> RecordBatch {
+        let resp: SiteEnergyDetailedResp = serde_json::from_str(
+            r#"{"energyDetails": {"timeUnit": "QUARTER_OF_AN_HOUR", "unit": "Wh", "meters": [
+                {"type": "Production", "values": [
+                    {"date": "2022-01-01 12:00:00", "value": 250.5},
+                    {"date": "2022-01-01 12:15:00"}]},
+                {"type": "FeedIn", "values": [
+                    {"date": "2022-01-01 12:00:00", "value": 100}]}
+            ]}}"#,
+        )
+        .unwrap();
+        to_record_batch("1234", &resp.energy_details.time_series().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn record_batch_unit_test() {
+        let batch = batch();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.schema(), time_series_schema());
+
+        let values = batch
+            .column(4)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert!(values.is_null(1));
+        assert!((values.value(0) - 250.5).abs() < f64::EPSILON);
+
+        let timestamps = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<TimestampSecondArray>()
+            .unwrap();
+        assert_eq!(
+            timestamps.value_as_datetime(1).unwrap().to_string(),
+            "2022-01-01 12:15:00"
+        );
+
+        let meter_types = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(meter_types.value(2), "FeedIn");
+        assert!(batch.column(3).is_null(0));
+    }
+
+    #[test]
+    fn parquet_round_trip_unit_test() {
+        let path = std::env::temp_dir().join(format!(
+            "se_ms_api_parquet_unit_test_{}.parquet",
+            std::process::id()
+        ));
+        let file = std::fs::File::create(&path).unwrap();
+        write_parquet(file, &[batch(), batch()]).unwrap();
+
+        let file = std::fs::File::open(&path).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 6);
+        assert_eq!(batches[0].schema(), time_series_schema());
+        assert_eq!(
+            batches
+                .iter()
+                .map(|b| b.column(4).null_count())
+                .sum::<usize>(),
+            2
+        );
+    }
+}
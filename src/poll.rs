@@ -0,0 +1,42 @@
+//! Module for spacing out the requests of a poller, such as a metrics exporter or
+//! an MQTT bridge, so it stays within the daily request limit of its api key.
+
+use std::time::Duration;
+
+/// Time between polls that keeps a poller within a daily request budget.
+///
+/// # Arguments
+///
+/// * `daily_budget` - requests the poller may send per day, at most
+///   [DAILY_REQUEST_LIMIT](crate::DAILY_REQUEST_LIMIT) unless the api key has a
+///   higher limit
+/// * `requests_per_poll` - requests sent on every poll
+///
+/// # Returns
+/// The interval, rounded up to whole seconds, or None if the budget does not allow a
+/// single poll per day.
+#[must_use]
+pub fn poll_interval(daily_budget: u32, requests_per_poll: u32) -> Option<Duration> {
+    if daily_budget < requests_per_poll || requests_per_poll == 0 {
+        return None;
+    }
+    let seconds = (86_400 * u64::from(requests_per_poll)).div_ceil(u64::from(daily_budget));
+    Some(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DAILY_REQUEST_LIMIT;
+
+    #[test]
+    fn poll_interval_unit_test() {
+        assert_eq!(
+            poll_interval(DAILY_REQUEST_LIMIT, 2),
+            Some(Duration::from_secs(576))
+        );
+        assert_eq!(poll_interval(7, 1), Some(Duration::from_secs(12_343)));
+        assert_eq!(poll_interval(2, 3), None);
+        assert_eq!(poll_interval(300, 0), None);
+    }
+}
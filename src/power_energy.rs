@@ -0,0 +1,308 @@
+//! Module for deriving energy from power series and checking it against the
+//! energy the server reports.
+//!
+//! The power requests return the average power of each quarter of an hour, so
+//! the energy of an interval is its power times its length. Summed over a period
+//! this should match the energy requests for the same period; a large difference
+//! points at corrupted or incomplete data on one side.
+
+use crate::resample::Resampler;
+use crate::time_series::kwh_factor;
+use crate::{DataEndpoint, Error, Sample, Series, SolaredgeCredentials, TimeUnit};
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use std::collections::BTreeMap;
+
+/// Length of an interval of the power requests.
+const POWER_INTERVAL_MINUTES: i64 = 15;
+
+/// Settings for comparing integrated power with reported energy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnergyCheck {
+    /// Granularity of the periods that are compared
+    pub time_unit: TimeUnit,
+
+    /// Largest accepted difference, as a fraction of the reported energy
+    pub relative_tolerance: f64,
+
+    /// Largest accepted difference in Wh, regardless of the relative tolerance
+    pub absolute_tolerance: f64,
+}
+
+/// Integrated and reported energy of one series over one period.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnergyComparison {
+    /// Name of the series, e.g. "Production"
+    pub name: String,
+
+    /// Site local start of the period
+    pub timestamp: NaiveDateTime,
+
+    /// Energy integrated from power, in Wh. None if there was no power value.
+    pub integrated: Option<f64>,
+
+    /// Energy reported by the server, in Wh. None if there was no energy value.
+    pub reported: Option<f64>,
+
+    /// Whether both are known and differ by more than the tolerances
+    pub discrepancy: bool,
+}
+
+/// Integrate a power series into the energy of each interval.
+///
+/// # Arguments
+///
+/// * `power` - average power of each interval, e.g. from
+///   [SitePowerResp](crate::SitePowerResp) or
+///   [SitePowerDetailedResp](crate::SitePowerDetailedResp)
+/// * `interval` - length of an interval, None for the quarter of an hour of the
+///   power requests
+///
+/// # Returns
+/// The energy of each interval, with the same timestamps, in the matching energy
+/// unit (e.g. "Wh" for "W"). Missing power values stay missing.
+#[must_use]
+pub fn integrate(power: &Series, interval: Option<TimeDelta>) -> Series {
+    let interval = interval.unwrap_or(TimeDelta::minutes(POWER_INTERVAL_MINUTES));
+    #[allow(clippy::cast_precision_loss)]
+    let hours = interval.num_seconds() as f64 / 3600.0;
+
+    Series {
+        name: power.name.clone(),
+        serial: power.serial.clone(),
+        unit: format!("{}h", power.unit),
+        samples: power
+            .samples
+            .iter()
+            .map(|s| Sample {
+                timestamp: s.timestamp,
+                value: s.value.map(|v| v * hours),
+            })
+            .collect(),
+    }
+}
+
+impl EnergyComparison {
+    /// Integrated less reported energy in Wh, if both are known.
+    #[must_use]
+    pub fn difference(&self) -> Option<f64> {
+        Some(self.integrated? - self.reported?)
+    }
+}
+
+impl EnergyCheck {
+    /// Create the settings for comparing energy.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_unit` - granularity of the compared periods, e.g. [TimeUnit::Day]
+    /// * `relative_tolerance` - largest accepted difference as a fraction of the
+    ///   reported energy, None for 5%
+    /// * `absolute_tolerance` - largest accepted difference in Wh regardless of the
+    ///   relative tolerance, None for 100 Wh. Keeps small periods (e.g. at dawn)
+    ///   from being flagged for rounding.
+    #[must_use]
+    pub fn new(
+        time_unit: TimeUnit,
+        relative_tolerance: Option<f64>,
+        absolute_tolerance: Option<f64>,
+    ) -> Self {
+        EnergyCheck {
+            time_unit,
+            relative_tolerance: relative_tolerance.unwrap_or(0.05),
+            absolute_tolerance: absolute_tolerance.unwrap_or(100.0),
+        }
+    }
+
+    /// Compare integrated power with reported energy.
+    ///
+    /// # Arguments
+    ///
+    /// * `power` - quarter-hour power series
+    /// * `energy` - energy series of the same range, at the granularity of the
+    ///   check or finer. Series are matched by name, so Production power is
+    ///   compared with Production energy.
+    ///
+    /// # Returns
+    /// A comparison for every name and period that either side has values for, in
+    /// order of name as first seen in `power` and then of period.
+    ///
+    /// # Errors
+    /// Returns [Kind::InvalidArgument](crate::Kind::InvalidArgument) if the
+    /// granularity is unknown.
+    pub fn compare(
+        &self,
+        power: &[Series],
+        energy: &[Series],
+    ) -> Result<Vec<EnergyComparison>, Error> {
+        // Both sides are totalled per local period start, so a repeated local
+        // hour is compared as one and the site's time zone is not needed.
+        let resampler = Resampler::new(self.time_unit.clone(), None, Utc);
+        let integrated: Vec<Series> = power.iter().map(|s| integrate(s, None)).collect();
+        let integrated = resampler.resample_all(&integrated)?;
+        let reported = resampler.resample_all(energy)?;
+
+        let mut names: Vec<&str> = Vec::new();
+        for s in integrated.iter().chain(&reported) {
+            if !names.contains(&s.name.as_str()) {
+                names.push(&s.name);
+            }
+        }
+
+        let mut comparisons = Vec::new();
+        for name in names {
+            let integrated = wh_totals(&integrated, name);
+            let reported = wh_totals(&reported, name);
+            let mut periods: Vec<NaiveDateTime> =
+                integrated.keys().chain(reported.keys()).copied().collect();
+            periods.sort_unstable();
+            periods.dedup();
+
+            for timestamp in periods {
+                let mut comparison = EnergyComparison {
+                    name: name.to_string(),
+                    timestamp,
+                    integrated: integrated.get(&timestamp).copied().flatten(),
+                    reported: reported.get(&timestamp).copied().flatten(),
+                    discrepancy: false,
+                };
+                comparison.discrepancy = self.exceeds_tolerance(&comparison);
+                comparisons.push(comparison);
+            }
+        }
+
+        Ok(comparisons)
+    }
+
+    /// Whether both sides of a comparison are known and differ by more than both
+    /// tolerances.
+    fn exceeds_tolerance(&self, comparison: &EnergyComparison) -> bool {
+        match (comparison.difference(), comparison.reported) {
+            (Some(d), Some(reported)) => {
+                d.abs() > self.absolute_tolerance
+                    && d.abs() > self.relative_tolerance * reported.abs()
+            }
+            _ => false,
+        }
+    }
+
+    /// Fetch power details and energy details for a range and compare them.
+    ///
+    /// # Arguments
+    ///
+    /// * `solaredge` - SolarEdge credentials of the site
+    /// * `start` - start of the range
+    /// * `end` - end of the range, inclusive
+    ///
+    /// # Errors
+    /// Returns an error if a request fails, a response date cannot be parsed or
+    /// the granularity is unknown.
+    pub fn fetch(
+        &self,
+        solaredge: &SolaredgeCredentials,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<EnergyComparison>, Error> {
+        let power = DataEndpoint::PowerDetails.fetch_range(
+            solaredge,
+            &TimeUnit::QuarterOfAnHour,
+            start,
+            end,
+        )?;
+        let energy =
+            DataEndpoint::EnergyDetails.fetch_range(solaredge, &self.time_unit, start, end)?;
+
+        self.compare(&power, &energy)
+    }
+}
+
+/// Total energy in Wh per period of the series named `name`. None for a period
+/// with only missing values.
+fn wh_totals(series: &[Series], name: &str) -> BTreeMap<NaiveDateTime, Option<f64>> {
+    let mut totals: BTreeMap<NaiveDateTime, Option<f64>> = BTreeMap::new();
+    for s in series.iter().filter(|s| s.name == name) {
+        let factor = kwh_factor(&s.unit) * 1000.0;
+        for sample in &s.samples {
+            let total = totals.entry(sample.timestamp).or_default();
+            if let Some(v) = sample.value {
+                *total = Some(total.unwrap_or(0.0) + v * factor);
+            }
+        }
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, SiteEnergyDetailedResp, SitePowerResp, TimeSeries};
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::URL_DATE_TIME_FORMAT).unwrap()
+    }
+
+    fn power() -> Vec<Series> {
+        let resp: SitePowerResp = serde_json::from_str(
+            r#"{"power": {"timeUnit": "QUARTER_OF_AN_HOUR", "unit": "W", "values": [
+                {"date": "2022-06-01 10:00:00", "value": 4000},
+                {"date": "2022-06-01 10:15:00", "value": 2000},
+                {"date": "2022-06-01 10:30:00"},
+                {"date": "2022-06-02 10:00:00", "value": 8000}]}}"#,
+        )
+        .unwrap();
+        resp.power.time_series().unwrap()
+    }
+
+    #[test]
+    fn integrate_unit_test() {
+        let energy = integrate(&power()[0], None);
+        assert_eq!(energy.unit, "Wh");
+        let values: Vec<_> = energy.samples.iter().map(|s| s.value).collect();
+        assert_eq!(values, [Some(1000.0), Some(500.0), None, Some(2000.0)]);
+
+        let energy = integrate(&power()[0], Some(TimeDelta::hours(1)));
+        assert_eq!(energy.samples[0].value, Some(4000.0));
+    }
+
+    #[test]
+    fn compare_unit_test() {
+        let resp: SiteEnergyDetailedResp = serde_json::from_str(
+            r#"{"energyDetails": {"timeUnit": "DAY", "unit": "kWh", "meters": [
+                {"type": "Production", "values": [
+                    {"date": "2022-06-01 00:00:00", "value": 1.52},
+                    {"date": "2022-06-02 00:00:00", "value": 3},
+                    {"date": "2022-06-03 00:00:00", "value": 40}]},
+                {"type": "Consumption", "values": [
+                    {"date": "2022-06-01 00:00:00", "value": 10}]}
+            ]}}"#,
+        )
+        .unwrap();
+        let energy = resp.energy_details.time_series().unwrap();
+
+        let comparisons = EnergyCheck::new(TimeUnit::Day, None, None)
+            .compare(&power(), &energy)
+            .unwrap();
+        assert_eq!(comparisons.len(), 4);
+
+        // 1500 Wh integrated against 1520 Wh reported is within tolerance.
+        assert_eq!(comparisons[0].timestamp, at("2022-06-01 00:00:00"));
+        assert_eq!(comparisons[0].integrated, Some(1500.0));
+        assert!((comparisons[0].difference().unwrap() + 20.0).abs() < 1e-9);
+        assert!(!comparisons[0].discrepancy);
+
+        // 2000 Wh against 3000 Wh is not.
+        assert!(comparisons[1].discrepancy);
+
+        // No power for the last day and no consumption power.
+        assert_eq!(comparisons[2].integrated, None);
+        assert!(!comparisons[2].discrepancy);
+        assert_eq!(comparisons[3].name, "Consumption");
+        assert_eq!(comparisons[3].reported, Some(10_000.0));
+        assert_eq!(comparisons[3].difference(), None);
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<EnergyCheck>();
+        is_normal::<EnergyComparison>();
+    }
+}
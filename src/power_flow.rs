@@ -0,0 +1,321 @@
+//! Module for reading the current power flow of a site as a graph with signed
+//! power.
+//!
+//! [SiteCurrentPowerFlow] reports the power of each element as a magnitude and
+//! the direction of the flows as a list of connections between element names.
+//! A [PowerFlowGraph] resolves the names into [Element]s, gives grid and storage
+//! power a sign, and works out the power along each connection from the balance
+//! of the elements it connects.
+
+use crate::SiteCurrentPowerFlow;
+
+/// An element of the power flow of a site.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Element {
+    /// Electric grid
+    Grid,
+
+    /// Site electricity consumers
+    Load,
+
+    /// Photovoltaic array
+    Pv,
+
+    /// Electric storage
+    Storage,
+}
+
+/// Power flowing along one connection of the power flow.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Flow {
+    /// The element providing power
+    pub from: Element,
+
+    /// The element consuming power
+    pub to: Element,
+
+    /// Power in W, None if the element powers do not determine it
+    pub power: Option<f64>,
+}
+
+/// The current power flow of a site with signed power, in W.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PowerFlowGraph {
+    /// Power of the PV array, None if the site has none
+    pub pv: Option<f64>,
+
+    /// Power consumed by the site
+    pub load: f64,
+
+    /// Power from the grid: positive when importing, negative when exporting
+    pub grid: f64,
+
+    /// Power from storage: positive when discharging, negative when charging.
+    /// None if the site has none.
+    pub storage: Option<f64>,
+
+    /// The connections between the elements, in the order reported
+    pub flows: Vec<Flow>,
+
+    /// Connections between elements not listed in the API documentation, as
+    /// reported (from, to)
+    pub unknown_connections: Vec<(String, String)>,
+}
+
+impl Element {
+    /// Every element.
+    pub const ALL: [Element; 4] = [Element::Grid, Element::Load, Element::Pv, Element::Storage];
+
+    /// The element of a name used in the power flow connections, ignoring case.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Element::ALL
+            .into_iter()
+            .find(|e| e.to_string().eq_ignore_ascii_case(name))
+    }
+}
+
+impl std::fmt::Display for Element {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Element::Grid => write!(f, "GRID"),
+            Element::Load => write!(f, "LOAD"),
+            Element::Pv => write!(f, "PV"),
+            Element::Storage => write!(f, "STORAGE"),
+        }
+    }
+}
+
+/// Factor converting a power flow value in `unit` to watts.
+fn watts(unit: &str) -> f64 {
+    match unit {
+        "kW" => 1_000.0,
+        "MW" => 1_000_000.0,
+        _ => 1.0,
+    }
+}
+
+impl PowerFlowGraph {
+    /// Build the graph of a power flow.
+    ///
+    /// # Arguments
+    ///
+    /// * `flow` - the current power flow, from
+    ///   [SitePowerFlowResp](crate::SitePowerFlowResp)
+    #[must_use]
+    pub fn new(flow: &SiteCurrentPowerFlow) -> Self {
+        let mut flows: Vec<Flow> = Vec::new();
+        let mut unknown_connections = Vec::new();
+        for c in &flow.connections {
+            match (Element::from_name(&c.from), Element::from_name(&c.to)) {
+                (Some(from), Some(to)) => {
+                    if !flows.iter().any(|f| f.from == from && f.to == to) {
+                        flows.push(Flow {
+                            from,
+                            to,
+                            power: None,
+                        });
+                    }
+                }
+                _ => unknown_connections.push((c.from.clone(), c.to.clone())),
+            }
+        }
+
+        // The magnitudes do not say which way grid and storage power flows, the
+        // connections do.
+        let factor = watts(&flow.unit);
+        let signed = |power: f32, element: Element| {
+            let power = f64::from(power) * factor;
+            if flows.iter().any(|f| f.to == element) {
+                -power
+            } else {
+                power
+            }
+        };
+
+        let mut graph = PowerFlowGraph {
+            pv: flow
+                .pv
+                .as_ref()
+                .map(|p| f64::from(p.current_power) * factor),
+            load: f64::from(flow.load.current_power) * factor,
+            grid: signed(flow.grid.current_power, Element::Grid),
+            storage: flow
+                .storage
+                .as_ref()
+                .map(|s| signed(s.current_power, Element::Storage)),
+            flows,
+            unknown_connections,
+        };
+        graph.resolve_flows();
+        graph
+    }
+
+    /// Power an element supplies to the rest of the site: positive for a source
+    /// and negative for a sink. None if the site does not have the element.
+    #[must_use]
+    pub fn power(&self, element: Element) -> Option<f64> {
+        match element {
+            Element::Grid => Some(self.grid),
+            Element::Load => Some(-self.load),
+            Element::Pv => self.pv,
+            Element::Storage => self.storage,
+        }
+    }
+
+    /// Whether the grid supplies power to the site.
+    #[must_use]
+    pub fn importing(&self) -> bool {
+        self.grid > 0.0
+    }
+
+    /// Whether the storage takes up power.
+    #[must_use]
+    pub fn charging(&self) -> bool {
+        self.storage.is_some_and(|s| s < 0.0)
+    }
+
+    /// Power supplied less power consumed, zero for a consistent power flow.
+    #[must_use]
+    pub fn imbalance(&self) -> f64 {
+        Element::ALL.iter().filter_map(|e| self.power(*e)).sum()
+    }
+
+    /// Whether the power flow is consistent: supply and consumption balance and
+    /// the power along every connection is known and not negative.
+    ///
+    /// # Arguments
+    ///
+    /// * `tolerance` - largest accepted deviation in W, e.g. for rounding of the
+    ///   reported values
+    #[must_use]
+    pub fn is_balanced(&self, tolerance: f64) -> bool {
+        self.imbalance().abs() <= tolerance
+            && self
+                .flows
+                .iter()
+                .all(|f| f.power.is_some_and(|p| p >= -tolerance))
+    }
+
+    /// Work out the power along the connections. An element with a single
+    /// connection of unknown power passes all of its power not accounted for by
+    /// its other connections along it; repeat until no such element is left.
+    fn resolve_flows(&mut self) {
+        loop {
+            let mut resolved = false;
+            for element in Element::ALL {
+                let mut unknown = None;
+                let mut unknown_count = 0;
+                let mut remaining = self.power(element).unwrap_or(0.0);
+                for (i, f) in self.flows.iter().enumerate() {
+                    let sign = if f.from == element {
+                        1.0
+                    } else if f.to == element {
+                        -1.0
+                    } else {
+                        continue;
+                    };
+                    if let Some(p) = f.power {
+                        remaining -= sign * p;
+                    } else {
+                        unknown = Some((i, sign));
+                        unknown_count += 1;
+                    }
+                }
+
+                if let (1, Some((i, sign))) = (unknown_count, unknown) {
+                    self.flows[i].power = Some(sign * remaining);
+                    resolved = true;
+                }
+            }
+            if !resolved {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, SitePowerFlowResp};
+
+    fn graph(connections: &str, storage: &str) -> PowerFlowGraph {
+        let resp: SitePowerFlowResp = serde_json::from_str(&format!(
+            r#"{{"siteCurrentPowerFlow": {{"updateRefreshRate": 3, "unit": "kW",
+                "connections": [{connections}],
+                "GRID": {{"status": "Active", "currentPower": 1.5}},
+                "LOAD": {{"status": "Active", "currentPower": 2.0}},
+                "PV": {{"status": "Active", "currentPower": 4.5}}{storage}}}}}"#
+        ))
+        .unwrap();
+        PowerFlowGraph::new(&resp.site_current_power_flow)
+    }
+
+    fn power(graph: &PowerFlowGraph, from: Element, to: Element) -> Option<f64> {
+        graph
+            .flows
+            .iter()
+            .find(|f| f.from == from && f.to == to)
+            .and_then(|f| f.power)
+    }
+
+    #[test]
+    fn exporting_and_charging_unit_test() {
+        let graph = graph(
+            r#"{"from": "PV", "to": "Load"}, {"from": "LOAD", "to": "Grid"},
+                {"from": "PV", "to": "Storage"}"#,
+            r#", "STORAGE": {"status": "Charging", "currentPower": 1.0}"#,
+        );
+
+        assert_eq!(graph.pv, Some(4500.0));
+        assert!((graph.load - 2000.0).abs() < f64::EPSILON);
+        assert!((graph.grid + 1500.0).abs() < f64::EPSILON);
+        assert_eq!(graph.storage, Some(-1000.0));
+        assert!(!graph.importing());
+        assert!(graph.charging());
+
+        assert_eq!(power(&graph, Element::Pv, Element::Storage), Some(1000.0));
+        assert_eq!(power(&graph, Element::Load, Element::Grid), Some(1500.0));
+        assert_eq!(power(&graph, Element::Pv, Element::Load), Some(3500.0));
+        assert!(graph.imbalance().abs() < 1e-9);
+        assert!(graph.is_balanced(1.0));
+    }
+
+    #[test]
+    fn importing_unit_test() {
+        let graph = graph(
+            r#"{"from": "GRID", "to": "Load"}, {"from": "PV", "to": "Load"},
+                {"from": "PV", "to": "Cloud"}"#,
+            "",
+        );
+
+        assert!((graph.grid - 1500.0).abs() < f64::EPSILON);
+        assert!(graph.importing());
+        assert!(!graph.charging());
+        assert_eq!(graph.storage, None);
+        assert_eq!(
+            graph.unknown_connections,
+            [("PV".to_string(), "Cloud".to_string())]
+        );
+
+        // 1.5 kW imported and 4.5 kW produced cannot all go into a 2 kW load.
+        assert!((graph.imbalance() - 4000.0).abs() < 1e-9);
+        assert!(!graph.is_balanced(1.0));
+    }
+
+    #[test]
+    fn element_unit_test() {
+        assert_eq!(Element::from_name("Storage"), Some(Element::Storage));
+        assert_eq!(Element::from_name("GRID"), Some(Element::Grid));
+        assert_eq!(Element::from_name("EV"), None);
+        assert_eq!(Element::Pv.to_string(), "PV");
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<Element>();
+        is_normal::<Flow>();
+        is_normal::<PowerFlowGraph>();
+    }
+}
@@ -0,0 +1,405 @@
+//! Module for checking that the meters of a site add up.
+//!
+//! Production is either consumed on site or fed into the grid, and consumption is
+//! covered either by production or by the grid, so for every interval
+//!
+//! * Production = SelfConsumption + FeedIn
+//! * Consumption = SelfConsumption + Purchased
+//!
+//! A site whose meters break these identities most of the time is usually
+//! misconfigured, e.g. with the current transformer at the grid connection
+//! installed the wrong way round.
+
+use crate::kpi::{meter_values, MeterValues};
+use crate::{DataEndpoint, Error, MeterType, Series, SolaredgeCredentials, TimeUnit};
+use chrono::NaiveDateTime;
+
+/// One of the identities the meters of a site satisfy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Identity {
+    /// Production = SelfConsumption + FeedIn
+    Production,
+
+    /// Consumption = SelfConsumption + Purchased
+    Consumption,
+}
+
+/// An interval in which an identity does not hold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Violation {
+    /// Site local start of the interval
+    pub timestamp: NaiveDateTime,
+
+    /// The identity that does not hold
+    pub identity: Identity,
+
+    /// Production or Consumption, in Wh (or W for power)
+    pub total: f64,
+
+    /// SelfConsumption plus FeedIn or Purchased, in Wh (or W for power)
+    pub parts: f64,
+}
+
+/// A likely cause of the violations.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Suspicion {
+    /// Most violations disappear when FeedIn and Purchased are exchanged: the
+    /// current transformer at the grid connection is probably reversed
+    SwappedGridDirection,
+
+    /// The meter reports negative values: its current transformer is probably
+    /// reversed
+    NegativeMeter(MeterType),
+
+    /// In most violations SelfConsumption exceeds Production: the production
+    /// meter probably misses part of the inverters
+    SelfConsumptionExceedsProduction,
+
+    /// In most violations SelfConsumption exceeds Consumption: the consumption
+    /// meter is probably installed at the wrong point
+    SelfConsumptionExceedsConsumption,
+}
+
+/// Result of checking the meters of a site.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceReport {
+    /// Number of identities checked: one per interval and identity whose meters
+    /// all have a value
+    pub checked: usize,
+
+    /// The identities that did not hold, in order of interval
+    pub violations: Vec<Violation>,
+
+    /// Likely causes of the violations
+    pub suspicions: Vec<Suspicion>,
+}
+
+/// Settings for checking the meters of a site.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reconciliation {
+    /// Largest accepted difference, as a fraction of Production or Consumption
+    pub relative_tolerance: f64,
+
+    /// Largest accepted difference in Wh (or W for power), regardless of the
+    /// relative tolerance
+    pub absolute_tolerance: f64,
+}
+
+impl std::fmt::Display for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Identity::Production => write!(f, "Production = SelfConsumption + FeedIn"),
+            Identity::Consumption => write!(f, "Consumption = SelfConsumption + Purchased"),
+        }
+    }
+}
+
+impl Violation {
+    /// Total less the sum of its parts. Positive if the parts fall short.
+    #[must_use]
+    pub fn difference(&self) -> f64 {
+        self.total - self.parts
+    }
+}
+
+impl BalanceReport {
+    /// Whether every checked identity held.
+    #[must_use]
+    pub fn is_balanced(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// The values of the five meters at one timestamp.
+#[derive(Clone, Copy)]
+struct Meters {
+    production: Option<f64>,
+    consumption: Option<f64>,
+    self_consumption: Option<f64>,
+    feed_in: Option<f64>,
+    purchased: Option<f64>,
+}
+
+impl Reconciliation {
+    /// Create the settings for checking the meters of a site.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_tolerance` - largest accepted difference as a fraction of
+    ///   Production or Consumption, None for 1%
+    /// * `absolute_tolerance` - largest accepted difference in Wh (or W) regardless
+    ///   of the relative tolerance, None for 10. Keeps rounding in small intervals
+    ///   from being reported.
+    #[must_use]
+    pub fn new(relative_tolerance: Option<f64>, absolute_tolerance: Option<f64>) -> Self {
+        Reconciliation {
+            relative_tolerance: relative_tolerance.unwrap_or(0.01),
+            absolute_tolerance: absolute_tolerance.unwrap_or(10.0),
+        }
+    }
+
+    /// Check the identities for every interval.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - energy details or power details series, e.g. from
+    ///   [SiteEnergyDetailedResp](crate::SiteEnergyDetailedResp). An identity is
+    ///   only checked in the intervals where all of its meters have a value.
+    #[must_use]
+    pub fn check(&self, series: &[Series]) -> BalanceReport {
+        let production = meter_values(series, &MeterType::Production);
+        let consumption = meter_values(series, &MeterType::Consumption);
+        let self_consumption = meter_values(series, &MeterType::SelfConsumption);
+        let feed_in = meter_values(series, &MeterType::FeedIn);
+        let purchased = meter_values(series, &MeterType::Purchased);
+
+        let mut suspicions = Vec::new();
+        for (meter_type, values) in [
+            (MeterType::Production, &production),
+            (MeterType::Consumption, &consumption),
+            (MeterType::SelfConsumption, &self_consumption),
+            (MeterType::FeedIn, &feed_in),
+            (MeterType::Purchased, &purchased),
+        ] {
+            let negative = values
+                .iter()
+                .flat_map(|v| v.values())
+                .any(|v| v.is_some_and(|v| v < -self.absolute_tolerance));
+            if negative {
+                suspicions.push(Suspicion::NegativeMeter(meter_type));
+            }
+        }
+
+        let mut timestamps: Vec<NaiveDateTime> = [&production, &consumption, &self_consumption]
+            .into_iter()
+            .flatten()
+            .flat_map(|v| v.keys().copied())
+            .collect();
+        timestamps.sort_unstable();
+        timestamps.dedup();
+
+        let at = |values: &Option<MeterValues>, t: &NaiveDateTime| {
+            values.as_ref().and_then(|v| v.get(t).copied().flatten())
+        };
+
+        let mut checked = 0;
+        let mut violations = Vec::new();
+        let (mut swapped, mut exceeds_production, mut exceeds_consumption) = (0, 0, 0);
+        for timestamp in timestamps {
+            let m = Meters {
+                production: at(&production, &timestamp),
+                consumption: at(&consumption, &timestamp),
+                self_consumption: at(&self_consumption, &timestamp),
+                feed_in: at(&feed_in, &timestamp),
+                purchased: at(&purchased, &timestamp),
+            };
+
+            let mut violated = false;
+            for (identity, total, other) in [
+                (Identity::Production, m.production, m.feed_in),
+                (Identity::Consumption, m.consumption, m.purchased),
+            ] {
+                let (Some(total), Some(self_consumption), Some(other)) =
+                    (total, m.self_consumption, other)
+                else {
+                    continue;
+                };
+                checked += 1;
+
+                let parts = self_consumption + other;
+                if self.holds(total, parts) {
+                    continue;
+                }
+                violated = true;
+                if self_consumption > total + self.absolute_tolerance {
+                    match identity {
+                        Identity::Production => exceeds_production += 1,
+                        Identity::Consumption => exceeds_consumption += 1,
+                    }
+                }
+                violations.push(Violation {
+                    timestamp,
+                    identity,
+                    total,
+                    parts,
+                });
+            }
+
+            if violated && self.holds_swapped(&m) {
+                swapped += 1;
+            }
+        }
+
+        let intervals = {
+            let mut t: Vec<_> = violations.iter().map(|v| v.timestamp).collect();
+            t.dedup();
+            t.len()
+        };
+        let count = |identity| violations.iter().filter(|v| v.identity == identity).count();
+        if swapped * 2 > intervals {
+            suspicions.push(Suspicion::SwappedGridDirection);
+        }
+        if exceeds_production * 2 > count(Identity::Production) {
+            suspicions.push(Suspicion::SelfConsumptionExceedsProduction);
+        }
+        if exceeds_consumption * 2 > count(Identity::Consumption) {
+            suspicions.push(Suspicion::SelfConsumptionExceedsConsumption);
+        }
+
+        BalanceReport {
+            checked,
+            violations,
+            suspicions,
+        }
+    }
+
+    /// Fetch energy details for a range and check them.
+    ///
+    /// # Arguments
+    ///
+    /// * `solaredge` - SolarEdge credentials of the site
+    /// * `time_unit` - granularity of the intervals, e.g. [TimeUnit::Day]
+    /// * `start` - start of the range
+    /// * `end` - end of the range, inclusive
+    ///
+    /// # Errors
+    /// Returns an error if a request fails or a response date cannot be parsed.
+    pub fn fetch(
+        &self,
+        solaredge: &SolaredgeCredentials,
+        time_unit: &TimeUnit,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<BalanceReport, Error> {
+        let series = DataEndpoint::EnergyDetails.fetch_range(solaredge, time_unit, start, end)?;
+        Ok(self.check(&series))
+    }
+
+    /// Whether `total` and the sum of its `parts` agree within the tolerances.
+    fn holds(&self, total: f64, parts: f64) -> bool {
+        let difference = (total - parts).abs();
+        difference <= self.absolute_tolerance || difference <= self.relative_tolerance * total.abs()
+    }
+
+    /// Whether both identities hold with FeedIn and Purchased exchanged.
+    fn holds_swapped(&self, meters: &Meters) -> bool {
+        let Meters {
+            production: Some(production),
+            consumption: Some(consumption),
+            self_consumption: Some(self_consumption),
+            feed_in: Some(feed_in),
+            purchased: Some(purchased),
+        } = *meters
+        else {
+            return false;
+        };
+        self.holds(production, self_consumption + purchased)
+            && self.holds(consumption, self_consumption + feed_in)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, SiteEnergyDetailedResp, TimeSeries};
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::URL_DATE_TIME_FORMAT).unwrap()
+    }
+
+    fn series(meters: &str) -> Vec<Series> {
+        let resp: SiteEnergyDetailedResp = serde_json::from_str(&format!(
+            r#"{{"energyDetails": {{"timeUnit": "DAY", "unit": "Wh", "meters": [{meters}]}}}}"#
+        ))
+        .unwrap();
+        resp.energy_details.time_series().unwrap()
+    }
+
+    #[test]
+    fn check_balanced_unit_test() {
+        let report = Reconciliation::new(None, None).check(&series(
+            r#"{"type": "Production", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 10000},
+                {"date": "2022-06-02 00:00:00", "value": 20000}]},
+            {"type": "Consumption", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 8000},
+                {"date": "2022-06-02 00:00:00"}]},
+            {"type": "SelfConsumption", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 6000},
+                {"date": "2022-06-02 00:00:00", "value": 9000}]},
+            {"type": "FeedIn", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 4005},
+                {"date": "2022-06-02 00:00:00", "value": 10000}]},
+            {"type": "Purchased", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 2000}]}"#,
+        ));
+
+        assert_eq!(report.checked, 3);
+        assert_eq!(
+            report.violations,
+            [Violation {
+                timestamp: at("2022-06-02 00:00:00"),
+                identity: Identity::Production,
+                total: 20000.0,
+                parts: 19000.0,
+            }]
+        );
+        assert!((report.violations[0].difference() - 1000.0).abs() < 1e-9);
+        assert!(report.suspicions.is_empty());
+        assert!(!report.is_balanced());
+    }
+
+    #[test]
+    fn check_swapped_unit_test() {
+        // FeedIn and Purchased reported the wrong way round.
+        let report = Reconciliation::new(None, None).check(&series(
+            r#"{"type": "Production", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 10000}]},
+            {"type": "Consumption", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 8000}]},
+            {"type": "SelfConsumption", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 6000}]},
+            {"type": "FeedIn", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 2000}]},
+            {"type": "Purchased", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 4000}]}"#,
+        ));
+
+        assert_eq!(report.violations.len(), 2);
+        assert_eq!(report.suspicions, [Suspicion::SwappedGridDirection]);
+    }
+
+    #[test]
+    fn check_meter_suspicions_unit_test() {
+        let report = Reconciliation::new(None, None).check(&series(
+            r#"{"type": "Production", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 5000}]},
+            {"type": "Consumption", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 8000}]},
+            {"type": "SelfConsumption", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 6000}]},
+            {"type": "FeedIn", "values": [
+                {"date": "2022-06-01 00:00:00", "value": 0}]},
+            {"type": "Purchased", "values": [
+                {"date": "2022-06-01 00:00:00", "value": -2000}]}"#,
+        ));
+
+        assert_eq!(report.violations.len(), 2);
+        assert_eq!(
+            report.suspicions,
+            [
+                Suspicion::NegativeMeter(MeterType::Purchased),
+                Suspicion::SelfConsumptionExceedsProduction
+            ]
+        );
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<Identity>();
+        is_normal::<Violation>();
+        is_normal::<Suspicion>();
+        is_normal::<BalanceReport>();
+        is_normal::<Reconciliation>();
+    }
+}
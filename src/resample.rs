@@ -0,0 +1,376 @@
+//! Module for aggregating time series to a coarser granularity locally, so
+//! quarter-hour values fetched once give hourly, daily, weekly, monthly and
+//! yearly totals without spending more requests.
+//!
+//! Energy is summed and power is averaged over each period. Periods follow the
+//! calendar of the site: the timestamps of a series are already in the site's
+//! local time, so days, weeks, months and years start at local midnight. The
+//! hour repeated when daylight saving ends gives two hours, not one.
+
+use crate::date_value::LocalClock;
+use crate::windows::period_start_from;
+use crate::{Error, Kind, Sample, Series, TimeUnit, URL_DATE_TIME_FORMAT};
+use chrono::{NaiveDateTime, TimeZone, Weekday};
+use std::collections::BTreeMap;
+
+/// How the values within a period are combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Aggregation {
+    /// Add the values up, e.g. energy
+    Sum,
+
+    /// Average the values, e.g. power
+    Mean,
+}
+
+/// Settings for aggregating series to one granularity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resampler<Tz: TimeZone> {
+    /// Granularity of the aggregated series
+    pub time_unit: TimeUnit,
+
+    /// First day of a week, for [TimeUnit::Week]
+    pub week_start: Weekday,
+
+    /// Time zone of the site
+    pub time_zone: Tz,
+}
+
+impl Aggregation {
+    /// How values of the given unit are combined: energy units ("Wh", "kWh",
+    /// "MWh") are summed, anything else (e.g. power in "W") is averaged.
+    #[must_use]
+    pub fn for_unit(unit: &str) -> Self {
+        if unit.ends_with("Wh") {
+            Aggregation::Sum
+        } else {
+            Aggregation::Mean
+        }
+    }
+}
+
+impl<Tz: TimeZone> Resampler<Tz> {
+    /// Create the settings for aggregating series.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_unit` - granularity of the aggregated series, coarser than (or the
+    ///   same as) that of the series to aggregate
+    /// * `week_start` - first day of a week, None for Monday
+    /// * `time_zone` - time zone of the site, e.g. a `chrono_tz::Tz`, for the
+    ///   daylight saving changes. `chrono::Utc` has none.
+    #[must_use]
+    pub fn new(time_unit: TimeUnit, week_start: Option<Weekday>, time_zone: Tz) -> Self {
+        Resampler {
+            time_unit,
+            week_start: week_start.unwrap_or(Weekday::Mon),
+            time_zone,
+        }
+    }
+
+    /// The start of the period containing `t`.
+    #[must_use]
+    pub fn period_start(&self, t: NaiveDateTime) -> NaiveDateTime {
+        period_start_from(t, &self.time_unit, self.week_start)
+    }
+
+    /// Aggregate a series, combining its values as [Aggregation::for_unit] gives for
+    /// its unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - the series to aggregate, in any order except that the
+    ///   occurrences of a repeated local time are taken in order
+    ///
+    /// # Returns
+    /// A series with one sample per period that has samples, timestamped with the
+    /// start of the period, in order of time. Missing values are left out of the sum or
+    /// average, and a period with only missing values has none. Fill the gaps
+    /// first (see [QualityCheck::fill](crate::QualityCheck::fill)) to count them.
+    ///
+    /// # Errors
+    /// Returns [Kind::InvalidArgument] if the granularity is unknown, or
+    /// [Kind::InvalidTimestamp] if a sample's local time does not exist when
+    /// aggregating to quarter hours or hours.
+    pub fn resample(&self, series: &Series) -> Result<Series, Error> {
+        self.resample_with(series, Aggregation::for_unit(&series.unit))
+    }
+
+    /// Aggregate a series, combining its values as given.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - the series to aggregate, see [resample](Self::resample)
+    /// * `aggregation` - how to combine the values of a period
+    ///
+    /// # Returns
+    /// See [resample](Self::resample).
+    ///
+    /// # Errors
+    /// See [resample](Self::resample).
+    pub fn resample_with(
+        &self,
+        series: &Series,
+        aggregation: Aggregation,
+    ) -> Result<Series, Error> {
+        if let TimeUnit::Unknown(unit) = &self.time_unit {
+            return Err(Error::new(Kind::InvalidArgument(format!(
+                "cannot resample to unknown time unit {unit}"
+            ))));
+        }
+
+        // Periods shorter than a day are keyed on the instant they start, so the
+        // two occurrences of a repeated hour stay apart. Longer ones start at
+        // local midnight, which is never repeated.
+        let sub_day = matches!(self.time_unit, TimeUnit::QuarterOfAnHour | TimeUnit::Hour);
+        let mut clock = LocalClock::new(&self.time_zone);
+        let mut periods = BTreeMap::new();
+        for sample in &series.samples {
+            let start = self.period_start(sample.timestamp);
+            let key = if sub_day {
+                let Some(instant) = clock.utc(sample.timestamp) else {
+                    return Err(Error::new(Kind::InvalidTimestamp(
+                        sample.timestamp.format(URL_DATE_TIME_FORMAT).to_string(),
+                    )));
+                };
+                instant - (sample.timestamp - start)
+            } else {
+                start.and_utc()
+            };
+            let period = periods.entry(key).or_insert((start, 0.0, 0));
+            if let Some(v) = sample.value {
+                period.1 += v;
+                period.2 += 1;
+            }
+        }
+
+        let samples = periods
+            .into_values()
+            .map(|(timestamp, total, count)| Sample {
+                timestamp,
+                value: match (aggregation, count) {
+                    (_, 0) => None,
+                    (Aggregation::Sum, _) => Some(total),
+                    (Aggregation::Mean, _) => Some(total / f64::from(count)),
+                },
+            })
+            .collect();
+
+        Ok(Series {
+            name: series.name.clone(),
+            serial: series.serial.clone(),
+            unit: series.unit.clone(),
+            samples,
+        })
+    }
+
+    /// Aggregate the series of one or more responses, e.g. from
+    /// [DataEndpoint::fetch_range](crate::DataEndpoint::fetch_range). Series with
+    /// the same name and serial number are joined before aggregating, so a period
+    /// split across requests is aggregated as a whole.
+    ///
+    /// # Returns
+    /// One aggregated series per name and serial number, in order of first
+    /// appearance.
+    ///
+    /// # Errors
+    /// See [resample](Self::resample).
+    pub fn resample_all(&self, series: &[Series]) -> Result<Vec<Series>, Error> {
+        let mut joined: Vec<Series> = Vec::new();
+        for s in series {
+            match joined
+                .iter_mut()
+                .find(|j| j.name == s.name && j.serial == s.serial)
+            {
+                Some(j) => j.samples.extend_from_slice(&s.samples),
+                None => joined.push(s.clone()),
+            }
+        }
+
+        joined.iter().map(|s| self.resample(s)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, SiteEnergyDetailedResp, TimeSeries};
+    use chrono::Utc;
+    use chrono_tz::Europe::Berlin;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::URL_DATE_TIME_FORMAT).unwrap()
+    }
+
+    fn series(unit: &str, values: &[(&str, Option<f64>)]) -> Series {
+        Series {
+            name: "Production".to_string(),
+            serial: None,
+            unit: unit.to_string(),
+            samples: values
+                .iter()
+                .map(|(t, value)| Sample {
+                    timestamp: at(t),
+                    value: *value,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn resample_energy_unit_test() {
+        let resp: SiteEnergyDetailedResp = serde_json::from_str(
+            r#"{"energyDetails": {"timeUnit": "QUARTER_OF_AN_HOUR", "unit": "Wh", "meters": [
+                {"type": "Production", "values": [
+                    {"date": "2022-06-01 10:00:00", "value": 100},
+                    {"date": "2022-06-01 10:15:00", "value": 200},
+                    {"date": "2022-06-01 10:30:00"},
+                    {"date": "2022-06-01 11:45:00", "value": 50}]}
+            ]}}"#,
+        )
+        .unwrap();
+        let quarters = resp.energy_details.time_series().unwrap();
+
+        let hourly = Resampler::new(TimeUnit::Hour, None, Utc)
+            .resample_all(&quarters)
+            .unwrap();
+        assert_eq!(hourly.len(), 1);
+        assert_eq!(
+            hourly[0].samples,
+            [
+                Sample {
+                    timestamp: at("2022-06-01 10:00:00"),
+                    value: Some(300.0)
+                },
+                Sample {
+                    timestamp: at("2022-06-01 11:00:00"),
+                    value: Some(50.0)
+                },
+            ]
+        );
+        assert_eq!(hourly[0].unit, "Wh");
+    }
+
+    #[test]
+    fn resample_power_unit_test() {
+        let power = series(
+            "W",
+            &[
+                ("2022-06-01 10:00:00", Some(1000.0)),
+                ("2022-06-01 10:15:00", Some(3000.0)),
+                ("2022-06-01 10:30:00", None),
+                ("2022-06-02 00:00:00", None),
+            ],
+        );
+        let daily = Resampler::new(TimeUnit::Day, None, Utc)
+            .resample(&power)
+            .unwrap();
+        assert_eq!(daily.samples[0].value, Some(2000.0));
+        assert_eq!(daily.samples[1].timestamp, at("2022-06-02 00:00:00"));
+        assert_eq!(daily.samples[1].value, None);
+    }
+
+    #[test]
+    fn resample_daylight_saving_unit_test() {
+        // 02:00 to 02:59 occurs twice when daylight saving ends in Europe/Berlin.
+        let energy = series(
+            "Wh",
+            &[
+                ("2022-10-30 01:45:00", Some(1.0)),
+                ("2022-10-30 02:00:00", Some(2.0)),
+                ("2022-10-30 02:30:00", Some(4.0)),
+                ("2022-10-30 02:00:00", Some(8.0)),
+                ("2022-10-30 02:30:00", Some(16.0)),
+                ("2022-10-30 03:00:00", Some(32.0)),
+            ],
+        );
+
+        let hourly = Resampler::new(TimeUnit::Hour, None, Berlin)
+            .resample(&energy)
+            .unwrap();
+        let hours: Vec<_> = hourly
+            .samples
+            .iter()
+            .map(|s| (s.timestamp, s.value))
+            .collect();
+        assert_eq!(
+            hours,
+            [
+                (at("2022-10-30 01:00:00"), Some(1.0)),
+                (at("2022-10-30 02:00:00"), Some(6.0)),
+                (at("2022-10-30 02:00:00"), Some(24.0)),
+                (at("2022-10-30 03:00:00"), Some(32.0)),
+            ]
+        );
+
+        let daily = Resampler::new(TimeUnit::Day, None, Berlin)
+            .resample(&energy)
+            .unwrap();
+        assert_eq!(daily.samples[0].value, Some(63.0));
+
+        // 02:00 to 02:59 does not exist when daylight saving starts.
+        let skipped = series("Wh", &[("2022-03-27 02:00:00", Some(1.0))]);
+        let e = Resampler::new(TimeUnit::Hour, None, Berlin)
+            .resample(&skipped)
+            .unwrap_err();
+        assert!(matches!(e.kind(), Kind::InvalidTimestamp(_)));
+    }
+
+    #[test]
+    fn resample_calendar_unit_test() {
+        // Wednesday, Saturday and Sunday of the same Monday week.
+        let energy = series(
+            "kWh",
+            &[
+                ("2022-06-01 00:00:00", Some(1.0)),
+                ("2022-06-04 00:00:00", Some(2.0)),
+                ("2022-06-05 00:00:00", Some(4.0)),
+                ("2022-07-01 00:00:00", Some(8.0)),
+            ],
+        );
+
+        let weekly = Resampler::new(TimeUnit::Week, None, Utc)
+            .resample(&energy)
+            .unwrap();
+        let values: Vec<_> = weekly.samples.iter().map(|s| s.value).collect();
+        assert_eq!(values, [Some(7.0), Some(8.0)]);
+        assert_eq!(weekly.samples[0].timestamp, at("2022-05-30 00:00:00"));
+
+        let weekly = Resampler::new(TimeUnit::Week, Some(Weekday::Sun), Utc)
+            .resample(&energy)
+            .unwrap();
+        let values: Vec<_> = weekly.samples.iter().map(|s| s.value).collect();
+        assert_eq!(values, [Some(3.0), Some(4.0), Some(8.0)]);
+        assert_eq!(weekly.samples[1].timestamp, at("2022-06-05 00:00:00"));
+
+        let monthly = Resampler::new(TimeUnit::Month, None, Utc)
+            .resample(&energy)
+            .unwrap();
+        assert_eq!(monthly.samples.len(), 2);
+        assert_eq!(monthly.samples[1].timestamp, at("2022-07-01 00:00:00"));
+
+        let yearly = Resampler::new(TimeUnit::Year, None, Utc)
+            .resample(&energy)
+            .unwrap();
+        assert_eq!(yearly.samples[0].value, Some(15.0));
+
+        let e = Resampler::new(TimeUnit::Unknown("DECADE".to_string()), None, Utc)
+            .resample(&energy)
+            .unwrap_err();
+        assert!(matches!(e.kind(), Kind::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn aggregation_unit_test() {
+        assert_eq!(Aggregation::for_unit("Wh"), Aggregation::Sum);
+        assert_eq!(Aggregation::for_unit("MWh"), Aggregation::Sum);
+        assert_eq!(Aggregation::for_unit("W"), Aggregation::Mean);
+        assert_eq!(Aggregation::for_unit("V"), Aggregation::Mean);
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<Aggregation>();
+        is_normal::<Resampler<Utc>>();
+    }
+}
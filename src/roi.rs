@@ -0,0 +1,306 @@
+//! Module for tracking the payback of an installation: the savings from
+//! self-consumed and exported energy, the balance still to be recovered and the
+//! (projected) break-even date.
+//!
+//! Savings are valued at flat rates: self-consumption avoids buying energy at the
+//! import rate and feed-in is paid at the export rate. The projection assumes the
+//! average daily savings of the last year (or of all days, if there is less
+//! history) continue.
+
+use crate::time_series::kwh_factor;
+use crate::{
+    DataEndpoint, Error, MeterType, SendReq, Series, SiteDetailsReq, SolaredgeCredentials, TimeUnit,
+};
+use chrono::{Days, NaiveDate, NaiveTime};
+use std::collections::BTreeMap;
+
+/// Number of trailing days whose average savings are projected forward.
+const PROJECTION_DAYS: usize = 365;
+
+/// Cost and tariffs of an installation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Investment {
+    /// Price paid for the system
+    pub system_cost: f64,
+
+    /// Rebates, tax credits and other incentives received
+    pub incentives: f64,
+
+    /// Price of energy bought from the grid, per kWh
+    pub import_rate: f64,
+
+    /// Price paid for energy exported to the grid, per kWh
+    pub export_rate: f64,
+
+    /// First day that counts towards payback, e.g. the PTO date
+    pub start: NaiveDate,
+}
+
+/// Savings of one day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DailySavings {
+    /// The day
+    pub date: NaiveDate,
+
+    /// Solar energy consumed on site, in kWh
+    pub self_consumption: f64,
+
+    /// Solar energy exported to the grid, in kWh
+    pub feed_in: f64,
+
+    /// Value of the day's self-consumption and feed-in
+    pub savings: f64,
+
+    /// Savings from the start of the investment up to and including this day
+    pub cumulative: f64,
+}
+
+/// When the savings cover the net cost of the installation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakEven {
+    /// The savings covered the net cost on this day
+    Reached(NaiveDate),
+
+    /// At the recent rate of savings, the net cost is covered on this day
+    Projected(NaiveDate),
+
+    /// There are no savings to project from
+    Never,
+}
+
+/// Payback of an installation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoiReport {
+    /// Currency of the amounts, e.g. "USD"
+    pub currency: String,
+
+    /// System cost less incentives
+    pub net_cost: f64,
+
+    /// Savings from the start of the investment until the last day with data
+    pub savings: f64,
+
+    /// Part of the net cost not yet recovered, zero once broken even
+    pub remaining: f64,
+
+    /// Average daily savings of the last year, used for the projection
+    pub average_daily_savings: f64,
+
+    /// When the savings cover the net cost
+    pub break_even: BreakEven,
+
+    /// Savings of each day with data, in order
+    pub days: Vec<DailySavings>,
+}
+
+impl Investment {
+    /// Create an investment to track.
+    ///
+    /// # Arguments
+    ///
+    /// * `system_cost` - price paid for the system
+    /// * `incentives` - rebates, tax credits and other incentives, None for none
+    /// * `import_rate` - price of energy bought from the grid, per kWh
+    /// * `export_rate` - price paid for exported energy, per kWh
+    /// * `start` - first day that counts towards payback
+    #[must_use]
+    pub fn new(
+        system_cost: f64,
+        incentives: Option<f64>,
+        import_rate: f64,
+        export_rate: f64,
+        start: NaiveDate,
+    ) -> Self {
+        Investment {
+            system_cost,
+            incentives: incentives.unwrap_or(0.0),
+            import_rate,
+            export_rate,
+            start,
+        }
+    }
+
+    /// System cost less incentives.
+    #[must_use]
+    pub fn net_cost(&self) -> f64 {
+        self.system_cost - self.incentives
+    }
+
+    /// Compute the payback from energy details.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - daily energy details with SelfConsumption and FeedIn meters,
+    ///   e.g. from [SiteEnergyDetailedResp](crate::SiteEnergyDetailedResp). Finer
+    ///   granularities are summed per day. Days before `start` are ignored.
+    /// * `currency` - currency of the costs and rates, e.g. from
+    ///   [SiteDetails::currency](crate::SiteDetails::currency)
+    #[must_use]
+    pub fn track(&self, series: &[Series], currency: &str) -> RoiReport {
+        let self_consumption = MeterType::SelfConsumption.to_string();
+        let feed_in = MeterType::FeedIn.to_string();
+
+        let mut energy: BTreeMap<NaiveDate, (f64, f64)> = BTreeMap::new();
+        for s in series {
+            let is_feed_in = s.name == feed_in;
+            if !is_feed_in && s.name != self_consumption {
+                continue;
+            }
+            let factor = kwh_factor(&s.unit);
+            for sample in &s.samples {
+                let date = sample.timestamp.date();
+                if date < self.start {
+                    continue;
+                }
+                let day = energy.entry(date).or_default();
+                let kwh = sample.value.unwrap_or(0.0) * factor;
+                if is_feed_in {
+                    day.1 += kwh;
+                } else {
+                    day.0 += kwh;
+                }
+            }
+        }
+
+        let mut cumulative = 0.0;
+        let days: Vec<DailySavings> = energy
+            .into_iter()
+            .map(|(date, (self_consumption, feed_in))| {
+                let savings = self_consumption * self.import_rate + feed_in * self.export_rate;
+                cumulative += savings;
+                DailySavings {
+                    date,
+                    self_consumption,
+                    feed_in,
+                    savings,
+                    cumulative,
+                }
+            })
+            .collect();
+
+        let net_cost = self.net_cost();
+        let recent = &days[days.len().saturating_sub(PROJECTION_DAYS)..];
+        #[allow(clippy::cast_precision_loss)]
+        let average_daily_savings = if recent.is_empty() {
+            0.0
+        } else {
+            recent.iter().map(|d| d.savings).sum::<f64>() / recent.len() as f64
+        };
+        let remaining = (net_cost - cumulative).max(0.0);
+
+        let break_even = if let Some(d) = days.iter().find(|d| d.cumulative >= net_cost) {
+            BreakEven::Reached(d.date)
+        } else if average_daily_savings > 0.0 {
+            let last = days.last().map_or(self.start, |d| d.date);
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let to_go = (remaining / average_daily_savings).ceil() as u64;
+            last.checked_add_days(Days::new(to_go))
+                .map_or(BreakEven::Never, BreakEven::Projected)
+        } else {
+            BreakEven::Never
+        };
+
+        RoiReport {
+            currency: currency.to_string(),
+            net_cost,
+            savings: cumulative,
+            remaining,
+            average_daily_savings,
+            break_even,
+            days,
+        }
+    }
+
+    /// Fetch the site's currency and daily energy details from the start of the
+    /// investment until today, and compute the payback.
+    ///
+    /// # Arguments
+    ///
+    /// * `solaredge` - SolarEdge credentials of the site
+    ///
+    /// # Errors
+    /// Returns an error if a request fails or a response date cannot be parsed.
+    pub fn fetch(&self, solaredge: &SolaredgeCredentials) -> Result<RoiReport, Error> {
+        let currency = SiteDetailsReq::new().send(solaredge)?.details.currency;
+
+        let today = chrono::Local::now().date_naive();
+        let series = DataEndpoint::EnergyDetails.fetch_range(
+            solaredge,
+            &TimeUnit::Day,
+            self.start.and_time(NaiveTime::MIN),
+            today.and_hms_opt(23, 59, 59).unwrap_or_default(),
+        )?;
+
+        Ok(self.track(&series, &currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, SiteEnergyDetailedResp, TimeSeries};
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, crate::URL_DATE_FORMAT).unwrap()
+    }
+
+    fn series() -> Vec<Series> {
+        let resp: SiteEnergyDetailedResp = serde_json::from_str(
+            r#"{"energyDetails": {"timeUnit": "DAY", "unit": "Wh", "meters": [
+                {"type": "Production", "values": [
+                    {"date": "2022-01-01 00:00:00", "value": 50000}]},
+                {"type": "SelfConsumption", "values": [
+                    {"date": "2021-12-31 00:00:00", "value": 10000},
+                    {"date": "2022-01-01 00:00:00", "value": 10000},
+                    {"date": "2022-01-02 00:00:00", "value": 20000},
+                    {"date": "2022-01-03 00:00:00"}]},
+                {"type": "FeedIn", "values": [
+                    {"date": "2022-01-01 00:00:00", "value": 20000},
+                    {"date": "2022-01-03 00:00:00", "value": 40000}]}
+            ]}}"#,
+        )
+        .unwrap();
+        resp.energy_details.time_series().unwrap()
+    }
+
+    #[test]
+    fn track_projected_unit_test() {
+        let investment = Investment::new(100.0, Some(20.0), 0.25, 0.1, day("2022-01-01"));
+        let report = investment.track(&series(), "USD");
+
+        // 10 kWh self-consumed at 0.25 and 20 kWh exported at 0.10, then 20 kWh
+        // self-consumed, then 40 kWh exported.
+        assert_eq!(report.days.len(), 3);
+        for (d, savings) in report.days.iter().zip([4.5, 5.0, 4.0]) {
+            assert!((d.savings - savings).abs() < 1e-9, "{d:?}");
+        }
+        assert_eq!(report.days[0].date, day("2022-01-01"));
+        assert!((report.savings - 13.5).abs() < 1e-9);
+        assert!((report.net_cost - 80.0).abs() < f64::EPSILON);
+        assert!((report.remaining - 66.5).abs() < 1e-9);
+        assert!((report.average_daily_savings - 4.5).abs() < 1e-9);
+        // 66.5 / 4.5 = 14.8, so 15 days after the last day with data.
+        assert_eq!(report.break_even, BreakEven::Projected(day("2022-01-18")));
+        assert_eq!(report.currency, "USD");
+    }
+
+    #[test]
+    fn track_reached_unit_test() {
+        let investment = Investment::new(9.0, None, 0.25, 0.1, day("2022-01-01"));
+        let report = investment.track(&series(), "EUR");
+        assert_eq!(report.break_even, BreakEven::Reached(day("2022-01-02")));
+        assert!(report.remaining.abs() < f64::EPSILON);
+
+        let report = investment.track(&[], "EUR");
+        assert_eq!(report.break_even, BreakEven::Never);
+        assert!(report.days.is_empty());
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<Investment>();
+        is_normal::<DailySavings>();
+        is_normal::<BreakEven>();
+        is_normal::<RoiReport>();
+    }
+}
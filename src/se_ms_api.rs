@@ -35,6 +35,79 @@
 //! Due to the restrictions that SolarEdge imposes on this API, this library
 //! does not try to be performant. For example, it makes blocking HTTP requests.
 //!
+//! With an account level api key, a [Fleet] sends the same request to every site of
+//! the account, staying within SolarEdge's concurrency and daily request limits.
+//!
+//! Enabling the `serialize` feature derives `serde::Serialize` for every response
+//! type, using the same field names as the API, so responses can be written back out
+//! (e.g. to a cache) and read in again into the same values. Optional keys the API
+//! may leave out are omitted when they have no value, whether the response sent them
+//! as null or left them out. Keys the API always sends, such as the value of a
+//! reading, are written as null.
+//!
+//! Enabling the `extras` feature collects response fields that are not in the
+//! documented schema into an `extras` map on each response struct. `unknown_fields()`
+//! lists the path of every such field in a response.
+//!
+//! # Time series
+//!
+//! The energy, power, meter and telemetry responses implement [TimeSeries], which
+//! turns them into a list of timestamped [Series].
+//!
+//! The time-series requests limit how long a span they return at once.
+//! [DataEndpoint::max_span] gives the limit and [windows] splits a longer range.
+//! Enabling the `store` feature adds [Store], a local SQLite history of a site's
+//! series that [Store::sync_site] keeps up to date incrementally.
+//!
+//! A [CsvWriter] writes series as tidy or wide CSV. Enabling the `parquet` feature
+//! adds [to_record_batch] and [write_parquet] for converting series to Arrow record
+//! batches and writing them as Parquet files. A [LineProtocol] writes power and
+//! energy details, the current power flow and inverter telemetries as InfluxDB line
+//! protocol, and [InfluxDb] posts it to a server.
+//!
+//! # Analysis
+//!
+//! A [QualityCheck] reports missing values and timestamps, duplicate timestamps
+//! and zero production in daylight in a series, taking daylight saving changes in
+//! the site's time zone into account. It fills the gaps with zeros, linear
+//! interpolation or the previous value, recording each [Fill] and every sample it
+//! left out in a [FillReport].
+//!
+//! A [Resampler] aggregates series to a coarser [TimeUnit] locally, summing energy
+//! and averaging power over calendar days, weeks, months and years.
+//! [integrate] turns quarter-hour power into energy, and an [EnergyCheck] compares
+//! it with the energy the server reports, flagging periods that differ too much.
+//!
+//! [interval_kpis] and [period_kpis] give the self-consumption ratio, self-sufficiency,
+//! export ratio and grid dependency of a site from its energy or power details.
+//! A [Reconciliation] checks that production and consumption equal the sum of
+//! their parts in every interval, and names likely meter misconfigurations.
+//! A [PowerFlowGraph] reads the current power flow as [Element]s with signed grid
+//! and storage power and the power along each connection, and checks it balances.
+//!
+//! # Costs and savings
+//!
+//! [fetch_period_energy] totals production, consumption, self-consumption, feed-in
+//! and purchased energy over [BillingPeriod]s, such as between meter read dates.
+//! An [Investment] tracks the payback of an installation from its self-consumption
+//! and feed-in, including the projected [BreakEven] date.
+//! A time-of-use [Tariff] prices quarter-hour or hourly purchased and fed-in
+//! energy per [Band], with fixed and demand charges.
+//! A [PriceSeries] read from a CSV file values them at spot prices instead, in
+//! the site's time zone, and points out where shifting purchases would have paid.
+//!
+//! # Binaries
+//!
+//! Enabling the `cli` feature builds `se-ms`, a command line tool with a subcommand
+//! for each request, printing responses as a table, JSON or CSV. The `exporter`
+//! feature builds `se-ms-exporter`, which serves a site's current power flow,
+//! energy and battery state as Prometheus metrics, and the `mqtt` feature builds
+//! `se-ms-mqtt`, which publishes them to an MQTT broker with Home Assistant
+//! discovery. [poll_interval] gives the time between polls that keeps such a
+//! poller within a daily request budget.
+//!
+//! # Requests
+//!
 //! Supported API requests/responses include:
 //! * [AccountsListReq] / [AccountsListResp]
 //! * [CurrentVersionReq] / [CurrentVersionResp]
@@ -74,11 +147,31 @@ pub use accounts_list::{
     AccountDetails, AccountLocation, Accounts, Entries as AccountListEntries,
     Req as AccountsListReq, Resp as AccountsListResp, SortProperty,
 };
+pub use alert_severity::AlertSeverity;
+pub use billing::{fetch_period_energy, period_energy, BillingPeriod, PeriodEnergy};
+pub use csv_export::CsvWriter;
 pub use current_version::{Req as CurrentVersionReq, Resp as CurrentVersionResp, Version};
+pub use data_quality::{
+    Fill, FillMethod, FillReport, Issue, IssueKind, QualityCheck, QualityReport,
+};
 pub use date_value::DateValue;
 pub use error::{Error, Kind};
+#[cfg(feature = "extras")]
+pub use extras::{unknown_fields, Extras, UnknownFields};
+pub use fleet::{Fleet, FleetReq, Progress, DAILY_REQUEST_LIMIT, MAX_CONCURRENT_REQUESTS};
+pub use influx::{InfluxDb, LineProtocol};
+pub use kpi::{interval_kpis, period_kpis, Kpi, SiteKpis};
 pub use meter_type::MeterType;
 pub use meter_value::MeterValue;
+pub use pagination::Paginated;
+#[cfg(feature = "parquet")]
+pub use parquet_export::{time_series_schema, to_record_batch, write_parquet};
+pub use poll::poll_interval;
+pub use power_energy::{integrate, EnergyCheck, EnergyComparison};
+pub use power_flow::{Element, Flow, PowerFlowGraph};
+pub use reconcile::{BalanceReport, Identity, Reconciliation, Suspicion, Violation};
+pub use resample::{Aggregation, Resampler};
+pub use roi::{BreakEven, DailySavings, Investment, RoiReport};
 use serde::Deserialize;
 pub use site_data_period::{Req as SiteDataPeriodReq, Resp as SiteDataPeriodResp, SiteDataPeriod};
 pub use site_details::{Req as SiteDetailsReq, Resp as SiteDetailsResp, SiteDetails};
@@ -110,10 +203,13 @@ pub use site_inventory::{
     Sensor as SiteInventorySensor,
 };
 pub use site_inverter_technical_data::{
-    InverterData, InverterMode, LxData, Req as SiteInverterTechnicalDataReq,
+    InverterData, InverterMode, LxData, OperationMode, Req as SiteInverterTechnicalDataReq,
     Resp as SiteInverterTechnicalDataResp, Telemetries, Telemetry,
 };
-pub use site_list::{Entries as SiteListEntries, Req as SiteListReq, Resp as SiteListResp, Sites};
+pub use site_list::{
+    Entries as SiteListEntries, Req as SiteListReq, Resp as SiteListResp, Sites,
+    SortProperty as SiteListSortProperty, Status as SiteListStatus,
+};
 pub use site_location::SiteLocation;
 pub use site_module::SiteModule;
 pub use site_overview::{
@@ -124,28 +220,54 @@ pub use site_power_detailed::{
     PowerDetails, Req as SitePowerDetailedReq, Resp as SitePowerDetailedResp,
 };
 pub use site_power_flow::{
-    Connections, Parameters, Req as SitePowerFlowReq, Resp as SitePowerFlowResp,
+    Connections, ElementStatus, Parameters, Req as SitePowerFlowReq, Resp as SitePowerFlowResp,
     SiteCurrentPowerFlow,
 };
 pub use site_public_settings::SitePublicSettings;
+pub use site_status::SiteStatus;
 pub use site_storage_data::{
     Batteries, Battery as SiteStorageDataBattery, Req as SiteStorageDataReq,
-    Resp as SiteStorageDataResp, StorageData,
+    Resp as SiteStorageDataResp, StorageData, StorageTelemetry,
 };
 pub use site_time_frame_energy::{
     Req as SiteTimeFrameEnergyReq, Resp as SiteTimeFrameEnergyResp, TimeFrameEnergy,
 };
+pub use site_type::SiteType;
 pub use sort_order::SortOrder;
+pub use spot_price::{HourValue, PriceSeries, ShiftOpportunity, SpotValuation};
+#[cfg(feature = "store")]
+pub use store::{Store, SyncReport};
 pub use supported_versions::{Release, Req as SupportedVersionsReq, Resp as SupportedVersionsResp};
 pub use system_units::SystemUnits;
+pub use tariff::{Band, BandCost, DayType, Season, Tariff, TariffCost};
+pub use time_series::{Sample, Series, TimeSeries};
 pub use time_unit::TimeUnit;
+pub use windows::{period_start, windows, DataEndpoint, MaxSpan};
 
 mod accounts_list;
+mod alert_severity;
+mod billing;
+mod csv_export;
 mod current_version;
+mod data_quality;
 mod date_value;
 mod error;
+mod extras;
+mod fleet;
+mod influx;
+mod kpi;
+mod lenient;
 mod meter_type;
 mod meter_value;
+mod pagination;
+#[cfg(feature = "parquet")]
+mod parquet_export;
+mod poll;
+mod power_energy;
+mod power_flow;
+mod reconcile;
+mod resample;
+mod roi;
 mod site_data_period;
 mod site_details;
 mod site_energy;
@@ -165,20 +287,35 @@ mod site_power;
 mod site_power_detailed;
 mod site_power_flow;
 mod site_public_settings;
+mod site_status;
 mod site_storage_data;
 mod site_time_frame_energy;
+mod site_type;
 mod sort_order;
+mod spot_price;
+#[cfg(feature = "store")]
+mod store;
 mod supported_versions;
 mod system_units;
+mod tariff;
+mod time_series;
 mod time_unit;
+mod windows;
 
 #[macro_use]
 extern crate lazy_static;
 
-lazy_static! {
-    static ref REQWEST_CLIENT: reqwest::blocking::Client = reqwest::blocking::Client::new();
-    static ref MONITORING_API_URL: String = "https://monitoringapi.solaredge.com/".to_string();
+// These statics predate std::sync::LazyLock; only they are exempt from the lint.
+#[allow(clippy::non_std_lazy_statics)]
+mod statics {
+    lazy_static! {
+        pub(crate) static ref REQWEST_CLIENT: reqwest::blocking::Client =
+            reqwest::blocking::Client::new();
+        pub(crate) static ref MONITORING_API_URL: String =
+            "https://monitoringapi.solaredge.com/".to_string();
+    }
 }
+use statics::{MONITORING_API_URL, REQWEST_CLIENT};
 
 const URL_DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 const URL_DATE_FORMAT: &str = "%Y-%m-%d";
@@ -216,6 +353,20 @@ impl SolaredgeCredentials {
     pub fn site_id(&self) -> &str {
         &self.site_id
     }
+
+    /// Credentials for another site, using the same api key.
+    /// Used with account level api keys to reach each of the account's sites.
+    ///
+    /// # Arguments
+    ///
+    /// * `site_id` - ID used by SolarEdge to identify the other site.
+    #[must_use]
+    pub fn for_site(&self, site_id: &str) -> Self {
+        SolaredgeCredentials {
+            site_id: site_id.to_string(),
+            api_key: self.api_key.clone(),
+        }
+    }
 }
 
 /// All Solaredge requests implement this trait since sending the request
@@ -241,31 +392,57 @@ pub trait SendReq<Resp> {
     {
         let url = self.build_url(&solaredge.site_id, &solaredge.api_key);
 
-        let res = REQWEST_CLIENT.get(url).send()?;
+        get(url)
+    }
+}
+
+/// Send a GET request to the SolarEdge server and parse the JSON response.
+pub(crate) fn get<Resp>(url: String) -> Result<Resp, Error>
+where
+    for<'de> Resp: Deserialize<'de>,
+{
+    let res = REQWEST_CLIENT.get(url).send()?;
 
-        if res.status().is_success() {
-            let parsed = res.json::<Resp>()?;
+    if res.status().is_success() {
+        let parsed = res.json::<Resp>()?;
 
-            Ok(parsed)
-        } else {
-            let reason = match res.status().canonical_reason() {
-                Some(r) => r.to_string(),
-                None => res.status().as_str().to_string(),
-            };
+        Ok(parsed)
+    } else {
+        let reason = match res.status().canonical_reason() {
+            Some(r) => r.to_string(),
+            None => res.status().as_str().to_string(),
+        };
 
-            let text = match res.text() {
-                Ok(t) => t,
-                Err(_) => String::new(),
-            };
+        let text = res.text().unwrap_or_default();
 
-            Err(Error::new(Kind::HttpErrorStatus(reason, text)))
-        }
+        Err(Error::new(Kind::HttpErrorStatus(reason, text)))
     }
 }
 
+#[cfg(any(test, feature = "cli", feature = "mqtt"))]
+use serde_json as _;
+
+#[cfg(any(feature = "cli", feature = "exporter", feature = "mqtt"))]
+use clap as _;
+
+#[cfg(feature = "mqtt")]
+use rumqttc as _;
+
 #[cfg(test)]
 pub(crate) fn is_normal<T: Sized + Send + Sync + Unpin>() {}
 
+#[cfg(all(test, feature = "serialize"))]
+pub(crate) fn round_trip<T>(json: &str)
+where
+    T: serde::de::DeserializeOwned + serde::Serialize,
+{
+    let expected: serde_json::Value = serde_json::from_str(json).unwrap();
+    let parsed: T = serde_json::from_str(json).unwrap();
+    let written = serde_json::to_string(&parsed).unwrap();
+    let actual: serde_json::Value = serde_json::from_str(&written).unwrap();
+    assert_eq!(actual, expected);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -276,6 +453,10 @@ mod tests {
         assert_eq!(se.site_id, "id");
         assert_eq!(se.site_id(), "id");
         assert_eq!(se.api_key, "api_key=key");
+
+        let other = se.for_site("other");
+        assert_eq!(other.site_id(), "other");
+        assert_eq!(other.api_key, "api_key=key");
     }
 
     #[test]
@@ -1,5 +1,6 @@
 //! Module for querying the energy production start and end dates of the site.
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, MONITORING_API_URL};
 use serde::Deserialize;
 
@@ -9,22 +10,40 @@ pub struct Req;
 
 /// site_data_period response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// Period of time site has been producing.
     pub data_period: SiteDataPeriod,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    data_period: "dataPeriod",
+});
+
 /// Period of time site has been producing.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct SiteDataPeriod {
     /// Start date of energy production.
     pub start_date: Option<String>,
     /// End date of energy production.
     pub end_date: Option<String>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(SiteDataPeriod {});
+
 impl Req {
     /// Create a site_data_period request message that can be sent to SolarEdge.
     #[must_use]
@@ -53,6 +72,14 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{"dataPeriod": {"startDate": "2018-02-08", "endDate": null}}"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
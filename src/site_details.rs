@@ -1,6 +1,10 @@
 //! Module for site details requests and responses exchanged with the SolarEdge server monitoring API.
 
-use crate::{SendReq, SiteLocation, SiteModule, SitePublicSettings, MONITORING_API_URL};
+use crate::extras::impl_unknown_fields;
+use crate::{
+    AlertSeverity, SendReq, SiteLocation, SiteModule, SitePublicSettings, SiteStatus, SiteType,
+    MONITORING_API_URL,
+};
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -10,13 +14,22 @@ pub struct Req;
 
 /// site_details response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Resp {
     /// Detailed information about the monitoring site
     pub details: SiteDetails,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp { details: "details" });
+
 /// Detailed information for a single site.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct SiteDetails {
     /// ID of the site. Should match the site_id specified in the Solaredge request.
@@ -29,9 +42,10 @@ pub struct SiteDetails {
     pub account_id: u32,
 
     /// Site status, either Active or Pending Communication.
-    pub status: String,
+    pub status: SiteStatus,
 
     /// Maximum power that can be generated by the site.
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub peak_power: f32,
 
     /// Last time the site reported in to SolarEdge.
@@ -51,7 +65,7 @@ pub struct SiteDetails {
 
     /// Site type, ("Optimizers & Inverters", "Safety & Monitoring Interface", "Monitoring Combiner Boxes")
     #[serde(rename = "type")]
-    pub site_type: String,
+    pub site_type: SiteType,
 
     /// Site location (mailing address)
     pub location: SiteLocation,
@@ -60,18 +74,31 @@ pub struct SiteDetails {
     pub primary_module: SiteModule,
 
     /// Number of open alerts at the site.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub alert_quantity: Option<u32>,
 
     /// Highest alert severity at the site.
-    pub alert_severity: Option<String>,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub alert_severity: Option<AlertSeverity>,
 
     /// Miscellaneous uris associated with the web page for the site.
     pub uris: HashMap<String, String>,
 
     /// Public settings of the web page for the site.
     pub public_settings: SitePublicSettings,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(SiteDetails {
+    location: "location",
+    primary_module: "primaryModule",
+    public_settings: "publicSettings",
+});
+
 impl Req {
     /// Create a site details request message that can be sent to SolarEdge.
     #[must_use]
@@ -100,6 +127,49 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "details": {
+                "id": 1,
+                "name": "Home",
+                "accountId": 2,
+                "status": "Active",
+                "peakPower": 9.8,
+                "lastUpdateTime": "2022-01-01 12:00:00",
+                "currency": "USD",
+                "installationDate": "2018-02-08",
+                "ptoDate": null,
+                "notes": "",
+                "type": "Optimizers & Inverters",
+                "location": {
+                    "country": "United States",
+                    "state": "Texas",
+                    "city": "Austin",
+                    "address": "1 Main St",
+                    "address2": "",
+                    "zip": "78701",
+                    "timeZone": "America/Chicago",
+                    "countryCode": "US",
+                    "stateCode": "TX"
+                },
+                "primaryModule": {
+                    "manufacturerName": "LG",
+                    "modelName": "LG335N1C-A5",
+                    "maximumPower": 335.0,
+                    "temperatureCoef": -0.36
+                },
+                "alertQuantity": 0,
+                "alertSeverity": "LOW",
+                "uris": {"SITE_IMAGE": "/site/1/siteImage/image.jpg"},
+                "publicSettings": {"isPublic": false}
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
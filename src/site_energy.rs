@@ -1,6 +1,11 @@
 //! Module for querying the site energy measurements.
 
-use crate::{DateValue, SendReq, TimeUnit, MONITORING_API_URL, URL_DATE_FORMAT};
+use crate::extras::impl_unknown_fields;
+use crate::fleet::MAX_BULK_SITES;
+use crate::{
+    DateValue, Error, FleetReq, SendReq, SolaredgeCredentials, TimeUnit, MONITORING_API_URL,
+    URL_DATE_FORMAT,
+};
 use serde::Deserialize;
 
 /// site_energy request
@@ -13,14 +18,23 @@ pub struct Req {
 
 /// site_energy response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// Energy measurements.
     pub energy: Energy,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp { energy: "energy" });
+
 /// Energy measurements.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Energy {
     /// Granularity of the energy measurements (should match the request)
@@ -31,6 +45,71 @@ pub struct Energy {
 
     /// For the dates requested, measurements over the time period
     pub values: Vec<DateValue>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
+}
+
+impl_unknown_fields!(Energy { values: "values" });
+
+/// site_energy bulk response, for several sites in one request
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkResp {
+    sites_energy: BulkEnergy,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkEnergy {
+    time_unit: TimeUnit,
+    unit: String,
+    site_energy_list: Vec<BulkEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkEntry {
+    site_id: u32,
+    energy_values: BulkValues,
+}
+
+#[derive(Deserialize)]
+struct BulkValues {
+    values: Vec<DateValue>,
+}
+
+impl BulkResp {
+    fn into_site_resps(self) -> Vec<(u32, Resp)> {
+        let BulkEnergy {
+            time_unit,
+            unit,
+            site_energy_list,
+        } = self.sites_energy;
+
+        site_energy_list
+            .into_iter()
+            .map(|e| {
+                let energy = Energy {
+                    time_unit: time_unit.clone(),
+                    unit: unit.clone(),
+                    values: e.energy_values.values,
+                    #[cfg(feature = "extras")]
+                    extras: crate::Extras::default(),
+                };
+                (
+                    e.site_id,
+                    Resp {
+                        energy,
+                        #[cfg(feature = "extras")]
+                        extras: crate::Extras::default(),
+                    },
+                )
+            })
+            .collect()
+    }
 }
 
 impl Req {
@@ -73,11 +152,81 @@ impl SendReq<Resp> for Req {
     }
 }
 
+impl FleetReq<Resp> for Req {
+    fn send_bulk(
+        &self,
+        site_ids: &[u32],
+        solaredge: &SolaredgeCredentials,
+    ) -> Option<Result<Vec<(u32, Resp)>, Error>> {
+        if site_ids.is_empty() || site_ids.len() > MAX_BULK_SITES {
+            return None;
+        }
+        let site_ids = site_ids
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let url = format!(
+            "{}sites/{}/energy?{}{}{}{}",
+            *MONITORING_API_URL,
+            site_ids,
+            self.time_unit,
+            self.start_date,
+            self.end_date,
+            solaredge.api_key,
+        );
+
+        Some(crate::get::<BulkResp>(url).map(BulkResp::into_site_resps))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "energy": {
+                "timeUnit": "DAY",
+                "unit": "Wh",
+                "values": [
+                    {"date": "2022-01-01 00:00:00", "value": 12926.0},
+                    {"date": "2022-01-02 00:00:00", "value": null}
+                ]
+            }
+        }"#,
+        );
+    }
+
+    #[test]
+    fn bulk_resp_unit_test() {
+        let json = r#"{
+            "sitesEnergy": {
+                "timeUnit": "DAY",
+                "unit": "Wh",
+                "count": 2,
+                "siteEnergyList": [
+                    {"siteId": 1, "energyValues": {"measuredBy": "INVERTER", "values": [
+                        {"date": "2022-01-01 00:00:00", "value": 12926.0}]}},
+                    {"siteId": 2, "energyValues": {"measuredBy": "METER", "values": [
+                        {"date": "2022-01-01 00:00:00", "value": null}]}}
+                ]
+            }
+        }"#;
+        let bulk: BulkResp = serde_json::from_str(json).unwrap();
+        let resps = bulk.into_site_resps();
+        assert_eq!(resps.len(), 2);
+        assert_eq!(resps[0].0, 1);
+        assert_eq!(resps[0].1.energy.time_unit, TimeUnit::Day);
+        assert_eq!(resps[0].1.energy.values[0].value, Some(12926.0));
+        assert_eq!(resps[1].1.energy.unit, "Wh");
+        assert_eq!(resps[1].1.energy.values[0].value, None);
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
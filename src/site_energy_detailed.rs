@@ -1,5 +1,6 @@
 //! Module for detailed site energy measurements from meters such as consumption, export (feed-in), import (purchase), etc.
 
+use crate::extras::impl_unknown_fields;
 use crate::{MeterType, MeterValue, SendReq, TimeUnit, MONITORING_API_URL, URL_DATE_TIME_FORMAT};
 use serde::Deserialize;
 
@@ -14,14 +15,25 @@ pub struct Req {
 
 /// site_energyDetails response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// Energy details
     pub energy_details: EnergyDetails,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    energy_details: "energyDetails",
+});
+
 /// Energy details
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct EnergyDetails {
     /// Granularity of the energy detail values (should match the request)
@@ -32,8 +44,15 @@ pub struct EnergyDetails {
 
     /// For the meter types requested, energy values over the time period
     pub meters: Vec<MeterValue>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(EnergyDetails { meters: "meters" });
+
 impl Req {
     /// Create an energy details request message that can be sent to SolarEdge.
     ///
@@ -42,8 +61,8 @@ impl Req {
     /// * `start_time` - beginning of the time period for the energy details
     /// * `end_time`   - end of the time period for the energy details
     /// * `time_unit`  - aggregation granularity
-    ///                  For the time period requested, energy detail values will be
-    ///                  chunked into units of this size.
+    ///   For the time period requested, energy detail values will be
+    ///   chunked into units of this size.
     /// * `meters`     - meter types to collect energy details for
     #[must_use]
     pub fn new(
@@ -116,6 +135,23 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "energyDetails": {
+                "timeUnit": "DAY",
+                "unit": "Wh",
+                "meters": [{
+                    "type": "SelfConsumption",
+                    "values": [{"date": "2022-01-01 00:00:00", "value": 9012.5}]
+                }]
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
@@ -1,6 +1,7 @@
 //! Module for getting all environmental benefits based on site energy production:
 //! CO2 emissions saved, equivalent trees planted, and light bulbs powered for a day.
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, SystemUnits, MONITORING_API_URL};
 use serde::Deserialize;
 
@@ -13,43 +14,76 @@ pub struct Req {
 
 /// site_environmental_benefits response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// Environmental benefits of the monitoring site
     pub env_benefits: EnvBenefits,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    env_benefits: "envBenefits",
+});
+
 /// Environmental benefits of the monitoring site
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct EnvBenefits {
     /// Quantity of CO2 emissions that would have been generated by an equivalent fossil fuel system.
     pub gas_emission_saved: GasEmissionSaved,
 
     /// Equivalent planting of new trees for reducing CO2 levels.
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub trees_planted: f32,
 
     /// Number of light bulbs that could have been powered by the site for a day.
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub light_bulbs: f32,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(EnvBenefits {
+    gas_emission_saved: "gasEmissionSaved",
+});
+
 /// Environmental benefits of the monitoring site
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct GasEmissionSaved {
     /// Measurement unit of following gases.
     pub units: String,
 
     /// Carbon Dioxide
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub co2: f32,
 
     /// Sulphur Dioxide
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub so2: f32,
 
     /// Nitrous Oxide
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub nox: f32,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(GasEmissionSaved {});
+
 impl Req {
     /// Create a site environmental benefits request message that can be sent to SolarEdge.
     #[must_use]
@@ -77,6 +111,20 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "envBenefits": {
+                "gasEmissionSaved": {"units": "kg", "co2": 36276.5, "so2": 26213.25, "nox": 8359.5},
+                "treesPlanted": 604.5,
+                "lightBulbs": 156510.0
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
@@ -1,6 +1,7 @@
 //! Module for getting a list of equipment component replacements ordered by date.
 //! This method is applicable to inverters, optimizers, batteries and gateways
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, MONITORING_API_URL};
 use serde::Deserialize;
 
@@ -12,14 +13,25 @@ pub struct Req {
 
 /// site_equipment_change_log response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "PascalCase")]
 pub struct Resp {
     /// Equipment change history
     pub change_log: ChangeLog,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    change_log: "ChangeLog",
+});
+
 /// Equipment change history
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct ChangeLog {
     /// Number of entries in the change list
@@ -27,10 +39,18 @@ pub struct ChangeLog {
 
     /// List of changes for the equipment
     pub list: Vec<ChangeEntry>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(ChangeLog { list: "list" });
+
 /// Equipment change record
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct ChangeEntry {
     /// Equipment short serial number
@@ -41,8 +61,15 @@ pub struct ChangeEntry {
 
     /// Date of replacement of that equipment component
     pub date: String,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(ChangeEntry {});
+
 impl Req {
     /// Create an power details request message that can be sent to SolarEdge.
     ///
@@ -71,6 +98,19 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "ChangeLog": {
+                "count": 1,
+                "list": [{"serialNumber": "7308CC3E-85", "partNumber": "SE7600H", "date": "2020-07-31"}]
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
@@ -1,5 +1,6 @@
 //! Module for getting a list of inverters/SMIs in the specific site.
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, MONITORING_API_URL};
 use serde::Deserialize;
 
@@ -9,14 +10,25 @@ pub struct Req;
 
 /// site_equipment_list response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// List size and list
     pub reporters: Reporters,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    reporters: "reporters",
+});
+
 /// List size and list
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Reporters {
     /// Number of entries in the equipment list
@@ -24,18 +36,29 @@ pub struct Reporters {
 
     /// List of equipment
     pub list: EquipmentList,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Reporters { list: "list" });
+
 /// List of equipment
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(transparent)]
 pub struct EquipmentList {
     /// Transparent list of equipment
     pub eq: Vec<Equipment>,
 }
 
+impl_unknown_fields!(transparent EquipmentList, eq);
+
 /// Details on a single piece of equipment
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Equipment {
     /// Equipment's name
@@ -50,11 +73,27 @@ pub struct Equipment {
     /// Equipment's serial number
     pub serial_number: String,
 
-    /// Kilowatts peak DC
+    /// Kilowatts peak DC. Sent as a numeric string, which is accepted as a number
+    /// and written back as a string.
     #[serde(rename = "kWpDC")]
-    pub kw_pdc: Option<String>,
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    #[cfg_attr(
+        feature = "serialize",
+        serde(
+            skip_serializing_if = "Option::is_none",
+            serialize_with = "crate::lenient::option_number_string"
+        )
+    )]
+    pub kw_pdc: Option<f32>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Equipment {});
+
 impl Req {
     /// Create a site environmental benefits request message that can be sent to SolarEdge.
     #[must_use]
@@ -83,6 +122,37 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "reporters": {
+                "count": 1,
+                "list": [{
+                    "name": "Inverter 1",
+                    "manufacturer": "SolarEdge",
+                    "model": "SE7600H-US000BNU4",
+                    "serialNumber": "7308CC3E-85",
+                    "kWpDC": "7.6"
+                }]
+            }
+        }"#,
+        );
+    }
+
+    #[test]
+    fn lenient_numbers_unit_test() {
+        let json = r#"[
+            {"name": "Inverter 1", "manufacturer": "SolarEdge", "model": "SE7600H",
+             "serialNumber": "7308CC3E-85", "kWpDC": "7.6"},
+            {"name": "Gateway 1", "manufacturer": "", "model": "", "serialNumber": "7F123456-12"}
+        ]"#;
+        let equipment: Vec<Equipment> = serde_json::from_str(json).unwrap();
+        assert_eq!(equipment[0].kw_pdc, Some(7.6));
+        assert_eq!(equipment[1].kw_pdc, None);
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
@@ -1,5 +1,6 @@
 //! Module for each meter on site its lifetime energy reading, metadata and the device to which it’s connected to.
 
+use crate::extras::impl_unknown_fields;
 use crate::{DateValue, MeterType, SendReq, TimeUnit, MONITORING_API_URL, URL_DATE_TIME_FORMAT};
 use serde::Deserialize;
 
@@ -14,14 +15,25 @@ pub struct Req {
 
 /// site_get_meters_data response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// Meter energy details
     pub meter_energy_details: MeterEnergyDetails,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    meter_energy_details: "meterEnergyDetails",
+});
+
 /// Meter energy details
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct MeterEnergyDetails {
     /// Granularity of the energy detail values (should match the request)
@@ -32,10 +44,18 @@ pub struct MeterEnergyDetails {
 
     /// For the meter types requested, meter info and energy values over the time period
     pub meters: Vec<Meter>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(MeterEnergyDetails { meters: "meters" });
+
 /// Meter details
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Meter {
     /// Serial number of the meter
@@ -53,8 +73,15 @@ pub struct Meter {
 
     /// energy values over the time period
     pub values: Vec<DateValue>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Meter { values: "values" });
+
 impl Req {
     /// Create an energy details request message that can be sent to SolarEdge.
     ///
@@ -63,8 +90,8 @@ impl Req {
     /// * `start_time` - beginning of the time period for the energy details
     /// * `end_time`   - end of the time period for the energy details
     /// * `time_unit`  - aggregation granularity
-    ///                  For the time period requested, energy detail values will be
-    ///                  chunked into units of this size.
+    ///   For the time period requested, energy detail values will be
+    ///   chunked into units of this size.
     /// * `meters`     - meter types to collect energy details for
     #[must_use]
     pub fn new(
@@ -137,6 +164,26 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "meterEnergyDetails": {
+                "timeUnit": "DAY",
+                "unit": "Wh",
+                "meters": [{
+                    "meterSerialNumber": "1234567",
+                    "connectedSolaredgeDeviceSN": "7308CC3E-85",
+                    "model": "WNC-3D-240-MB",
+                    "meterType": "FeedIn",
+                    "values": [{"date": "2022-01-01 00:00:00", "value": 30233284.0}]
+                }]
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
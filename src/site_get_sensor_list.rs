@@ -1,5 +1,6 @@
 //! Module for getting a list of all the sensors in the site, and the device to which they are connected.
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, MONITORING_API_URL};
 use serde::Deserialize;
 
@@ -9,14 +10,25 @@ pub struct Req;
 
 /// site_get_sensor_list response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "PascalCase")]
 pub struct Resp {
     /// The list of sensors installed in the site associated with the gateway they are connected with.
     pub site_sensors: SiteSensors,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    site_sensors: "SiteSensors",
+});
+
 /// The list of sensors installed in the site associated with the gateway they are connected with.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct SiteSensors {
     /// Number of gateways in the list.
@@ -24,18 +36,29 @@ pub struct SiteSensors {
 
     /// list of gateways
     pub list: Gateways,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(SiteSensors { list: "list" });
+
 /// List of gateways
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(transparent)]
 pub struct Gateways {
     /// Transparent list of gateways
     pub g: Vec<Gateway>,
 }
 
+impl_unknown_fields!(transparent Gateways, g);
+
 /// Sensor information for a gateway
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Gateway {
     /// Gateway that the sensors are connected to
@@ -46,18 +69,29 @@ pub struct Gateway {
 
     /// List of sensors
     pub sensors: Sensors,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Gateway { sensors: "sensors" });
+
 /// List of sensors
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(transparent)]
 pub struct Sensors {
     /// Transparent list of sensors
     pub s: Vec<Sensor>,
 }
 
+impl_unknown_fields!(transparent Sensors, s);
+
 /// Information for a single sensor
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Sensor {
     /// Name of the sensor
@@ -69,8 +103,15 @@ pub struct Sensor {
     /// Sensor type
     #[serde(rename = "type")]
     pub sensor_type: String,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Sensor {});
+
 impl Req {
     /// Create a site details request message that can be sent to SolarEdge.
     #[must_use]
@@ -99,6 +140,23 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "SiteSensors": {
+                "total": 1,
+                "list": [{
+                    "connectedTo": "Gateway 1",
+                    "count": 1,
+                    "sensors": [{"name": "Irradiance", "measurement": "SensorGlobalHorizontalIrradiance", "type": "IRRADIANCE"}]
+                }]
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
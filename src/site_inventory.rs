@@ -1,6 +1,7 @@
 //! Module for getting the inventory of SolarEdge equipment in the site,
 //! including inverters/SMIs, batteries, meters, gateways and sensors.
 
+use crate::extras::impl_unknown_fields;
 use crate::{MeterType, SendReq, MONITORING_API_URL};
 use serde::Deserialize;
 
@@ -10,14 +11,25 @@ pub struct Req;
 
 /// site_inventory response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "PascalCase")]
 pub struct Resp {
     /// List size and list
     pub inventory: Inventory,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    inventory: "Inventory",
+});
+
 /// Inventory of site equipment
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Inventory {
     /// List of site meters
@@ -34,19 +46,35 @@ pub struct Inventory {
 
     /// List of site inverters
     pub inverters: Vec<Inverter>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Inventory {
+    meters: "meters",
+    sensors: "sensors",
+    gateways: "gateways",
+    batteries: "batteries",
+    inverters: "inverters",
+});
+
 /// Meter info
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Meter {
     /// Meter's name
     pub name: String,
 
     /// Meter's manufacturer
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub manufacturer: Option<String>,
 
     /// Meter model
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub model: Option<String>,
 
     /// Meter's firmware version
@@ -68,11 +96,20 @@ pub struct Meter {
 
     /// Meter serial number
     #[serde(rename = "SN")]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub sn: Option<String>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Meter {});
+
 /// Sensor info
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Sensor {
     /// Serial number of connected SolarEdge device
@@ -91,10 +128,18 @@ pub struct Sensor {
     /// Sensor type
     #[serde(rename = "type")]
     pub sensor_type: String,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Sensor {});
+
 /// Gateway info
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Gateway {
     /// Gateway name
@@ -109,10 +154,18 @@ pub struct Gateway {
 
     /// CPU version
     pub cpu_version: String,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Gateway {});
+
 /// Battery info
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Battery {
     /// Battery's name
@@ -130,16 +183,34 @@ pub struct Battery {
     /// Serial number of connected inverter
     pub connected_inverter_sn: String,
 
-    /// The nameplate capacity of the battery as provided by the manufacturer
-    pub nameplate_capacity: String,
+    /// The nameplate capacity of the battery as provided by the manufacturer. Sent
+    /// as a numeric string, which is accepted as a number and written back as a
+    /// string.
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    #[cfg_attr(
+        feature = "serialize",
+        serde(
+            skip_serializing_if = "Option::is_none",
+            serialize_with = "crate::lenient::option_number_string"
+        )
+    )]
+    pub nameplate_capacity: Option<f64>,
 
     /// Battery serial number
     #[serde(rename = "SN")]
     pub sn: String,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Battery {});
+
 /// Inverter info
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Inverter {
     /// Inverter's name
@@ -163,8 +234,15 @@ pub struct Inverter {
 
     /// number of optimizers connected to the inverter
     pub connected_optimizers: u32,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Inverter {});
+
 impl Req {
     /// Create a site environmental benefits request message that can be sent to SolarEdge.
     #[must_use]
@@ -193,6 +271,59 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "Inventory": {
+                "meters": [{
+                    "name": "Production Meter",
+                    "manufacturer": "SolarEdge",
+                    "model": "WNC-3D-240-MB",
+                    "firmwareVersion": "18",
+                    "connectedTo": "Inverter 1",
+                    "connectedSolaredgeDeviceSN": "7308CC3E-85",
+                    "type": "Production",
+                    "form": "physical",
+                    "SN": "1234567"
+                }],
+                "sensors": [{
+                    "connectedSolaredgeDeviceSN": "7308CC3E-85",
+                    "id": "SensorGlobalHorizontalIrradiance",
+                    "connectedTo": "Gateway 1",
+                    "category": "IRRADIANCE",
+                    "type": "Global Horizontal Irradiance"
+                }],
+                "gateways": [{
+                    "name": "Gateway 1",
+                    "communicationMethod": "ETHERNET",
+                    "SN": "7F123456-12",
+                    "cpuVersion": "4.12.36"
+                }],
+                "batteries": [{
+                    "name": "Battery 1",
+                    "manufacturer": "LG",
+                    "model": "RESU10H",
+                    "firmwareVersion": "1.2",
+                    "connectedInverterSn": "7308CC3E-85",
+                    "nameplateCapacity": "9800.0",
+                    "SN": "B1234"
+                }],
+                "inverters": [{
+                    "name": "Inverter 1",
+                    "manufacturer": "SolarEdge",
+                    "model": "SE7600H-US000BNU4",
+                    "communicationMethod": "ZIGBEE",
+                    "cpuVersion": "4.13.74",
+                    "SN": "7308CC3E-85",
+                    "connectedOptimizers": 22
+                }]
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
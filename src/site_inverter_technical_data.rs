@@ -1,5 +1,6 @@
 //! Module for specific inverter data for a given time frame.
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, MONITORING_API_URL, URL_DATE_TIME_FORMAT};
 use serde::Deserialize;
 
@@ -13,94 +14,146 @@ pub struct Req {
 
 /// site_inverter_technical_data response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// inverter technical data
     pub data: InverterData,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp { data: "data" });
+
 /// Inverter data for each telemetry
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct InverterData {
     /// Number of telemetries in the list
     pub count: u32,
     /// List of telemetries
     pub telemetries: Telemetries,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(InverterData {
+    telemetries: "telemetries",
+});
+
 /// Array of telemetries
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(transparent)]
 pub struct Telemetries {
     /// Transparent list of accounts
     pub t: Vec<Telemetry>,
 }
 
+impl_unknown_fields!(transparent Telemetries, t);
+
 /// Data for a single telemetry
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Telemetry {
     /// Date of telemetry collected
     pub date: String,
 
     /// Total active power
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
     pub total_active_power: Option<f32>,
 
     /// DC voltage
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
     pub dc_voltage: Option<f32>,
 
     /// Power limit
-    pub power_limit: f32,
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    pub power_limit: Option<f32>,
 
     /// Total energy
-    pub total_energy: f32,
+    #[serde(deserialize_with = "crate::lenient::number")]
+    pub total_energy: f64,
 
     /// Celsius
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub temperature: f32,
 
     /// Operating mode of inverter
     pub inverter_mode: InverterMode,
 
-    /// 0 – On-grid
-    /// 1 – Operating in off-grid mode using PV or battery
-    /// 2 - Operating in off-grid mode with generator (e.g. diesel) is present
-    pub operation_mode: u16,
+    /// Operation mode of inverter (on-grid or one of the off-grid modes)
+    pub operation_mode: OperationMode,
 
     /// Data for phase level 1
     #[serde(rename = "L1Data")]
     pub l1_data: LxData,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Telemetry { l1_data: "L1Data" });
+
 /// Data for a phase level
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct LxData {
     /// AC current
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub ac_current: f32,
 
     /// AC voltage
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub ac_voltage: f32,
 
     /// AC frequency
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub ac_frequency: f32,
 
     /// Apparent power
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub apparent_power: f32,
 
     /// Active power
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub active_power: f32,
 
     /// Reactive power
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub reactive_power: f32,
 
     /// cos phi?
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub cos_phi: f32,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(LxData {});
+
 /// Inverter operating mode
 #[derive(Clone, Deserialize, Debug, PartialEq, Default)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize),
+    serde(into = "String")
+)]
+#[serde(from = "String")]
 pub enum InverterMode {
     /// Off
     #[default]
@@ -145,20 +198,71 @@ pub enum InverterMode {
     LockedInvArcDetected,
 
     /// Inverter lock due to DG mode enable
-    #[serde(rename = "LOCKED_DG")]
     LockedDG,
 
     /// MPPT?
-    #[serde(rename = "MPPT")]
     Mppt,
 
     /// Sleeping
     Sleeping,
+
+    /// A mode not listed in the API documentation.
+    Unknown(String),
+}
+
+impl From<String> for InverterMode {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "OFF" => InverterMode::Off,
+            "NIGHT" => InverterMode::Night,
+            "WAKE_UP" => InverterMode::WakeUp,
+            "PRODUCTION" => InverterMode::Production,
+            "PRODUCTION_LIMIT" => InverterMode::ProductionLimit,
+            "SHUTDOWN" => InverterMode::Shutdown,
+            "ERROR" => InverterMode::Error,
+            "SETUP" => InverterMode::Setup,
+            "LOCKED_STDBY" => InverterMode::LockedStdby,
+            "LOCKED_FIRE_FIGHTERS" => InverterMode::LockedFireFighters,
+            "LOCKED_FORCE_SHUTDOWN" => InverterMode::LockedForceShutdown,
+            "LOCKED_COMM_TIMEOUT" => InverterMode::LockedCommTimeout,
+            "LOCKED_INV_TRIP" => InverterMode::LockedInvTrip,
+            "LOCKED_INV_ARC_DETECTED" => InverterMode::LockedInvArcDetected,
+            "LOCKED_DG" => InverterMode::LockedDG,
+            "MPPT" => InverterMode::Mppt,
+            "SLEEPING" => InverterMode::Sleeping,
+            _ => InverterMode::Unknown(s),
+        }
+    }
+}
+
+impl From<InverterMode> for String {
+    fn from(m: InverterMode) -> Self {
+        match m {
+            InverterMode::Off => "OFF".to_string(),
+            InverterMode::Night => "NIGHT".to_string(),
+            InverterMode::WakeUp => "WAKE_UP".to_string(),
+            InverterMode::Production => "PRODUCTION".to_string(),
+            InverterMode::ProductionLimit => "PRODUCTION_LIMIT".to_string(),
+            InverterMode::Shutdown => "SHUTDOWN".to_string(),
+            InverterMode::Error => "ERROR".to_string(),
+            InverterMode::Setup => "SETUP".to_string(),
+            InverterMode::LockedStdby => "LOCKED_STDBY".to_string(),
+            InverterMode::LockedFireFighters => "LOCKED_FIRE_FIGHTERS".to_string(),
+            InverterMode::LockedForceShutdown => "LOCKED_FORCE_SHUTDOWN".to_string(),
+            InverterMode::LockedCommTimeout => "LOCKED_COMM_TIMEOUT".to_string(),
+            InverterMode::LockedInvTrip => "LOCKED_INV_TRIP".to_string(),
+            InverterMode::LockedInvArcDetected => "LOCKED_INV_ARC_DETECTED".to_string(),
+            InverterMode::LockedDG => "LOCKED_DG".to_string(),
+            InverterMode::Mppt => "MPPT".to_string(),
+            InverterMode::Sleeping => "SLEEPING".to_string(),
+            InverterMode::Unknown(s) => s,
+        }
+    }
 }
 
 impl std::fmt::Display for InverterMode {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match *self {
+        match self {
             InverterMode::Off => write!(f, "Off"),
             InverterMode::Night => write!(f, "Night"),
             InverterMode::WakeUp => write!(f, "Wake Up"),
@@ -176,6 +280,59 @@ impl std::fmt::Display for InverterMode {
             InverterMode::LockedDG => write!(f, "Locked DG"),
             InverterMode::Mppt => write!(f, "MPPT"),
             InverterMode::Sleeping => write!(f, "Sleeping"),
+            InverterMode::Unknown(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// Inverter operation mode (grid connection)
+#[derive(Clone, Deserialize, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize), serde(into = "u16"))]
+#[serde(from = "u16")]
+pub enum OperationMode {
+    /// 0 - On-grid
+    #[default]
+    OnGrid,
+
+    /// 1 - Operating in off-grid mode using PV or battery
+    OffGridPvOrBattery,
+
+    /// 2 - Operating in off-grid mode with generator (e.g. diesel) is present
+    OffGridWithGenerator,
+
+    /// A mode not listed in the API documentation.
+    Unknown(u16),
+}
+
+impl From<u16> for OperationMode {
+    fn from(m: u16) -> Self {
+        match m {
+            0 => OperationMode::OnGrid,
+            1 => OperationMode::OffGridPvOrBattery,
+            2 => OperationMode::OffGridWithGenerator,
+            _ => OperationMode::Unknown(m),
+        }
+    }
+}
+
+impl From<OperationMode> for u16 {
+    fn from(m: OperationMode) -> Self {
+        match m {
+            OperationMode::OnGrid => 0,
+            OperationMode::OffGridPvOrBattery => 1,
+            OperationMode::OffGridWithGenerator => 2,
+            OperationMode::Unknown(m) => m,
+        }
+    }
+}
+
+impl std::fmt::Display for OperationMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            OperationMode::OnGrid => write!(f, "On-grid"),
+            OperationMode::OffGridPvOrBattery => write!(f, "Off-grid (PV or battery)"),
+            OperationMode::OffGridWithGenerator => write!(f, "Off-grid with generator"),
+            OperationMode::Unknown(m) => write!(f, "{m}"),
         }
     }
 }
@@ -239,6 +396,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn inverter_mode_from_unit_test() {
+        assert_eq!(
+            InverterMode::from("LOCKED_DG".to_string()),
+            InverterMode::LockedDG
+        );
+        assert_eq!(InverterMode::from("MPPT".to_string()), InverterMode::Mppt);
+        assert_eq!(
+            InverterMode::from("LOCKED_GRID_FAULT".to_string()),
+            InverterMode::Unknown("LOCKED_GRID_FAULT".to_string())
+        );
+    }
+
+    #[test]
+    fn operation_mode_from_unit_test() {
+        assert_eq!(OperationMode::from(0), OperationMode::OnGrid);
+        assert_eq!(OperationMode::from(2), OperationMode::OffGridWithGenerator);
+        assert_eq!(OperationMode::from(7), OperationMode::Unknown(7));
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "data": {
+                "count": 1,
+                "telemetries": [{
+                    "date": "2022-01-01 08:05:12",
+                    "totalActivePower": 1520.5,
+                    "dcVoltage": 380.25,
+                    "powerLimit": 100.0,
+                    "totalEnergy": 31234.0,
+                    "temperature": 31.5,
+                    "inverterMode": "MPPT",
+                    "operationMode": 0,
+                    "L1Data": {
+                        "acCurrent": 6.25,
+                        "acVoltage": 245.42,
+                        "acFrequency": 59.99,
+                        "apparentPower": 1530.5,
+                        "activePower": 1520.5,
+                        "reactivePower": 12.5,
+                        "cosPhi": 1.0
+                    }
+                }]
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
@@ -248,5 +456,6 @@ mod tests {
         is_normal::<Telemetry>();
         is_normal::<LxData>();
         is_normal::<InverterMode>();
+        is_normal::<OperationMode>();
     }
 }
@@ -1,7 +1,11 @@
 //! Module for querying a list of sites related to the given token, which is the account api_key.
 //! This API accepts parameters for convenient search, sort and pagination.
 
-use crate::{SendReq, SiteDetails, SortOrder, MONITORING_API_URL};
+use crate::extras::impl_unknown_fields;
+use crate::pagination::MAX_PAGE_SIZE;
+use crate::{
+    Error, Paginated, SendReq, SiteDetails, SolaredgeCredentials, SortOrder, MONITORING_API_URL,
+};
 use serde::Deserialize;
 
 /// site_list request
@@ -13,8 +17,11 @@ pub struct Req {
     sort_property: String,
     sort_order: String,
     status: String,
+    page_size: u16,
+    first_index: u16,
 }
 
+/// A sorting option for this site list, based on one of its properties.
 #[derive(Clone, Debug, PartialEq)]
 pub enum SortProperty {
     /// sort by site name
@@ -73,6 +80,7 @@ impl std::fmt::Display for SortProperty {
     }
 }
 
+/// Select the sites to be included in the list by their status.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Status {
     /// Active sites
@@ -101,44 +109,63 @@ impl std::fmt::Display for Status {
 
 /// site_list response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Resp {
     /// The sites matching the request.
     pub sites: Sites,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp { sites: "sites" });
+
 /// The sites matching the request.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Sites {
     /// The count of matching sites
     pub count: u16,
 
     /// Array of matching sites
     pub site: Entries,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Sites { site: "site" });
+
 /// Array of matching sites
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(transparent)]
 pub struct Entries {
     /// Transparent list of sites
     pub e: Vec<SiteDetails>,
 }
 
+impl_unknown_fields!(transparent Entries, e);
+
 impl Req {
     /// Create a site_list request message that can be sent to SolarEdge.
     ///
     /// # Arguments
     ///
     /// * `size` - The maximum number of sites returned by this call.
-    ///            If you have more than 100 sites, just request another 100
-    ///            sites with startIndex=100. This will fetch sites 100-199.
+    ///   If you have more than 100 sites, just request another 100
+    ///   sites with startIndex=100. This will fetch sites 100-199.
     /// * `start_index` - The first site index to be returned in the results
     /// * `search_text` - Search text for this site
     /// * `sort_property` - A sorting option for this site list, based on
-    ///                     one of its properties
+    ///   one of its properties
     /// * `sort_order` - Sort order for the sort property
     /// * `status` - Select the sites to be included in the list by their status.
-    ///              Default list will include Active and Pending sites.
+    ///   Default list will include Active and Pending sites.
     #[must_use]
     pub fn new(
         size: Option<u16>,
@@ -148,9 +175,14 @@ impl Req {
         sort_order: Option<SortOrder>,
         status: Option<Vec<Status>>,
     ) -> Self {
+        let page_size = match size {
+            Some(s) if s > 0 && s <= MAX_PAGE_SIZE => s,
+            _ => MAX_PAGE_SIZE,
+        };
+
         let size = match size {
             Some(s) => {
-                if s > 0 && s <= 100 {
+                if s > 0 && s <= MAX_PAGE_SIZE {
                     format!("size={s}&")
                 } else {
                     String::new()
@@ -159,6 +191,8 @@ impl Req {
             None => String::new(),
         };
 
+        let first_index = start_index.unwrap_or(0);
+
         let start_index = match start_index {
             Some(si) => format!("startIndex={si}&"),
             None => String::new(),
@@ -197,8 +231,51 @@ impl Req {
             sort_property,
             sort_order,
             status,
+            page_size,
+            first_index,
         }
     }
+
+    /// Iterate over every site matching this request's search, sort and status filters,
+    /// fetching further pages from SolarEdge as needed.
+    ///
+    /// Pages hold `size` sites (100 if not given) and iteration begins at
+    /// `start_index` (0 if not given).
+    ///
+    /// # Arguments
+    ///
+    /// * `solaredge` - SolarEdge credentials to use for sending
+    ///
+    /// # Returns
+    /// An iterator yielding each site, or the error that stopped the iteration.
+    #[must_use]
+    pub fn iter<'a>(&self, solaredge: &'a SolaredgeCredentials) -> Paginated<'a, SiteDetails> {
+        self.iter_with(solaredge, || Ok(()))
+    }
+
+    /// Like [iter](Self::iter), calling `before_page` before each page is fetched.
+    /// An error from `before_page` is yielded instead of fetching the page.
+    pub(crate) fn iter_with<'a, F>(
+        &self,
+        solaredge: &'a SolaredgeCredentials,
+        mut before_page: F,
+    ) -> Paginated<'a, SiteDetails>
+    where
+        F: FnMut() -> Result<(), Error> + 'a,
+    {
+        let mut req = self.clone();
+        Paginated::new(
+            self.first_index,
+            self.page_size,
+            move |start_index, size| {
+                before_page()?;
+                req.start_index = format!("startIndex={start_index}&");
+                req.size = format!("size={size}&");
+                let resp = req.send(solaredge)?;
+                Ok((resp.sites.count, resp.sites.site.e))
+            },
+        )
+    }
 }
 
 impl SendReq<Resp> for Req {
@@ -222,6 +299,69 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "sites": {"count": 1, "site": [{
+                "id": 1,
+                "name": "Home",
+                "accountId": 2,
+                "status": "Active",
+                "peakPower": 9.8,
+                "lastUpdateTime": "2022-01-01 12:00:00",
+                "currency": "USD",
+                "installationDate": "2018-02-08",
+                "ptoDate": null,
+                "notes": "",
+                "type": "Optimizers & Inverters",
+                "location": {
+                    "country": "United States",
+                    "state": "Texas",
+                    "city": "Austin",
+                    "address": "1 Main St",
+                    "address2": "",
+                    "zip": "78701",
+                    "timeZone": "America/Chicago",
+                    "countryCode": "US",
+                    "stateCode": "TX"
+                },
+                "primaryModule": {
+                    "manufacturerName": "LG",
+                    "modelName": "LG335N1C-A5",
+                    "maximumPower": 335.0,
+                    "temperatureCoef": -0.36
+                },
+                "alertQuantity": 0,
+                "alertSeverity": "LOW",
+                "uris": {"SITE_IMAGE": "/site/1/siteImage/image.jpg"},
+                "publicSettings": {"isPublic": false}
+            }]}
+        }"#,
+        );
+    }
+
+    #[test]
+    fn site_list_req_page_unit_test() {
+        let req = Req::new(Some(500), None, None, None, None, None);
+        assert_eq!(req.size, "");
+        assert_eq!(req.page_size, 100);
+        assert_eq!(req.first_index, 0);
+
+        let req = Req::new(
+            Some(25),
+            Some(50),
+            None,
+            None,
+            None,
+            Some(vec![Status::All]),
+        );
+        assert_eq!(req.page_size, 25);
+        assert_eq!(req.first_index, 50);
+        assert_eq!(req.status, "status=All&");
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
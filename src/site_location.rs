@@ -1,15 +1,18 @@
 //! Module for holding site location data returned in the SolarEdge server monitoring API responses.
 
+use crate::extras::impl_unknown_fields;
 use serde::Deserialize;
 
 /// Location of the SolarEdge inverter.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct SiteLocation {
     /// Country of the SolarEdge inverter.
     pub country: String,
 
     /// State of the SolarEdge inverter.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub state: Option<String>, // seems US specific. should this be Option<String>? probably
 
     /// City of the SolarEdge inverter.
@@ -31,9 +34,17 @@ pub struct SiteLocation {
     pub country_code: String,
 
     /// State (abbreviation) of the SolarEdge inverter.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub state_code: Option<String>, // seems US specific. should this be Option<String>?
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(SiteLocation {});
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,9 +1,11 @@
 //! Module for reporting solar panel module information from the SolarEdge server monitoring API.
 
+use crate::extras::impl_unknown_fields;
 use serde::Deserialize;
 
 /// Solar panel module information
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct SiteModule {
     /// solar panel manufacturer
@@ -13,12 +15,21 @@ pub struct SiteModule {
     pub model_name: String,
 
     /// solar panel max output power
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub maximum_power: f32,
 
     /// solar panel temperature coefficient
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub temperature_coef: f32,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(SiteModule {});
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,6 +1,8 @@
 //! Module for site overview requests and responses exchanged with the SolarEdge server monitoring API.
 
-use crate::{SendReq, MONITORING_API_URL};
+use crate::extras::impl_unknown_fields;
+use crate::fleet::MAX_BULK_SITES;
+use crate::{Error, FleetReq, SendReq, SolaredgeCredentials, MONITORING_API_URL};
 use serde::Deserialize;
 
 /// site_overview request
@@ -9,13 +11,24 @@ pub struct Req;
 
 /// site_overview response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Resp {
     /// Overview information about the monitoring site
     pub overview: Overview,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    overview: "overview",
+});
+
 /// Overview information for the single site.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Overview {
     /// Last time the site reported in to SolarEdge.
@@ -38,25 +51,97 @@ pub struct Overview {
 
     /// Source of reading
     pub measured_by: String,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Overview {
+    life_time_data: "lifeTimeData",
+    last_year_data: "lastYearData",
+    last_month_data: "lastMonthData",
+    last_day_data: "lastDayData",
+    current_power: "currentPower",
+});
+
 /// Energy and revenue pair
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct EnergyRevenue {
     /// Energy value
-    pub energy: f32,
+    #[serde(deserialize_with = "crate::lenient::number")]
+    pub energy: f64,
 
     /// Revenue value
-    pub revenue: Option<f32>,
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub revenue: Option<f64>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(EnergyRevenue {});
+
 /// Power currently being produced by the site
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct CurrentPower {
     /// Current power reading
-    pub power: f32,
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    pub power: Option<f32>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
+}
+
+impl_unknown_fields!(CurrentPower {});
+
+/// site_overview bulk response, for several sites in one request
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkResp {
+    sites_overviews: BulkOverviews,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkOverviews {
+    site_energy_list: Vec<BulkEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkEntry {
+    site_id: u32,
+    site_overview: Overview,
+}
+
+impl BulkResp {
+    fn into_site_resps(self) -> Vec<(u32, Resp)> {
+        self.sites_overviews
+            .site_energy_list
+            .into_iter()
+            .map(|e| {
+                (
+                    e.site_id,
+                    Resp {
+                        overview: e.site_overview,
+                        #[cfg(feature = "extras")]
+                        extras: crate::Extras::default(),
+                    },
+                )
+            })
+            .collect()
+    }
 }
 
 impl Req {
@@ -76,6 +161,29 @@ impl SendReq<Resp> for Req {
     }
 }
 
+impl FleetReq<Resp> for Req {
+    fn send_bulk(
+        &self,
+        site_ids: &[u32],
+        solaredge: &SolaredgeCredentials,
+    ) -> Option<Result<Vec<(u32, Resp)>, Error>> {
+        if site_ids.is_empty() || site_ids.len() > MAX_BULK_SITES {
+            return None;
+        }
+        let site_ids = site_ids
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let url = format!(
+            "{}sites/{}/overview?{}",
+            *MONITORING_API_URL, site_ids, solaredge.api_key,
+        );
+
+        Some(crate::get::<BulkResp>(url).map(BulkResp::into_site_resps))
+    }
+}
+
 impl Default for Req {
     fn default() -> Self {
         Self::new()
@@ -87,6 +195,86 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "overview": {
+                "lastUpdateTime": "2022-01-01 12:00:00",
+                "lifeTimeData": {"energy": 44535.0, "revenue": 4127.25},
+                "lastYearData": {"energy": 12926.0},
+                "lastMonthData": {"energy": 12926.0},
+                "lastDayData": {"energy": 4419.0},
+                "currentPower": {"power": 1670.5},
+                "measuredBy": "INVERTER"
+            }
+        }"#,
+        );
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn null_key_omitted_unit_test() {
+        let json = r#"{"energy": 12926.0, "revenue": null}"#;
+        let data: EnergyRevenue = serde_json::from_str(json).unwrap();
+        let written: serde_json::Value = serde_json::to_value(&data).unwrap();
+        assert_eq!(written, serde_json::json!({"energy": 12926.0}));
+    }
+
+    #[test]
+    fn lenient_numbers_unit_test() {
+        let json = r#"{
+            "lastUpdateTime": "2022-01-01 12:00:00",
+            "lifeTimeData": {"energy": "44535.0", "revenue": "4127.25"},
+            "lastYearData": {"energy": 12926, "revenue": null},
+            "lastMonthData": {"energy": 12926.0},
+            "lastDayData": {"energy": 4419.0, "revenue": ""},
+            "currentPower": {"power": null},
+            "measuredBy": "INVERTER"
+        }"#;
+        let overview: Overview = serde_json::from_str(json).unwrap();
+        assert!((overview.life_time_data.energy - 44535.0).abs() < f64::EPSILON);
+        assert_eq!(overview.life_time_data.revenue, Some(4127.25));
+        assert!((overview.last_year_data.energy - 12926.0).abs() < f64::EPSILON);
+        assert_eq!(overview.last_month_data.revenue, None);
+        assert_eq!(overview.last_day_data.revenue, None);
+        assert_eq!(overview.current_power.power, None);
+    }
+
+    #[test]
+    fn bulk_resp_unit_test() {
+        let json = r#"{
+            "sitesOverviews": {
+                "count": 2,
+                "siteEnergyList": [
+                    {"siteId": 1, "siteOverview": {
+                        "lastUpdateTime": "2022-01-01 12:00:00",
+                        "lifeTimeData": {"energy": 100.0},
+                        "lastYearData": {"energy": 50.0},
+                        "lastMonthData": {"energy": 10.0},
+                        "lastDayData": {"energy": 1.0},
+                        "currentPower": {"power": 0.0},
+                        "measuredBy": "INVERTER"}},
+                    {"siteId": 2, "siteOverview": {
+                        "lastUpdateTime": "2022-01-01 12:00:00",
+                        "lifeTimeData": {"energy": 200.0},
+                        "lastYearData": {"energy": 60.0},
+                        "lastMonthData": {"energy": 20.0},
+                        "lastDayData": {"energy": 2.0},
+                        "currentPower": {"power": null},
+                        "measuredBy": "METER"}}
+                ]
+            }
+        }"#;
+        let bulk: BulkResp = serde_json::from_str(json).unwrap();
+        let resps = bulk.into_site_resps();
+        assert_eq!(resps.len(), 2);
+        assert_eq!(resps[1].0, 2);
+        assert_eq!(resps[1].1.overview.measured_by, "METER");
+        assert!((resps[0].1.overview.life_time_data.energy - 100.0).abs() < f64::EPSILON);
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
@@ -1,5 +1,6 @@
 //! Module for querying the site power measurements in 15 minute resolution.
 
+use crate::extras::impl_unknown_fields;
 use crate::{DateValue, SendReq, TimeUnit, MONITORING_API_URL, URL_DATE_TIME_FORMAT};
 use serde::Deserialize;
 
@@ -12,14 +13,23 @@ pub struct Req {
 
 /// site_power response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// Power measurements.
     pub power: Power,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp { power: "power" });
+
 /// Power measurements.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Power {
     /// Time unit of the Power measurements
@@ -30,8 +40,15 @@ pub struct Power {
 
     /// For the dates requested, measurements over the time period
     pub values: Vec<DateValue>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Power { values: "values" });
+
 impl Req {
     /// Create a site_power request message that can be sent to SolarEdge.
     ///     
@@ -66,6 +83,20 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "power": {
+                "timeUnit": "QUARTER_OF_AN_HOUR",
+                "unit": "W",
+                "values": [{"date": "2022-01-01 12:00:00", "value": 2013.872}]
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
@@ -1,5 +1,6 @@
 //! Module for detailed site power measurements from meters such as consumption, export (feed-in), import (purchase), etc.
 
+use crate::extras::impl_unknown_fields;
 use crate::{MeterType, MeterValue, SendReq, TimeUnit, MONITORING_API_URL, URL_DATE_TIME_FORMAT};
 use serde::Deserialize;
 
@@ -13,14 +14,25 @@ pub struct Req {
 
 /// site_powerDetails response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// Power details
     pub power_details: PowerDetails,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    power_details: "powerDetails",
+});
+
 /// Power details
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct PowerDetails {
     /// Granularity of the power detail values (should match the request)
@@ -31,8 +43,15 @@ pub struct PowerDetails {
 
     /// For the meter types requested, power values over the time period
     pub meters: Vec<MeterValue>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(PowerDetails { meters: "meters" });
+
 impl Req {
     /// Create an power details request message that can be sent to SolarEdge.
     ///
@@ -98,6 +117,23 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "powerDetails": {
+                "timeUnit": "QUARTER_OF_AN_HOUR",
+                "unit": "W",
+                "meters": [{
+                    "type": "Purchased",
+                    "values": [{"date": "2022-01-01 12:00:00", "value": 512.25}]
+                }]
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
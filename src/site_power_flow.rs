@@ -1,6 +1,7 @@
 //! Module for querying site power flow between all elements of the site including
 //! PV array, storage (battery), loads (consumption) and grid.
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, MONITORING_API_URL};
 use serde::Deserialize;
 
@@ -10,14 +11,25 @@ pub struct Req;
 
 /// site_power_flow response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// Site's current power flow status
     pub site_current_power_flow: SiteCurrentPowerFlow,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    site_current_power_flow: "siteCurrentPowerFlow",
+});
+
 /// Site's current power flow status
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct SiteCurrentPowerFlow {
     /// undocumented
@@ -40,15 +52,31 @@ pub struct SiteCurrentPowerFlow {
 
     /// Photovoltaic array
     #[serde(rename = "PV")]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub pv: Option<Parameters>,
 
     /// Electric storage
     #[serde(rename = "STORAGE")]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub storage: Option<Parameters>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(SiteCurrentPowerFlow {
+    connections: "connections",
+    grid: "GRID",
+    load: "LOAD",
+    pv: "PV",
+    storage: "STORAGE",
+});
+
 /// List of producers (from) adn consumers (to) of electricity.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Connections {
     /// The element providing power
@@ -56,30 +84,100 @@ pub struct Connections {
 
     /// The element consuming power
     pub to: String,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Connections {});
+
 /// Parameters for each site producer/consumer element.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Parameters {
-    /// The current status of the element (Active / Idle Disabled)
-    pub status: String,
+    /// The current status of the element (Active / Idle / Disabled)
+    pub status: ElementStatus,
 
     /// The current power of the element. All numbers are positive;
     /// power direction is determined by the “connections” section.
+    #[serde(deserialize_with = "crate::lenient::number")]
     pub current_power: f32,
 
     /// (STORAGE only) The accumulated state of energy (% of charge) for all batteries.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub charge_level: Option<u16>,
 
     /// (STORAGE only) If the accumulated storage charge level drops below
     /// a configurable level (currently 10%), this flag is returned.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub critical: Option<bool>,
 
     /// (STORAGE only) In Backup mode (GRID is Disabled), this property
     /// is returned to specify the time left before the storage energy runs out
     /// (estimated according to current load level).
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub time_left: Option<String>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
+}
+
+impl_unknown_fields!(Parameters {});
+
+/// Status of a site producer/consumer element.
+#[derive(Clone, Deserialize, Debug, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize),
+    serde(into = "String")
+)]
+#[serde(from = "String")]
+pub enum ElementStatus {
+    /// Element is producing or consuming power
+    #[default]
+    Active,
+
+    /// Element is idle
+    Idle,
+
+    /// Element is disabled (e.g. GRID during backup mode)
+    Disabled,
+
+    /// A status not listed in the API documentation.
+    Unknown(String),
+}
+
+impl From<String> for ElementStatus {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "Active" => ElementStatus::Active,
+            "Idle" => ElementStatus::Idle,
+            "Disabled" => ElementStatus::Disabled,
+            _ => ElementStatus::Unknown(s),
+        }
+    }
+}
+
+impl From<ElementStatus> for String {
+    fn from(e: ElementStatus) -> Self {
+        e.to_string()
+    }
+}
+
+impl std::fmt::Display for ElementStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ElementStatus::Active => write!(f, "Active"),
+            ElementStatus::Idle => write!(f, "Idle"),
+            ElementStatus::Disabled => write!(f, "Disabled"),
+            ElementStatus::Unknown(s) => write!(f, "{s}"),
+        }
+    }
 }
 
 impl Req {
@@ -110,6 +208,33 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[test]
+    fn element_status_from_unit_test() {
+        assert_eq!(ElementStatus::from("Idle".to_string()), ElementStatus::Idle);
+        assert_eq!(
+            ElementStatus::from("Sleeping".to_string()),
+            ElementStatus::Unknown("Sleeping".to_string())
+        );
+        assert_eq!(format!("{}", ElementStatus::Disabled), "Disabled");
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "siteCurrentPowerFlow": {
+                "updateRefreshRate": 3,
+                "unit": "kW",
+                "connections": [{"from": "PV", "to": "Load"}, {"from": "GRID", "to": "Load"}],
+                "GRID": {"status": "Active", "currentPower": 0.5},
+                "LOAD": {"status": "Active", "currentPower": 2.5},
+                "PV": {"status": "Active", "currentPower": 2.0}
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
@@ -117,5 +242,6 @@ mod tests {
         is_normal::<SiteCurrentPowerFlow>();
         is_normal::<Connections>();
         is_normal::<Parameters>();
+        is_normal::<ElementStatus>();
     }
 }
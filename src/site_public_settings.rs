@@ -1,18 +1,28 @@
 //! Module for reporting information about the web pages for the site served by SolarEdge.
 
+use crate::extras::impl_unknown_fields;
 use serde::Deserialize;
 
 /// Information about the public web page for the site provided by SolarEdge.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct SitePublicSettings {
     /// Optional name given to the web page fro the site.
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
     pub name: Option<String>,
 
     /// Is the web page accessible to the public (i.e. no password required)?
     pub is_public: bool,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(SitePublicSettings {});
+
 #[cfg(test)]
 mod tests {
     use super::*;
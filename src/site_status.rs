@@ -0,0 +1,89 @@
+//! Module for the status of a site reported by the SolarEdge server monitoring API.
+
+use serde::Deserialize;
+
+/// Status of a monitoring site.
+#[derive(Clone, Deserialize, Debug, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize),
+    serde(into = "String")
+)]
+#[serde(from = "String")]
+pub enum SiteStatus {
+    /// Site is up and reporting.
+    #[default]
+    Active,
+
+    /// Site is not active yet.
+    Pending,
+
+    /// Site is waiting for its first communication with the server.
+    PendingCommunication,
+
+    /// Site has been disabled.
+    Disabled,
+
+    /// A status not listed in the API documentation.
+    Unknown(String),
+}
+
+impl From<String> for SiteStatus {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "Active" => SiteStatus::Active,
+            "Pending" => SiteStatus::Pending,
+            "Pending Communication" => SiteStatus::PendingCommunication,
+            "Disabled" => SiteStatus::Disabled,
+            _ => SiteStatus::Unknown(s),
+        }
+    }
+}
+
+impl From<SiteStatus> for String {
+    fn from(s: SiteStatus) -> Self {
+        s.to_string()
+    }
+}
+
+impl std::fmt::Display for SiteStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SiteStatus::Active => write!(f, "Active"),
+            SiteStatus::Pending => write!(f, "Pending"),
+            SiteStatus::PendingCommunication => write!(f, "Pending Communication"),
+            SiteStatus::Disabled => write!(f, "Disabled"),
+            SiteStatus::Unknown(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_normal;
+
+    #[test]
+    fn site_status_fmt_unit_test() {
+        let t = SiteStatus::PendingCommunication;
+        assert_eq!(format!("{t}"), "Pending Communication");
+    }
+
+    #[test]
+    fn site_status_from_unit_test() {
+        assert_eq!(SiteStatus::from("Active".to_string()), SiteStatus::Active);
+        assert_eq!(
+            SiteStatus::from("Pending Communication".to_string()),
+            SiteStatus::PendingCommunication
+        );
+        assert_eq!(
+            SiteStatus::from("Hibernating".to_string()),
+            SiteStatus::Unknown("Hibernating".to_string())
+        );
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<SiteStatus>();
+    }
+}
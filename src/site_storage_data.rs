@@ -1,5 +1,6 @@
 //! Module for detailed storage information from batteries: the state of energy, power and lifetime energy.
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, MONITORING_API_URL, URL_DATE_TIME_FORMAT};
 use serde::Deserialize;
 
@@ -13,14 +14,25 @@ pub struct Req {
 
 /// site_storage_data response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// Information about the site's storage
     pub storage_data: StorageData,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    storage_data: "storageData",
+});
+
 /// Information about the site's storage
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct StorageData {
     /// Number of batteries in teh battery list
@@ -28,24 +40,122 @@ pub struct StorageData {
 
     /// List of batteries at the site
     pub batteries: Batteries,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(StorageData {
+    batteries: "batteries",
+});
+
 /// Array of batteries
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(transparent)]
 pub struct Batteries {
     /// Transparent list of batteries
     pub e: Vec<Battery>,
 }
 
+impl_unknown_fields!(transparent Batteries, e);
+
 /// Data for a single battery
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Battery {
     /// The nameplate capacity of the battery as provided by the manufacturer
     pub nameplate: u32,
+
+    /// Battery serial number
+    #[serde(default)]
+    pub serial_number: String,
+
+    /// Battery model number
+    #[serde(default)]
+    pub model_number: String,
+
+    /// Number of telemetries in the list
+    #[serde(default)]
+    pub telemetry_count: u32,
+
+    /// Telemetries of the battery over the requested time frame
+    #[serde(default)]
+    pub telemetries: Vec<StorageTelemetry>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
+}
+
+impl_unknown_fields!(Battery {
+    telemetries: "telemetries",
+});
+
+/// A single battery telemetry
+#[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct StorageTelemetry {
+    /// YYYY-mm-dd HH:MM:SS
+    pub time_stamp: String,
+
+    /// Charge (positive) or discharge (negative) power in W
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub power: Option<f32>,
+
+    /// Battery state: 0 invalid, 1 standby, 2 thermal management, 3 enabled, 4 fault
+    #[serde(default)]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub battery_state: Option<u8>,
+
+    /// Lifetime energy charged into the battery in Wh
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub life_time_energy_charged: Option<f64>,
+
+    /// Lifetime energy discharged from the battery in Wh
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub life_time_energy_discharged: Option<f64>,
+
+    /// Maximum energy the battery can currently hold in Wh
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub full_pack_energy_available: Option<f64>,
+
+    /// Battery internal temperature in Celsius
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub internal_temp: Option<f32>,
+
+    /// Energy charged from the grid in Wh
+    #[serde(
+        rename = "ACGridCharging",
+        default,
+        deserialize_with = "crate::lenient::option_number"
+    )]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub ac_grid_charging: Option<f64>,
+
+    /// State of charge in percent
+    #[serde(default, deserialize_with = "crate::lenient::option_number")]
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    pub battery_percentage_state: Option<f32>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(StorageTelemetry {});
+
 impl Req {
     /// Create a site storage data request message that can be sent to SolarEdge.
     #[must_use]
@@ -85,6 +195,35 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(
+            r#"{
+            "storageData": {
+                "batteryCount": 1,
+                "batteries": [{
+                    "nameplate": 9800,
+                    "serialNumber": "BAT-1",
+                    "modelNumber": "LGCHEM RESU10H",
+                    "telemetryCount": 1,
+                    "telemetries": [{
+                        "timeStamp": "2022-01-01 12:00:00",
+                        "power": -1520.5,
+                        "batteryState": 3,
+                        "lifeTimeEnergyCharged": 1234567.0,
+                        "lifeTimeEnergyDischarged": 1134567.0,
+                        "fullPackEnergyAvailable": 9500.0,
+                        "internalTemp": 31.5,
+                        "ACGridCharging": 0.0,
+                        "batteryPercentageState": 87.5
+                    }]
+                }]
+            }
+        }"#,
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
@@ -92,5 +231,6 @@ mod tests {
         is_normal::<StorageData>();
         is_normal::<Batteries>();
         is_normal::<Battery>();
+        is_normal::<StorageTelemetry>();
     }
 }
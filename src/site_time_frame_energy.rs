@@ -1,5 +1,6 @@
 //! Module for querying the site total energy produced for a given period.
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, MONITORING_API_URL, URL_DATE_FORMAT};
 use serde::Deserialize;
 
@@ -12,23 +13,42 @@ pub struct Req {
 
 /// site_time_frame_energy response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Resp {
     /// Energy measurements.
     pub time_frame_energy: TimeFrameEnergy,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    time_frame_energy: "timeFrameEnergy",
+});
+
 /// Energy measurements.
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct TimeFrameEnergy {
     /// Energy produced during the time period
-    pub energy: f32,
+    #[serde(deserialize_with = "crate::lenient::number")]
+    pub energy: f64,
 
     /// Measurement unit (e.g. Wh)
     pub unit: String,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(TimeFrameEnergy {});
+
 impl Req {
     /// Create a site_time_frame_energy request message that can be sent to SolarEdge.
     #[must_use]
@@ -58,6 +78,12 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(r#"{"timeFrameEnergy": {"energy": 12896.0, "unit": "Wh"}}"#);
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
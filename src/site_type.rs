@@ -0,0 +1,83 @@
+//! Module for the type of a site reported by the SolarEdge server monitoring API.
+
+use serde::Deserialize;
+
+/// Kind of SolarEdge installation at a site.
+#[derive(Clone, Deserialize, Debug, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize),
+    serde(into = "String")
+)]
+#[serde(from = "String")]
+pub enum SiteType {
+    /// Optimizers & Inverters
+    #[default]
+    OptimizersAndInverters,
+
+    /// Safety & Monitoring Interface
+    SafetyAndMonitoringInterface,
+
+    /// Monitoring Combiner Boxes
+    MonitoringCombinerBoxes,
+
+    /// A site type not listed in the API documentation.
+    Unknown(String),
+}
+
+impl From<String> for SiteType {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "Optimizers & Inverters" => SiteType::OptimizersAndInverters,
+            "Safety & Monitoring Interface" => SiteType::SafetyAndMonitoringInterface,
+            "Monitoring Combiner Boxes" => SiteType::MonitoringCombinerBoxes,
+            _ => SiteType::Unknown(s),
+        }
+    }
+}
+
+impl From<SiteType> for String {
+    fn from(s: SiteType) -> Self {
+        s.to_string()
+    }
+}
+
+impl std::fmt::Display for SiteType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SiteType::OptimizersAndInverters => write!(f, "Optimizers & Inverters"),
+            SiteType::SafetyAndMonitoringInterface => write!(f, "Safety & Monitoring Interface"),
+            SiteType::MonitoringCombinerBoxes => write!(f, "Monitoring Combiner Boxes"),
+            SiteType::Unknown(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_normal;
+
+    #[test]
+    fn site_type_fmt_unit_test() {
+        let t = SiteType::OptimizersAndInverters;
+        assert_eq!(format!("{t}"), "Optimizers & Inverters");
+    }
+
+    #[test]
+    fn site_type_from_unit_test() {
+        assert_eq!(
+            SiteType::from("Monitoring Combiner Boxes".to_string()),
+            SiteType::MonitoringCombinerBoxes
+        );
+        assert_eq!(
+            SiteType::from("Microinverters".to_string()),
+            SiteType::Unknown("Microinverters".to_string())
+        );
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<SiteType>();
+    }
+}
@@ -0,0 +1,480 @@
+//! Module for valuing purchased and fed-in energy at dynamic (spot) prices read
+//! from a local CSV file.
+//!
+//! Both the prices and SolarEdge's meter data are matched on UTC instants. Prices
+//! with a UTC offset (RFC 3339) are taken as they are; prices and meter values in
+//! local time are converted with the site's time zone. In the hour repeated when
+//! daylight saving ends, the first occurrence of a local time is taken as the
+//! earlier instant and the second as the later one.
+
+use crate::date_value::LocalClock;
+use crate::time_series::kwh_factor;
+use crate::{Error, Kind, MeterType, Series};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Timelike, Utc};
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+/// Formats accepted for local timestamps in a price file.
+const LOCAL_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// Prices per kWh over time, e.g. hourly or quarter-hourly spot prices.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PriceSeries {
+    /// Start of each price, in order
+    prices: Vec<(DateTime<Utc>, f64)>,
+
+    /// How long each price applies
+    resolution: TimeDelta,
+}
+
+/// Energy and value of one hour.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HourValue {
+    /// Start of the hour in site local time
+    pub start: NaiveDateTime,
+
+    /// Start of the hour
+    pub utc: DateTime<Utc>,
+
+    /// Average price of the hour, None if there is no price for it
+    pub price: Option<f64>,
+
+    /// Energy purchased, in kWh
+    pub purchased: f64,
+
+    /// Cost of the purchased energy
+    pub purchase_cost: f64,
+
+    /// Energy fed in, in kWh
+    pub feed_in: f64,
+
+    /// Value of the fed-in energy
+    pub feed_in_value: f64,
+}
+
+/// Value of meter data at spot prices.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpotValuation {
+    /// Energy purchased, in kWh
+    pub purchased: f64,
+
+    /// Cost of the purchased energy
+    pub purchase_cost: f64,
+
+    /// Energy fed in, in kWh
+    pub feed_in: f64,
+
+    /// Value of the fed-in energy
+    pub feed_in_value: f64,
+
+    /// Energy in kWh purchased at times without a price, not valued
+    pub unpriced_purchased: f64,
+
+    /// Energy in kWh fed in at times without a price, not valued
+    pub unpriced_feed_in: f64,
+
+    /// Each hour with meter data, in order
+    pub hours: Vec<HourValue>,
+}
+
+/// An hour whose purchases would have been cheaper at the cheapest hour of the day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShiftOpportunity {
+    /// The hour with the purchases
+    pub hour: HourValue,
+
+    /// Start of the cheapest hour of the same day, in site local time
+    pub cheapest: NaiveDateTime,
+
+    /// Price of the cheapest hour
+    pub cheapest_price: f64,
+
+    /// What buying the hour's energy at the cheapest price would have saved
+    pub saving: f64,
+}
+
+impl PriceSeries {
+    /// Create a price series.
+    ///
+    /// # Arguments
+    ///
+    /// * `prices` - start and price per kWh of each interval, in any order
+    /// * `resolution` - how long each price applies. None for the smallest gap
+    ///   between prices, or an hour if there is only one.
+    #[must_use]
+    pub fn new(mut prices: Vec<(DateTime<Utc>, f64)>, resolution: Option<TimeDelta>) -> Self {
+        prices.sort_by_key(|(t, _)| *t);
+        prices.dedup_by_key(|(t, _)| *t);
+
+        let resolution = resolution.unwrap_or_else(|| {
+            prices
+                .windows(2)
+                .map(|p| p[1].0 - p[0].0)
+                .min()
+                .unwrap_or(TimeDelta::hours(1))
+        });
+
+        PriceSeries { prices, resolution }
+    }
+
+    /// Read prices from CSV with a timestamp and a price column, separated by a
+    /// comma or semicolon. The first line that is not blank decides which: a
+    /// semicolon if it has one, else a comma. With semicolons the prices may use a
+    /// decimal comma, e.g. `2022-01-01 00:00;0,123`. A first line whose price is
+    /// not a number is taken as the header. Blank lines are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - the CSV
+    /// * `time_zone` - time zone of timestamps without a UTC offset, i.e. the
+    ///   site's time zone
+    /// * `factor` - multiplied with every price to get the price per kWh, e.g.
+    ///   0.001 for prices per MWh. None for prices that are already per kWh.
+    ///
+    /// # Errors
+    /// Returns [Kind::InvalidArgument] for a line that cannot be parsed, has more
+    /// than two columns or a local time that does not exist, or [Kind::IoError]
+    /// if reading fails.
+    pub fn from_csv<R: BufRead, Tz: TimeZone>(
+        reader: R,
+        time_zone: &Tz,
+        factor: Option<f64>,
+    ) -> Result<Self, Error> {
+        let factor = factor.unwrap_or(1.0);
+        let mut clock = LocalClock::new(time_zone);
+        let mut prices = Vec::new();
+        let mut delimiter = None;
+
+        for (n, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |what: &str| {
+                Error::new(Kind::InvalidArgument(format!(
+                    "price file line {}: {what}: {line}",
+                    n + 1
+                )))
+            };
+
+            let delimiter = *delimiter.get_or_insert(if line.contains(';') { ';' } else { ',' });
+            let mut columns = line.split(delimiter).map(str::trim);
+            let (Some(timestamp), Some(price), None) =
+                (columns.next(), columns.next(), columns.next())
+            else {
+                return Err(invalid("expected timestamp and price"));
+            };
+            let mut price = price.trim_matches('"').to_string();
+            if delimiter == ';' {
+                price = price.replace(',', ".");
+            }
+            let Ok(price) = price.parse::<f64>() else {
+                if n == 0 {
+                    continue;
+                }
+                return Err(invalid("invalid price"));
+            };
+
+            let timestamp = timestamp.trim_matches('"');
+            let start = if let Ok(t) = DateTime::parse_from_rfc3339(timestamp) {
+                t.with_timezone(&Utc)
+            } else {
+                let local = LOCAL_FORMATS
+                    .iter()
+                    .find_map(|f| NaiveDateTime::parse_from_str(timestamp, f).ok())
+                    .ok_or_else(|| invalid("invalid timestamp"))?;
+                clock
+                    .utc(local)
+                    .ok_or_else(|| invalid("local time does not exist"))?
+            };
+            prices.push((start, price * factor));
+        }
+
+        Ok(PriceSeries::new(prices, None))
+    }
+
+    /// How long each price applies.
+    #[must_use]
+    pub fn resolution(&self) -> TimeDelta {
+        self.resolution
+    }
+
+    /// The price per kWh at `t`, None if no price covers it.
+    #[must_use]
+    pub fn price_at(&self, t: DateTime<Utc>) -> Option<f64> {
+        let i = self.prices.partition_point(|(start, _)| *start <= t);
+        let (start, price) = self.prices.get(i.checked_sub(1)?)?;
+        (t < *start + self.resolution).then_some(*price)
+    }
+
+    /// Value the Purchased and FeedIn meters of energy details at these prices.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - quarter-hour or hourly energy details series. Other meters are
+    ///   ignored.
+    /// * `time_zone` - the site's time zone
+    #[must_use]
+    pub fn value<Tz: TimeZone>(&self, series: &[Series], time_zone: &Tz) -> SpotValuation {
+        let purchased = MeterType::Purchased.to_string();
+        let feed_in = MeterType::FeedIn.to_string();
+
+        let mut valuation = SpotValuation::default();
+        let mut hours: BTreeMap<DateTime<Utc>, (HourValue, Vec<f64>)> = BTreeMap::new();
+
+        for s in series {
+            let is_purchased = s.name == purchased;
+            if !is_purchased && s.name != feed_in {
+                continue;
+            }
+            let factor = kwh_factor(&s.unit);
+            let mut clock = LocalClock::new(time_zone);
+
+            for sample in &s.samples {
+                let kwh = sample.value.unwrap_or(0.0) * factor;
+                let utc = clock.utc(sample.timestamp);
+                let price = utc.and_then(|t| self.price_at(t));
+
+                if let Some(utc) = utc {
+                    let local = sample.timestamp;
+                    let offset = i64::from(local.minute() * 60 + local.second());
+                    let start = local - TimeDelta::seconds(offset);
+                    let (hour, prices) = hours
+                        .entry(utc - TimeDelta::seconds(offset))
+                        .or_insert_with(|| (HourValue::new(start, utc), Vec::new()));
+                    prices.extend(price);
+                    if is_purchased {
+                        hour.purchased += kwh;
+                        hour.purchase_cost += kwh * price.unwrap_or(0.0);
+                    } else {
+                        hour.feed_in += kwh;
+                        hour.feed_in_value += kwh * price.unwrap_or(0.0);
+                    }
+                }
+
+                match (price, is_purchased) {
+                    (Some(price), true) => {
+                        valuation.purchased += kwh;
+                        valuation.purchase_cost += kwh * price;
+                    }
+                    (Some(price), false) => {
+                        valuation.feed_in += kwh;
+                        valuation.feed_in_value += kwh * price;
+                    }
+                    (None, true) => valuation.unpriced_purchased += kwh,
+                    (None, false) => valuation.unpriced_feed_in += kwh,
+                }
+            }
+        }
+
+        valuation.hours = hours
+            .into_iter()
+            .map(|(utc, (mut hour, prices))| {
+                hour.utc = utc;
+                #[allow(clippy::cast_precision_loss)]
+                if !prices.is_empty() {
+                    hour.price = Some(prices.iter().sum::<f64>() / prices.len() as f64);
+                }
+                hour
+            })
+            .collect();
+        valuation
+    }
+}
+
+impl HourValue {
+    fn new(start: NaiveDateTime, utc: DateTime<Utc>) -> Self {
+        HourValue {
+            start,
+            utc,
+            price: None,
+            purchased: 0.0,
+            purchase_cost: 0.0,
+            feed_in: 0.0,
+            feed_in_value: 0.0,
+        }
+    }
+}
+
+impl SpotValuation {
+    /// The hours where moving the purchases to the cheapest hour of the same (site
+    /// local) day would have saved the most.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - number of hours to return
+    ///
+    /// # Returns
+    /// Up to `n` hours with a saving, largest saving first.
+    #[must_use]
+    pub fn shift_opportunities(&self, n: usize) -> Vec<ShiftOpportunity> {
+        let mut cheapest: BTreeMap<NaiveDate, (NaiveDateTime, f64)> = BTreeMap::new();
+        for hour in &self.hours {
+            let Some(price) = hour.price else { continue };
+            let day = cheapest
+                .entry(hour.start.date())
+                .or_insert((hour.start, price));
+            if price < day.1 {
+                *day = (hour.start, price);
+            }
+        }
+
+        let mut opportunities: Vec<ShiftOpportunity> = self
+            .hours
+            .iter()
+            .filter_map(|hour| {
+                let (cheapest, cheapest_price) = *cheapest.get(&hour.start.date())?;
+                let saving = hour.purchased * (hour.price? - cheapest_price);
+                (saving > 0.0).then_some(ShiftOpportunity {
+                    hour: *hour,
+                    cheapest,
+                    cheapest_price,
+                    saving,
+                })
+            })
+            .collect();
+
+        opportunities.sort_by(|a, b| b.saving.total_cmp(&a.saving));
+        opportunities.truncate(n);
+        opportunities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, Sample};
+    use chrono_tz::Europe::Berlin;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::URL_DATE_TIME_FORMAT).unwrap()
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        at(s).and_utc()
+    }
+
+    fn meter(name: &str, values: &[(&str, f64)]) -> Series {
+        Series {
+            name: name.to_string(),
+            serial: None,
+            unit: "Wh".to_string(),
+            samples: values
+                .iter()
+                .map(|(t, v)| Sample {
+                    timestamp: at(t),
+                    value: Some(*v),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn from_csv_unit_test() {
+        let csv = "timestamp;price\n\
+                   2022-01-01T00:00:00Z;100.5\n\
+                   \n\
+                   2022-01-01 03:00;120\n\
+                   \"2022-01-01T01:00:00+00:00\";\"80\"\n";
+        let prices = PriceSeries::from_csv(csv.as_bytes(), &Berlin, Some(0.001)).unwrap();
+        let price = |t| prices.price_at(utc(t)).unwrap();
+
+        assert_eq!(prices.resolution(), TimeDelta::hours(1));
+        assert!((price("2022-01-01 00:30:00") - 0.1005).abs() < 1e-9);
+        assert!((price("2022-01-01 01:00:00") - 0.08).abs() < 1e-9);
+        // 03:00 in Berlin is 02:00 UTC
+        assert!((price("2022-01-01 02:59:59") - 0.12).abs() < 1e-9);
+        assert_eq!(prices.price_at(utc("2022-01-01 03:00:00")), None);
+        assert_eq!(prices.price_at(utc("2021-12-31 23:59:59")), None);
+
+        let e = PriceSeries::from_csv("2022-01-01;x\nnope;1".as_bytes(), &Berlin, None);
+        assert!(matches!(e.unwrap_err().kind(), Kind::InvalidArgument(_)));
+        let e = PriceSeries::from_csv("2022-03-27 02:30:00,1".as_bytes(), &Berlin, None);
+        assert!(matches!(e.unwrap_err().kind(), Kind::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn from_csv_decimal_comma_unit_test() {
+        let csv = "Zeit;Preis\n\
+                   2022-01-01 00:00;0,123\n\
+                   2022-01-01 01:00;0,25\n";
+        let prices = PriceSeries::from_csv(csv.as_bytes(), &Berlin, None).unwrap();
+        // 00:00 in Berlin is 23:00 UTC the day before
+        let price = prices.price_at(utc("2021-12-31 23:30:00")).unwrap();
+        assert!((price - 0.123).abs() < 1e-9);
+        let price = prices.price_at(utc("2022-01-01 00:30:00")).unwrap();
+        assert!((price - 0.25).abs() < 1e-9);
+
+        // Only the detected delimiter separates columns, and extra columns are
+        // not ignored.
+        for csv in [
+            "2022-01-01 00:00,0.1\n2022-01-01 01:00;0.2\n",
+            "2022-01-01 00:00,0.1,EUR\n",
+            "2022-01-01 00:00;0,1;EUR\n",
+        ] {
+            let e = PriceSeries::from_csv(csv.as_bytes(), &Berlin, None).unwrap_err();
+            assert!(matches!(e.kind(), Kind::InvalidArgument(_)), "{csv}");
+        }
+    }
+
+    #[test]
+    fn dst_end_unit_test() {
+        // Daylight saving ends at 03:00 CEST on 2022-10-30, so 02:00 local time
+        // happens twice: at 00:00 and at 01:00 UTC.
+        let csv = "2022-10-30 01:00:00,0.10\n\
+                   2022-10-30 02:00:00,0.20\n\
+                   2022-10-30 02:00:00,0.30\n\
+                   2022-10-30 03:00:00,0.40\n";
+        let prices = PriceSeries::from_csv(csv.as_bytes(), &Berlin, None).unwrap();
+        assert_eq!(prices.resolution(), TimeDelta::hours(1));
+        assert_eq!(prices.price_at(utc("2022-10-30 00:15:00")), Some(0.2));
+        assert_eq!(prices.price_at(utc("2022-10-30 01:15:00")), Some(0.3));
+
+        let purchased = meter(
+            "Purchased",
+            &[
+                ("2022-10-30 01:00:00", 1000.0),
+                ("2022-10-30 02:00:00", 1000.0),
+                ("2022-10-30 02:00:00", 2000.0),
+                ("2022-10-30 03:00:00", 1000.0),
+                ("2022-10-30 04:00:00", 500.0),
+            ],
+        );
+        let feed_in = meter("FeedIn", &[("2022-10-30 03:00:00", 3000.0)]);
+
+        let valuation = prices.value(&[purchased, feed_in], &Berlin);
+        assert!((valuation.purchased - 5.0).abs() < 1e-9);
+        assert!((valuation.purchase_cost - (0.1 + 0.2 + 0.6 + 0.4)).abs() < 1e-9);
+        assert!((valuation.unpriced_purchased - 0.5).abs() < 1e-9);
+        assert!((valuation.feed_in_value - 1.2).abs() < 1e-9);
+
+        assert_eq!(valuation.hours.len(), 5);
+        assert_eq!(valuation.hours[1].start, at("2022-10-30 02:00:00"));
+        assert_eq!(valuation.hours[2].start, at("2022-10-30 02:00:00"));
+        assert_eq!(valuation.hours[2].utc, utc("2022-10-30 01:00:00"));
+        assert_eq!(valuation.hours[2].price, Some(0.3));
+        assert_eq!(valuation.hours[4].price, None);
+
+        let shifts = valuation.shift_opportunities(2);
+        assert_eq!(shifts.len(), 2);
+        // 2 kWh at 0.30 instead of 0.10
+        assert_eq!(shifts[0].hour.utc, utc("2022-10-30 01:00:00"));
+        assert_eq!(shifts[0].cheapest, at("2022-10-30 01:00:00"));
+        assert!((shifts[0].saving - 0.4).abs() < 1e-9);
+        assert!((shifts[1].saving - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<PriceSeries>();
+        is_normal::<HourValue>();
+        is_normal::<SpotValuation>();
+        is_normal::<ShiftOpportunity>();
+    }
+}
@@ -0,0 +1,716 @@
+//! Module for keeping a local SQLite history of a site's time series, so data is
+//! downloaded from SolarEdge only once. Enabled by the `store` feature.
+//!
+//! For every site, endpoint and time unit the store records the end of the last
+//! synced window, which is never later than the time of the sync. A sync starts
+//! from there (or from the start of the site's data period on the first run) and
+//! fetches windows no longer than the API allows.
+//! Each window is committed in its own transaction, so an interrupted sync resumes
+//! after the last complete window.
+//!
+//! Values are keyed on their instant in UTC, so both occurrences of a local time
+//! repeated when daylight saving ends are kept.
+
+use crate::date_value::{parse_timestamp, LocalClock};
+use crate::windows::{period_start, windows};
+use crate::{
+    DataEndpoint, Error, Kind, Sample, SendReq, Series, SiteDataPeriodReq, SolaredgeCredentials,
+    TimeUnit, URL_DATE_FORMAT, URL_DATE_TIME_FORMAT,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sync_state (
+        site_id     TEXT NOT NULL,
+        endpoint    TEXT NOT NULL,
+        time_unit   TEXT NOT NULL,
+        last_synced TEXT NOT NULL,
+        PRIMARY KEY (site_id, endpoint, time_unit)
+    );
+    CREATE TABLE IF NOT EXISTS samples (
+        site_id   TEXT NOT NULL,
+        endpoint  TEXT NOT NULL,
+        time_unit TEXT NOT NULL,
+        name      TEXT NOT NULL,
+        serial    TEXT NOT NULL,
+        instant   TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        value     REAL,
+        unit      TEXT NOT NULL,
+        PRIMARY KEY (site_id, endpoint, time_unit, name, serial, instant)
+    );
+";
+
+/// Outcome of a [Store::sync].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SyncReport {
+    /// Number of windows fetched and committed
+    pub windows: u32,
+
+    /// Number of values stored (new or updated)
+    pub samples: u64,
+
+    /// End of the last synced window, None if nothing has been synced yet
+    pub last_synced: Option<NaiveDateTime>,
+}
+
+/// A local SQLite history of site time series.
+#[derive(Debug)]
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Open the store in the given SQLite database file, creating it if needed.
+    ///
+    /// # Errors
+    /// Returns [Kind::StoreError] if the database cannot be opened.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Store::init(Connection::open(path)?)
+    }
+
+    /// Open a store held in memory, e.g. for testing.
+    ///
+    /// # Errors
+    /// Returns [Kind::StoreError] if the database cannot be created.
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Store::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store { conn })
+    }
+
+    /// End of the last synced window for a site, endpoint and time unit.
+    ///
+    /// # Errors
+    /// Returns [Kind::StoreError] if the database cannot be read.
+    pub fn last_synced(
+        &self,
+        site_id: &str,
+        endpoint: &DataEndpoint,
+        time_unit: &TimeUnit,
+    ) -> Result<Option<NaiveDateTime>, Error> {
+        let last: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT last_synced FROM sync_state
+                 WHERE site_id = ?1 AND endpoint = ?2 AND time_unit = ?3",
+                params![site_id, endpoint.to_string(), time_unit.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        last.map(|l| parse_timestamp(&l)).transpose()
+    }
+
+    /// Read stored series of a site, with the values from `start` to `end` inclusive.
+    ///
+    /// # Returns
+    /// One series per name and serial number, with values in timestamp order.
+    ///
+    /// # Errors
+    /// Returns [Kind::StoreError] if the database cannot be read.
+    pub fn series(
+        &self,
+        site_id: &str,
+        endpoint: &DataEndpoint,
+        time_unit: &TimeUnit,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<Series>, Error> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT name, serial, unit, timestamp, value FROM samples
+             WHERE site_id = ?1 AND endpoint = ?2 AND time_unit = ?3
+               AND timestamp BETWEEN ?4 AND ?5
+             ORDER BY name, serial, instant",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                site_id,
+                endpoint.to_string(),
+                time_unit.to_string(),
+                start.format(URL_DATE_TIME_FORMAT).to_string(),
+                end.format(URL_DATE_TIME_FORMAT).to_string(),
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<f64>>(4)?,
+                ))
+            },
+        )?;
+
+        let mut series: Vec<Series> = Vec::new();
+        for row in rows {
+            let (name, serial, unit, timestamp, value) = row?;
+            let serial = Some(serial).filter(|s| !s.is_empty());
+            let sample = Sample {
+                timestamp: parse_timestamp(&timestamp)?,
+                value,
+            };
+            match series.last_mut() {
+                Some(s) if s.name == name && s.serial == serial => s.samples.push(sample),
+                _ => series.push(Series {
+                    name,
+                    serial,
+                    unit,
+                    samples: vec![sample],
+                }),
+            }
+        }
+        Ok(series)
+    }
+
+    /// Bring the stored history of a site up to date, fetching from SolarEdge.
+    ///
+    /// On the first sync the site's data period is requested to find where its
+    /// history starts. Data is fetched up to now, in the time zone of the site.
+    ///
+    /// # Arguments
+    ///
+    /// * `solaredge` - SolarEdge credentials of the site to sync
+    /// * `endpoint` - the request to sync
+    /// * `time_unit` - aggregation granularity. Ignored by endpoints that have a
+    ///   fixed resolution (power, inverter and storage data), but still part of
+    ///   the stored key.
+    /// * `time_zone` - time zone of the site, e.g. parsed from the `time_zone` of
+    ///   its [SiteLocation](crate::SiteLocation)
+    ///
+    /// # Errors
+    /// Returns an error if a request fails or the database cannot be written.
+    /// Windows committed before the error are kept.
+    pub fn sync_site<Tz: TimeZone>(
+        &mut self,
+        solaredge: &SolaredgeCredentials,
+        endpoint: &DataEndpoint,
+        time_unit: &TimeUnit,
+        time_zone: &Tz,
+    ) -> Result<SyncReport, Error> {
+        let site_id = solaredge.site_id();
+
+        // A previous sync determines where this one resumes, the start date is
+        // only needed for the first sync.
+        let start_date = if let Some(last) = self.last_synced(site_id, endpoint, time_unit)? {
+            last.date()
+        } else {
+            let period = SiteDataPeriodReq::new().send(solaredge)?.data_period;
+            let Some(d) = period.start_date else {
+                // The site has not produced yet, so there is nothing to sync.
+                return Ok(SyncReport::default());
+            };
+            NaiveDate::parse_from_str(&d, URL_DATE_FORMAT)
+                .map_err(|_| Error::new(Kind::InvalidTimestamp(d)))?
+        };
+
+        let until = Utc::now().with_timezone(time_zone);
+
+        self.sync(
+            site_id,
+            endpoint,
+            time_unit,
+            start_date,
+            &until,
+            |start, end| endpoint.fetch(solaredge, time_unit, start, end),
+        )
+    }
+
+    /// Bring the stored history up to date using the given fetch function.
+    ///
+    /// Syncing resumes at the start of the period, at the endpoint's
+    /// [resolution](DataEndpoint::resolution), that contains the end of the last
+    /// synced window, so values of a period that was still in progress are fetched
+    /// again and replaced. Inverter and storage data resume at the last stored
+    /// telemetry. Without a previous sync it starts at `start_date`, which is
+    /// otherwise ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `site_id` - the site to sync
+    /// * `endpoint` - the request to sync, which determines the window length
+    /// * `time_unit` - aggregation granularity
+    /// * `start_date` - where the site's history starts
+    /// * `until` - end of the range to sync, in the time zone of the site and not
+    ///   later than now: the end of the last window is recorded as synced
+    /// * `fetch` - returns the series for a window start and end
+    ///
+    /// # Errors
+    /// Returns the first error from `fetch` or the database. Windows committed
+    /// before the error are kept.
+    pub fn sync<F, Tz: TimeZone>(
+        &mut self,
+        site_id: &str,
+        endpoint: &DataEndpoint,
+        time_unit: &TimeUnit,
+        start_date: NaiveDate,
+        until: &DateTime<Tz>,
+        mut fetch: F,
+    ) -> Result<SyncReport, Error>
+    where
+        F: FnMut(NaiveDateTime, NaiveDateTime) -> Result<Vec<Series>, Error>,
+    {
+        let mut report = SyncReport {
+            last_synced: self.last_synced(site_id, endpoint, time_unit)?,
+            ..SyncReport::default()
+        };
+
+        let start = match (report.last_synced, endpoint.resolution(time_unit)) {
+            (Some(last), Some(resolution)) => period_start(last, &resolution),
+            (Some(last), None) => self
+                .last_sample(site_id, endpoint, time_unit)?
+                .unwrap_or(last),
+            (None, _) => start_date.and_time(NaiveTime::MIN),
+        };
+
+        let time_zone = until.timezone();
+        let until = until.naive_local();
+        for (window_start, window_end) in windows(start, until, endpoint.max_span(time_unit)) {
+            let series = fetch(window_start, window_end)?;
+            report.samples += self.save_window(
+                site_id, endpoint, time_unit, &time_zone, &series, window_end,
+            )?;
+            report.windows += 1;
+            report.last_synced = Some(window_end);
+        }
+
+        Ok(report)
+    }
+
+    /// Timestamp of the latest stored value for a site, endpoint and time unit.
+    fn last_sample(
+        &self,
+        site_id: &str,
+        endpoint: &DataEndpoint,
+        time_unit: &TimeUnit,
+    ) -> Result<Option<NaiveDateTime>, Error> {
+        let last: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT timestamp FROM samples
+                 WHERE site_id = ?1 AND endpoint = ?2 AND time_unit = ?3
+                 ORDER BY instant DESC LIMIT 1",
+                params![site_id, endpoint.to_string(), time_unit.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        last.map(|l| parse_timestamp(&l)).transpose()
+    }
+
+    fn save_window<Tz: TimeZone>(
+        &mut self,
+        site_id: &str,
+        endpoint: &DataEndpoint,
+        time_unit: &TimeUnit,
+        time_zone: &Tz,
+        series: &[Series],
+        window_end: NaiveDateTime,
+    ) -> Result<u64, Error> {
+        let endpoint = endpoint.to_string();
+        let time_unit = time_unit.to_string();
+        let mut count = 0;
+
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT OR REPLACE INTO samples
+                 (site_id, endpoint, time_unit, name, serial, instant, timestamp, value, unit)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for s in series {
+                let mut clock = LocalClock::new(time_zone);
+                for sample in &s.samples {
+                    let timestamp = sample.timestamp.format(URL_DATE_TIME_FORMAT).to_string();
+                    let Some(instant) = clock.utc(sample.timestamp) else {
+                        return Err(Error::new(Kind::InvalidTimestamp(timestamp)));
+                    };
+                    insert.execute(params![
+                        site_id,
+                        endpoint,
+                        time_unit,
+                        s.name,
+                        s.serial.as_deref().unwrap_or_default(),
+                        instant.format(URL_DATE_TIME_FORMAT).to_string(),
+                        timestamp,
+                        sample.value,
+                        s.unit,
+                    ])?;
+                    count += 1;
+                }
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO sync_state (site_id, endpoint, time_unit, last_synced)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                site_id,
+                endpoint,
+                time_unit,
+                window_end.format(URL_DATE_TIME_FORMAT).to_string()
+            ],
+        )?;
+        tx.commit()?;
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_normal;
+    use chrono::TimeDelta;
+    use chrono_tz::Europe::Berlin;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, URL_DATE_TIME_FORMAT).unwrap()
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, URL_DATE_FORMAT).unwrap()
+    }
+
+    /// Fake server returning one daily Production value per day of the window,
+    /// the value being the day of the month.
+    fn daily(start: NaiveDateTime, end: NaiveDateTime) -> Vec<Series> {
+        let mut samples = Vec::new();
+        let mut t = start;
+        while t <= end {
+            samples.push(Sample {
+                timestamp: t,
+                value: Some(f64::from(chrono::Datelike::day(&t))),
+            });
+            t += TimeDelta::days(1);
+        }
+        vec![Series {
+            name: "Production".to_string(),
+            serial: None,
+            unit: "Wh".to_string(),
+            samples,
+        }]
+    }
+
+    #[test]
+    fn store_first_sync_unit_test() {
+        let mut store = Store::open_in_memory().unwrap();
+        let mut calls = Vec::new();
+
+        let report = store
+            .sync(
+                "1",
+                &DataEndpoint::Power,
+                &TimeUnit::Day,
+                date("2022-01-10"),
+                &at("2022-03-20 23:59:59").and_utc(),
+                |start, end| {
+                    calls.push((start, end));
+                    Ok(daily(start, end))
+                },
+            )
+            .unwrap();
+
+        assert_eq!(calls.len(), 3);
+        assert_eq!(
+            calls[0],
+            (at("2022-01-10 00:00:00"), at("2022-02-09 23:59:59"))
+        );
+        assert_eq!(report.windows, 3);
+        assert_eq!(report.samples, 70);
+        assert_eq!(report.last_synced, Some(at("2022-03-20 23:59:59")));
+
+        let stored = store
+            .series(
+                "1",
+                &DataEndpoint::Power,
+                &TimeUnit::Day,
+                at("2022-01-01 00:00:00"),
+                at("2022-12-31 00:00:00"),
+            )
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].samples.len(), 70);
+        assert_eq!(stored[0].samples[0].value, Some(10.0));
+    }
+
+    #[test]
+    fn store_incremental_sync_unit_test() {
+        let mut store = Store::open_in_memory().unwrap();
+        let endpoint = DataEndpoint::EnergyDetails;
+        store
+            .sync(
+                "1",
+                &endpoint,
+                &TimeUnit::Day,
+                date("2022-01-01"),
+                &at("2022-01-15 12:00:00").and_utc(),
+                |s, e| Ok(daily(s, e)),
+            )
+            .unwrap();
+
+        // The next sync fetches again from the start of the last, partial, day.
+        let mut calls = Vec::new();
+        let report = store
+            .sync(
+                "1",
+                &endpoint,
+                &TimeUnit::Day,
+                date("2022-01-01"),
+                &at("2022-01-20 23:59:59").and_utc(),
+                |s, e| {
+                    calls.push((s, e));
+                    Ok(daily(s, e))
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            calls,
+            vec![(at("2022-01-15 00:00:00"), at("2022-01-20 23:59:59"))]
+        );
+        assert_eq!(report.samples, 6);
+
+        let stored = store
+            .series(
+                "1",
+                &endpoint,
+                &TimeUnit::Day,
+                at("2022-01-01 00:00:00"),
+                at("2022-01-31 00:00:00"),
+            )
+            .unwrap();
+        assert_eq!(stored[0].samples.len(), 20);
+
+        // Other sites, endpoints and time units are synced separately.
+        assert_eq!(
+            store.last_synced("1", &endpoint, &TimeUnit::Hour).unwrap(),
+            None
+        );
+        assert_eq!(
+            store.last_synced("2", &endpoint, &TimeUnit::Day).unwrap(),
+            None
+        );
+    }
+
+    /// Fake server returning a quarter-hour Power value of 1000 W for every quarter
+    /// of an hour of the window up to `now`.
+    fn quarters(start: NaiveDateTime, end: NaiveDateTime, now: NaiveDateTime) -> Vec<Series> {
+        let mut samples = Vec::new();
+        let mut t = start;
+        while t <= end.min(now) {
+            samples.push(Sample {
+                timestamp: t,
+                value: Some(1000.0),
+            });
+            t += TimeDelta::minutes(15);
+        }
+        vec![Series {
+            name: "Power".to_string(),
+            serial: None,
+            unit: "W".to_string(),
+            samples,
+        }]
+    }
+
+    #[test]
+    fn store_sync_midday_unit_test() {
+        let mut store = Store::open_in_memory().unwrap();
+        let endpoint = DataEndpoint::Power;
+        let midday = at("2022-01-15 12:05:00");
+        let report = store
+            .sync(
+                "1",
+                &endpoint,
+                &TimeUnit::Month,
+                date("2022-01-15"),
+                &midday.and_utc(),
+                |s, e| Ok(quarters(s, e, midday)),
+            )
+            .unwrap();
+        assert_eq!(report.last_synced, Some(midday));
+
+        // Power has quarter-hour resolution whatever the time unit, so the next
+        // day's sync resumes at the quarter in progress rather than the month.
+        let next_day = at("2022-01-16 12:05:00");
+        let mut calls = Vec::new();
+        store
+            .sync(
+                "1",
+                &endpoint,
+                &TimeUnit::Month,
+                date("2022-01-15"),
+                &next_day.and_utc(),
+                |s, e| {
+                    calls.push(s);
+                    Ok(quarters(s, e, next_day))
+                },
+            )
+            .unwrap();
+        assert_eq!(calls, vec![at("2022-01-15 12:00:00")]);
+
+        let stored = store
+            .series(
+                "1",
+                &endpoint,
+                &TimeUnit::Month,
+                at("2022-01-15 12:15:00"),
+                at("2022-01-15 23:45:00"),
+            )
+            .unwrap();
+        assert_eq!(stored[0].samples.len(), 47);
+        assert_eq!(stored[0].samples[0].value, Some(1000.0));
+    }
+
+    #[test]
+    fn store_daylight_saving_unit_test() {
+        let mut store = Store::open_in_memory().unwrap();
+        let endpoint = DataEndpoint::Power;
+        let until = Berlin
+            .from_local_datetime(&at("2022-10-30 23:59:59"))
+            .unwrap();
+        // 02:00 to 02:59 occurs twice when daylight saving ends.
+        let quarters = [
+            "01:45:00", "02:00:00", "02:15:00", "02:00:00", "02:15:00", "03:00:00",
+        ];
+        store
+            .sync(
+                "1",
+                &endpoint,
+                &TimeUnit::Day,
+                date("2022-10-30"),
+                &until,
+                |_, _| {
+                    Ok(vec![Series {
+                        name: "Power".to_string(),
+                        serial: None,
+                        unit: "W".to_string(),
+                        samples: quarters
+                            .iter()
+                            .zip(1..)
+                            .map(|(time, value)| Sample {
+                                timestamp: at(&format!("2022-10-30 {time}")),
+                                value: Some(f64::from(value)),
+                            })
+                            .collect(),
+                    }])
+                },
+            )
+            .unwrap();
+
+        let stored = store
+            .series(
+                "1",
+                &endpoint,
+                &TimeUnit::Day,
+                at("2022-10-30 00:00:00"),
+                at("2022-10-30 23:59:59"),
+            )
+            .unwrap();
+        let values = stored[0]
+            .samples
+            .iter()
+            .map(|s| s.value.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(stored[0].samples[3].timestamp, at("2022-10-30 02:00:00"));
+    }
+
+    #[test]
+    fn store_resume_at_period_start_unit_test() {
+        let mut store = Store::open_in_memory().unwrap();
+        let endpoint = DataEndpoint::Meters;
+        let sync = |store: &mut Store, until: &str| {
+            let mut calls = Vec::new();
+            store
+                .sync(
+                    "1",
+                    &endpoint,
+                    &TimeUnit::Month,
+                    date("2022-01-20"),
+                    &at(until).and_utc(),
+                    |s, _| {
+                        calls.push(s);
+                        Ok(Vec::new())
+                    },
+                )
+                .unwrap();
+            calls
+        };
+
+        assert_eq!(
+            sync(&mut store, "2022-03-15 23:59:59"),
+            vec![at("2022-01-20 00:00:00")]
+        );
+        // March was still in progress, so it is fetched again from its start.
+        assert_eq!(
+            sync(&mut store, "2022-04-02 23:59:59"),
+            vec![at("2022-03-01 00:00:00")]
+        );
+    }
+
+    #[test]
+    fn store_resume_after_error_unit_test() {
+        let mut store = Store::open_in_memory().unwrap();
+        let endpoint = DataEndpoint::InverterData("7E1".to_string());
+        let mut calls = 0;
+
+        let result = store.sync(
+            "1",
+            &endpoint,
+            &TimeUnit::Day,
+            date("2022-01-01"),
+            &at("2022-01-31 23:59:59").and_utc(),
+            |s, e| {
+                calls += 1;
+                if calls == 3 {
+                    Err(Error::new(Kind::HttpErrorStatus(
+                        "429".to_string(),
+                        "Too Many Requests".to_string(),
+                    )))
+                } else {
+                    Ok(daily(s, e))
+                }
+            },
+        );
+        assert!(result.is_err());
+        assert_eq!(
+            store.last_synced("1", &endpoint, &TimeUnit::Day).unwrap(),
+            Some(at("2022-01-14 23:59:59"))
+        );
+
+        let mut starts = Vec::new();
+        let report = store
+            .sync(
+                "1",
+                &endpoint,
+                &TimeUnit::Day,
+                date("2022-01-01"),
+                &at("2022-01-31 23:59:59").and_utc(),
+                |s, e| {
+                    starts.push(s);
+                    Ok(daily(s, e))
+                },
+            )
+            .unwrap();
+        assert_eq!(starts[0], at("2022-01-14 00:00:00"));
+        assert_eq!(report.last_synced, Some(at("2022-01-31 23:59:59")));
+
+        let stored = store
+            .series(
+                "1",
+                &endpoint,
+                &TimeUnit::Day,
+                at("2022-01-01 00:00:00"),
+                at("2022-01-31 23:59:59"),
+            )
+            .unwrap();
+        assert_eq!(stored[0].samples.len(), 31);
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<SyncReport>();
+    }
+}
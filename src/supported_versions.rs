@@ -1,5 +1,6 @@
 //! Module for querying the API versions supported by the SolarEdge monitoring server.
 
+use crate::extras::impl_unknown_fields;
 use crate::{SendReq, MONITORING_API_URL};
 use serde::Deserialize;
 
@@ -9,18 +10,36 @@ pub struct Req;
 
 /// Supported versions response
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Resp {
     /// An array of all the API versions supported by the server
     pub supported: Vec<Release>,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Resp {
+    supported: "supported",
+});
+
 /// A release version supported by the server
 #[derive(Clone, Deserialize, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize))]
 pub struct Release {
     /// A release number supported by the server in <major.minor.revision> format.
     pub release: String,
+
+    /// Fields not in the documented API schema
+    #[cfg(feature = "extras")]
+    #[serde(flatten)]
+    pub extras: crate::Extras,
 }
 
+impl_unknown_fields!(Release {});
+
 impl Req {
     /// Create a supported versions request message that can be sent to SolarEdge.
     #[must_use]
@@ -31,7 +50,7 @@ impl Req {
 
 impl SendReq<Resp> for Req {
     fn build_url(&self, _: &str, api_key: &str) -> String {
-        format!("{}version/supported?{}", *MONITORING_API_URL, api_key,)
+        format!("{}version/supported?{}", *MONITORING_API_URL, api_key)
     }
 }
 
@@ -46,6 +65,12 @@ mod tests {
     use super::*;
     use crate::is_normal;
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn round_trip_unit_test() {
+        crate::round_trip::<Resp>(r#"{"supported": [{"release": "1.0.0"}]}"#);
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<Req>();
@@ -5,19 +5,45 @@ use serde::Deserialize;
 /// Time units specified in SolarEdge server monitoring API requests and responses.
 /// Specifies the aggregation granularity of the data.
 #[derive(Clone, Deserialize, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize),
+    serde(into = "String")
+)]
+#[serde(from = "String")]
 pub enum SystemUnits {
     /// Imperial measurement units
     Imperial,
 
     /// Metric measurement units
     Metrics,
+
+    /// A measurement system not listed in the API documentation.
+    Unknown(String),
+}
+
+impl From<String> for SystemUnits {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "Imperial" => SystemUnits::Imperial,
+            "Metrics" => SystemUnits::Metrics,
+            _ => SystemUnits::Unknown(s),
+        }
+    }
+}
+
+impl From<SystemUnits> for String {
+    fn from(s: SystemUnits) -> Self {
+        s.to_string()
+    }
 }
 
 impl std::fmt::Display for SystemUnits {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match *self {
+        match self {
             SystemUnits::Imperial => write!(f, "Imperial"),
             SystemUnits::Metrics => write!(f, "Metrics"),
+            SystemUnits::Unknown(s) => write!(f, "{s}"),
         }
     }
 }
@@ -0,0 +1,496 @@
+//! Module for pricing purchased and fed-in energy under a time-of-use tariff.
+//!
+//! A [Tariff] is a list of [Band]s, each with an import and export rate that
+//! applies in some seasons, on weekdays or weekends, between two hours of the day.
+//! The first band matching an interval prices it. Fixed daily charges and a demand
+//! charge on the highest purchased power of each period are added on top.
+//!
+//! Pricing needs the Purchased and FeedIn meters of
+//! [SiteEnergyDetailedResp](crate::SiteEnergyDetailedResp) at quarter-hour or hour
+//! granularity, since coarser values cannot be split over the bands.
+
+use crate::time_series::kwh_factor;
+use crate::{BillingPeriod, Error, Kind, MeterType, Series, TimeUnit};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike, Weekday};
+
+/// Days of the week a band applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DayType {
+    /// Monday to Friday, except holidays
+    Weekday,
+
+    /// Saturday, Sunday and holidays
+    Weekend,
+
+    /// Every day
+    All,
+}
+
+/// A part of the year, e.g. summer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Season {
+    /// Name the bands refer to
+    pub name: String,
+
+    /// Months of the season, 1 for January to 12 for December
+    pub months: Vec<u32>,
+}
+
+/// Rates that apply during part of the day.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Band {
+    /// Name of the band, e.g. "peak". Bands with the same name are reported together.
+    pub name: String,
+
+    /// Season the band applies in, None for the whole year
+    pub season: Option<String>,
+
+    /// Days the band applies on
+    pub days: DayType,
+
+    /// First hour of the band, 0 to 23
+    pub start_hour: u32,
+
+    /// Hour the band ends, exclusive. A band ending at or before its start hour
+    /// runs past midnight, e.g. 22 to 6. Equal start and end hours cover the day.
+    pub end_hour: u32,
+
+    /// Price of purchased energy, per kWh
+    pub import_rate: f64,
+
+    /// Price paid for fed-in energy, per kWh
+    pub export_rate: f64,
+}
+
+/// A time-of-use tariff.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tariff {
+    /// Seasons the bands refer to
+    pub seasons: Vec<Season>,
+
+    /// Bands in order of precedence
+    pub bands: Vec<Band>,
+
+    /// Charge per day, regardless of energy
+    pub fixed_daily_charge: f64,
+
+    /// Charge per kW of the highest purchased power in a period
+    pub demand_rate: f64,
+
+    /// Days priced as weekends
+    pub holidays: Vec<NaiveDate>,
+}
+
+/// Energy and cost of one band over a period.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BandCost {
+    /// Name of the band
+    pub band: String,
+
+    /// Energy purchased, in kWh
+    pub purchased: f64,
+
+    /// Energy fed in, in kWh
+    pub feed_in: f64,
+
+    /// Cost of the purchased energy
+    pub import_cost: f64,
+
+    /// Credit for the fed-in energy
+    pub export_credit: f64,
+}
+
+/// Cost of a billing period under a tariff.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TariffCost {
+    /// The billing period
+    pub period: BillingPeriod,
+
+    /// Energy and cost per band, in the order the bands are first listed in the tariff
+    pub bands: Vec<BandCost>,
+
+    /// Energy in kWh purchased at times no band covers, not priced
+    pub unpriced_purchased: f64,
+
+    /// Energy in kWh fed in at times no band covers, not priced
+    pub unpriced_feed_in: f64,
+
+    /// Fixed daily charges of the period
+    pub fixed_charges: f64,
+
+    /// Highest purchased power of the period, in kW
+    pub peak_demand: f64,
+
+    /// Demand charge on the peak demand
+    pub demand_charges: f64,
+
+    /// Import costs, fixed and demand charges, less export credits
+    pub total: f64,
+}
+
+impl Season {
+    /// Create a season.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name the bands refer to
+    /// * `months` - months of the season, 1 for January to 12 for December
+    #[must_use]
+    pub fn new(name: &str, months: &[u32]) -> Self {
+        Season {
+            name: name.to_string(),
+            months: months.to_vec(),
+        }
+    }
+}
+
+impl Band {
+    /// Create a band.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name of the band, e.g. "peak"
+    /// * `season` - name of the season the band applies in, None for the whole year
+    /// * `days` - days the band applies on
+    /// * `start_hour` - first hour of the band
+    /// * `end_hour` - hour the band ends, exclusive. At or before `start_hour` for
+    ///   bands that run past midnight.
+    /// * `import_rate` - price of purchased energy, per kWh
+    /// * `export_rate` - price paid for fed-in energy, per kWh
+    #[must_use]
+    pub fn new(
+        name: &str,
+        season: Option<&str>,
+        days: DayType,
+        start_hour: u32,
+        end_hour: u32,
+        import_rate: f64,
+        export_rate: f64,
+    ) -> Self {
+        Band {
+            name: name.to_string(),
+            season: season.map(str::to_string),
+            days,
+            start_hour,
+            end_hour,
+            import_rate,
+            export_rate,
+        }
+    }
+
+    fn covers_hour(&self, hour: u32) -> bool {
+        if self.start_hour < self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+impl Tariff {
+    /// Create a tariff.
+    ///
+    /// # Arguments
+    ///
+    /// * `bands` - bands in order of precedence
+    /// * `seasons` - seasons the bands refer to, None if no band is seasonal
+    /// * `fixed_daily_charge` - charge per day, None for no charge
+    /// * `demand_rate` - charge per kW of the highest purchased power in a period,
+    ///   None for no demand charge
+    #[must_use]
+    pub fn new(
+        bands: Vec<Band>,
+        seasons: Option<Vec<Season>>,
+        fixed_daily_charge: Option<f64>,
+        demand_rate: Option<f64>,
+    ) -> Self {
+        Tariff {
+            seasons: seasons.unwrap_or_default(),
+            bands,
+            fixed_daily_charge: fixed_daily_charge.unwrap_or(0.0),
+            demand_rate: demand_rate.unwrap_or(0.0),
+            holidays: Vec::new(),
+        }
+    }
+
+    fn is_weekend(&self, date: NaiveDate) -> bool {
+        matches!(date.weekday(), Weekday::Sat | Weekday::Sun) || self.holidays.contains(&date)
+    }
+
+    /// The band that prices the interval starting at `t`, None if no band covers it.
+    #[must_use]
+    pub fn band_at(&self, t: NaiveDateTime) -> Option<&Band> {
+        let weekend = self.is_weekend(t.date());
+        self.bands.iter().find(|b| {
+            let season = b.season.as_ref().is_none_or(|name| {
+                self.seasons
+                    .iter()
+                    .any(|s| &s.name == name && s.months.contains(&t.month()))
+            });
+            let days = match b.days {
+                DayType::Weekday => !weekend,
+                DayType::Weekend => weekend,
+                DayType::All => true,
+            };
+            season && days && b.covers_hour(t.hour())
+        })
+    }
+
+    /// Price purchased and fed-in energy over billing periods.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - energy details series with Purchased and FeedIn meters. Other
+    ///   meters are ignored. Several series of a meter (e.g. from consecutive
+    ///   requests) are all priced, as is each occurrence of a local time
+    ///   repeated when daylight saving ends.
+    /// * `time_unit` - granularity of the series, QuarterOfAnHour or Hour
+    /// * `periods` - the billing periods
+    ///
+    /// # Returns
+    /// The cost of each period, in the order of `periods`.
+    ///
+    /// # Errors
+    /// Returns [Kind::InvalidArgument] if `time_unit` is coarser than an hour.
+    pub fn price(
+        &self,
+        series: &[Series],
+        time_unit: &TimeUnit,
+        periods: &[BillingPeriod],
+    ) -> Result<Vec<TariffCost>, Error> {
+        let interval_hours = match time_unit {
+            TimeUnit::QuarterOfAnHour => 0.25,
+            TimeUnit::Hour => 1.0,
+            _ => {
+                return Err(Error::new(Kind::InvalidArgument(format!(
+                    "time-of-use pricing needs quarter-hour or hour values, not {time_unit}"
+                ))))
+            }
+        };
+
+        let purchased = MeterType::Purchased.to_string();
+        let feed_in = MeterType::FeedIn.to_string();
+
+        Ok(periods
+            .iter()
+            .map(|period| {
+                let mut cost = TariffCost::new(*period, &self.bands);
+
+                for s in series {
+                    let is_purchased = s.name == purchased;
+                    if !is_purchased && s.name != feed_in {
+                        continue;
+                    }
+                    let factor = kwh_factor(&s.unit);
+                    for sample in s.samples.iter().filter(|v| period.contains(v.timestamp)) {
+                        let kwh = sample.value.unwrap_or(0.0) * factor;
+                        cost.add(self.band_at(sample.timestamp), is_purchased, kwh);
+                        if is_purchased {
+                            cost.peak_demand = cost.peak_demand.max(kwh / interval_hours);
+                        }
+                    }
+                }
+
+                let days = (period.end - period.start).num_days() + 1;
+                #[allow(clippy::cast_precision_loss)]
+                let days = days.max(0) as f64;
+                cost.fixed_charges = self.fixed_daily_charge * days;
+                cost.demand_charges = self.demand_rate * cost.peak_demand;
+                cost.total = cost
+                    .bands
+                    .iter()
+                    .map(|b| b.import_cost - b.export_credit)
+                    .sum::<f64>()
+                    + cost.fixed_charges
+                    + cost.demand_charges;
+                cost
+            })
+            .collect())
+    }
+}
+
+impl TariffCost {
+    fn new(period: BillingPeriod, bands: &[Band]) -> Self {
+        let mut costs: Vec<BandCost> = Vec::new();
+        for b in bands {
+            if !costs.iter().any(|c| c.band == b.name) {
+                costs.push(BandCost {
+                    band: b.name.clone(),
+                    ..BandCost::default()
+                });
+            }
+        }
+
+        TariffCost {
+            period,
+            bands: costs,
+            unpriced_purchased: 0.0,
+            unpriced_feed_in: 0.0,
+            fixed_charges: 0.0,
+            peak_demand: 0.0,
+            demand_charges: 0.0,
+            total: 0.0,
+        }
+    }
+
+    fn add(&mut self, band: Option<&Band>, purchased: bool, kwh: f64) {
+        let cost = band.and_then(|b| {
+            let cost = self.bands.iter_mut().find(|c| c.band == b.name)?;
+            Some((b, cost))
+        });
+        match (cost, purchased) {
+            (Some((band, cost)), true) => {
+                cost.purchased += kwh;
+                cost.import_cost += kwh * band.import_rate;
+            }
+            (Some((band, cost)), false) => {
+                cost.feed_in += kwh;
+                cost.export_credit += kwh * band.export_rate;
+            }
+            (None, true) => self.unpriced_purchased += kwh,
+            (None, false) => self.unpriced_feed_in += kwh,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, SiteEnergyDetailedResp, TimeSeries};
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::URL_DATE_TIME_FORMAT).unwrap()
+    }
+
+    fn tariff() -> Tariff {
+        let mut tariff = Tariff::new(
+            vec![
+                Band::new("peak", Some("summer"), DayType::Weekday, 16, 21, 0.5, 0.1),
+                Band::new("off-peak", None, DayType::All, 21, 16, 0.2, 0.05),
+                Band::new("shoulder", None, DayType::All, 16, 21, 0.3, 0.05),
+            ],
+            Some(vec![Season::new("summer", &[6, 7, 8, 9])]),
+            Some(1.0),
+            Some(10.0),
+        );
+        tariff.holidays.push(at("2022-07-04 00:00:00").date());
+        tariff
+    }
+
+    #[test]
+    fn band_at_unit_test() {
+        let tariff = tariff();
+        let band = |t| tariff.band_at(at(t)).map(|b| b.name.as_str());
+        // Friday in summer
+        assert_eq!(band("2022-07-01 16:00:00"), Some("peak"));
+        assert_eq!(band("2022-07-01 20:45:00"), Some("peak"));
+        assert_eq!(band("2022-07-01 21:00:00"), Some("off-peak"));
+        assert_eq!(band("2022-07-01 03:00:00"), Some("off-peak"));
+        // Saturday, a holiday and a winter weekday
+        assert_eq!(band("2022-07-02 17:00:00"), Some("shoulder"));
+        assert_eq!(band("2022-07-04 17:00:00"), Some("shoulder"));
+        assert_eq!(band("2022-01-03 17:00:00"), Some("shoulder"));
+
+        let empty = Tariff::default();
+        assert!(empty.band_at(at("2022-07-01 16:00:00")).is_none());
+    }
+
+    #[test]
+    fn price_unit_test() {
+        let resp: SiteEnergyDetailedResp = serde_json::from_str(
+            r#"{"energyDetails": {"timeUnit": "QUARTER_OF_AN_HOUR", "unit": "Wh", "meters": [
+                {"type": "Purchased", "values": [
+                    {"date": "2022-07-01 16:00:00", "value": 500},
+                    {"date": "2022-07-01 16:15:00", "value": 1000},
+                    {"date": "2022-07-01 22:00:00", "value": 250},
+                    {"date": "2022-07-02 00:00:00", "value": 4000}]},
+                {"type": "FeedIn", "values": [
+                    {"date": "2022-07-01 12:00:00", "value": 2000},
+                    {"date": "2022-07-01 16:30:00"}]},
+                {"type": "Production", "values": [
+                    {"date": "2022-07-01 12:00:00", "value": 9000}]}
+            ]}}"#,
+        )
+        .unwrap();
+        let series = resp.energy_details.time_series().unwrap();
+        let day = at("2022-07-01 00:00:00").date();
+
+        let costs = tariff()
+            .price(
+                &series,
+                &TimeUnit::QuarterOfAnHour,
+                &[BillingPeriod::new(day, day)],
+            )
+            .unwrap();
+        assert_eq!(costs.len(), 1);
+        let cost = &costs[0];
+
+        let bands = cost
+            .bands
+            .iter()
+            .map(|b| b.band.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(bands, vec!["peak", "off-peak", "shoulder"]);
+
+        let peak = &cost.bands[0];
+        assert!((peak.purchased - 1.5).abs() < 1e-9);
+        assert!((peak.import_cost - 0.75).abs() < 1e-9);
+        let off_peak = &cost.bands[1];
+        assert!((off_peak.purchased - 0.25).abs() < 1e-9);
+        assert!((off_peak.feed_in - 2.0).abs() < 1e-9);
+        assert!((off_peak.export_credit - 0.1).abs() < 1e-9);
+
+        // 1 kWh in a quarter hour is 4 kW
+        assert!((cost.peak_demand - 4.0).abs() < 1e-9);
+        assert!((cost.demand_charges - 40.0).abs() < 1e-9);
+        assert!((cost.fixed_charges - 1.0).abs() < f64::EPSILON);
+        assert!((cost.total - (0.75 + 0.05 - 0.1 + 1.0 + 40.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn price_daylight_saving_unit_test() {
+        // 02:00 to 02:59 occurs twice when daylight saving ends in Europe/Berlin.
+        let resp: SiteEnergyDetailedResp = serde_json::from_str(
+            r#"{"energyDetails": {"timeUnit": "QUARTER_OF_AN_HOUR", "unit": "Wh", "meters": [
+                {"type": "Purchased", "values": [
+                    {"date": "2022-10-30 01:45:00", "value": 500},
+                    {"date": "2022-10-30 02:00:00", "value": 1000},
+                    {"date": "2022-10-30 02:15:00", "value": 250},
+                    {"date": "2022-10-30 02:00:00", "value": 1500},
+                    {"date": "2022-10-30 02:15:00", "value": 250}]}
+            ]}}"#,
+        )
+        .unwrap();
+        let series = resp.energy_details.time_series().unwrap();
+        let day = at("2022-10-30 00:00:00").date();
+
+        let cost = &tariff()
+            .price(
+                &series,
+                &TimeUnit::QuarterOfAnHour,
+                &[BillingPeriod::new(day, day)],
+            )
+            .unwrap()[0];
+        let off_peak = &cost.bands[1];
+        assert!((off_peak.purchased - 3.5).abs() < 1e-9);
+        assert!((off_peak.import_cost - 0.7).abs() < 1e-9);
+        // The second 02:00 quarter hour, 1.5 kWh, is 6 kW
+        assert!((cost.peak_demand - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn price_granularity_unit_test() {
+        let e = tariff().price(&[], &TimeUnit::Day, &[]).unwrap_err();
+        assert!(matches!(e.kind(), Kind::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<DayType>();
+        is_normal::<Season>();
+        is_normal::<Band>();
+        is_normal::<Tariff>();
+        is_normal::<BandCost>();
+        is_normal::<TariffCost>();
+    }
+}
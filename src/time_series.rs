@@ -0,0 +1,306 @@
+//! Module for viewing the time-series responses (energy, power, meter readings and
+//! telemetries) as a common list of timestamped series, so they can be exported
+//! and analyzed the same way.
+
+use crate::date_value::parse_timestamp;
+use crate::{
+    DateValue, Energy, EnergyDetails, Error, MeterEnergyDetails, MeterType, MeterValue, Power,
+    PowerDetails, StorageData, Telemetries,
+};
+use chrono::NaiveDateTime;
+
+/// A single timestamped value of a series.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    /// Site local date and time of the value
+    pub timestamp: NaiveDateTime,
+
+    /// The value, None if the server did not report one for this time
+    pub value: Option<f64>,
+}
+
+/// Values of one quantity over time, e.g. the readings of one meter.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Series {
+    /// What the values measure: the meter type (e.g. "Production") or the
+    /// telemetry field (e.g. "totalActivePower")
+    pub name: String,
+
+    /// Serial number of the meter or device that reported the values, if known
+    pub serial: Option<String>,
+
+    /// Unit of the values, e.g. "Wh"
+    pub unit: String,
+
+    /// The values in the order returned by the server
+    pub samples: Vec<Sample>,
+}
+
+impl Series {
+    /// Name of the series, followed by the serial number if there is one.
+    #[must_use]
+    pub fn label(&self) -> String {
+        match &self.serial {
+            Some(s) => format!("{} {s}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    fn from_values(
+        name: String,
+        serial: Option<String>,
+        unit: &str,
+        values: &[DateValue],
+    ) -> Result<Self, Error> {
+        let samples = values
+            .iter()
+            .map(|v| {
+                Ok(Sample {
+                    timestamp: v.timestamp()?,
+                    value: v.value,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Series {
+            name,
+            serial,
+            unit: unit.to_string(),
+            samples,
+        })
+    }
+
+    fn from_meters(meters: &[MeterValue], unit: &str) -> Result<Vec<Self>, Error> {
+        meters
+            .iter()
+            .map(|m| Series::from_values(m.meter_type.to_string(), None, unit, &m.values))
+            .collect()
+    }
+}
+
+/// Factor converting an energy value in `unit` to kWh. Unknown units are taken
+/// as Wh, the unit SolarEdge uses by default.
+pub(crate) fn kwh_factor(unit: &str) -> f64 {
+    match unit {
+        "kWh" => 1.0,
+        "MWh" => 1_000.0,
+        _ => 0.001,
+    }
+}
+
+/// Implemented by the responses that hold values over time.
+pub trait TimeSeries {
+    /// The series held by this response, one per meter, device or telemetry field.
+    ///
+    /// # Returns
+    /// The series, or an error if a date in the response cannot be parsed.
+    ///
+    /// # Errors
+    /// Returns [Kind::InvalidTimestamp](crate::Kind::InvalidTimestamp) for a malformed date.
+    fn time_series(&self) -> Result<Vec<Series>, Error>;
+}
+
+impl TimeSeries for Energy {
+    fn time_series(&self) -> Result<Vec<Series>, Error> {
+        let name = MeterType::Production.to_string();
+        Ok(vec![Series::from_values(
+            name,
+            None,
+            &self.unit,
+            &self.values,
+        )?])
+    }
+}
+
+impl TimeSeries for Power {
+    fn time_series(&self) -> Result<Vec<Series>, Error> {
+        let name = MeterType::Production.to_string();
+        Ok(vec![Series::from_values(
+            name,
+            None,
+            &self.unit,
+            &self.values,
+        )?])
+    }
+}
+
+impl TimeSeries for EnergyDetails {
+    fn time_series(&self) -> Result<Vec<Series>, Error> {
+        Series::from_meters(&self.meters, &self.unit)
+    }
+}
+
+impl TimeSeries for PowerDetails {
+    fn time_series(&self) -> Result<Vec<Series>, Error> {
+        Series::from_meters(&self.meters, &self.unit)
+    }
+}
+
+impl TimeSeries for MeterEnergyDetails {
+    fn time_series(&self) -> Result<Vec<Series>, Error> {
+        self.meters
+            .iter()
+            .map(|m| {
+                Series::from_values(
+                    m.meter_type.to_string(),
+                    Some(m.meter_serial_number.clone()),
+                    &self.unit,
+                    &m.values,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Name, unit and accessor of a numeric telemetry field.
+type TelemetryField<T> = (&'static str, &'static str, fn(&T) -> Option<f64>);
+
+/// Collect the numeric fields of a list of telemetries into one series per field.
+fn telemetry_series<T>(
+    serial: Option<&str>,
+    telemetries: &[T],
+    date: impl Fn(&T) -> &str,
+    fields: &[TelemetryField<T>],
+) -> Result<Vec<Series>, Error> {
+    let timestamps = telemetries
+        .iter()
+        .map(|t| parse_timestamp(date(t)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(fields
+        .iter()
+        .map(|(name, unit, value)| Series {
+            name: (*name).to_string(),
+            serial: serial.map(str::to_string),
+            unit: (*unit).to_string(),
+            samples: telemetries
+                .iter()
+                .zip(&timestamps)
+                .map(|(t, timestamp)| Sample {
+                    timestamp: *timestamp,
+                    value: value(t),
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+impl TimeSeries for Telemetries {
+    fn time_series(&self) -> Result<Vec<Series>, Error> {
+        telemetry_series(
+            None,
+            &self.t,
+            |t| &t.date,
+            &[
+                ("totalActivePower", "W", |t| {
+                    t.total_active_power.map(f64::from)
+                }),
+                ("dcVoltage", "V", |t| t.dc_voltage.map(f64::from)),
+                ("powerLimit", "%", |t| t.power_limit.map(f64::from)),
+                ("totalEnergy", "Wh", |t| Some(t.total_energy)),
+                ("temperature", "C", |t| Some(f64::from(t.temperature))),
+            ],
+        )
+    }
+}
+
+impl TimeSeries for StorageData {
+    fn time_series(&self) -> Result<Vec<Series>, Error> {
+        let mut series = Vec::new();
+        for b in &self.batteries.e {
+            series.extend(telemetry_series(
+                Some(&b.serial_number),
+                &b.telemetries,
+                |t| &t.time_stamp,
+                &[
+                    ("power", "W", |t| t.power.map(f64::from)),
+                    ("batteryPercentageState", "%", |t| {
+                        t.battery_percentage_state.map(f64::from)
+                    }),
+                    ("lifeTimeEnergyCharged", "Wh", |t| {
+                        t.life_time_energy_charged
+                    }),
+                    ("lifeTimeEnergyDischarged", "Wh", |t| {
+                        t.life_time_energy_discharged
+                    }),
+                    ("fullPackEnergyAvailable", "Wh", |t| {
+                        t.full_pack_energy_available
+                    }),
+                    ("internalTemp", "C", |t| t.internal_temp.map(f64::from)),
+                    ("ACGridCharging", "Wh", |t| t.ac_grid_charging),
+                ],
+            )?);
+        }
+        Ok(series)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_normal, Kind, SiteEnergyDetailedResp, SiteStorageDataResp};
+
+    #[test]
+    fn energy_details_time_series_unit_test() {
+        let resp: SiteEnergyDetailedResp = serde_json::from_str(
+            r#"{"energyDetails": {"timeUnit": "DAY", "unit": "Wh", "meters": [
+                {"type": "Production", "values": [
+                    {"date": "2022-01-01 00:00:00", "value": 1000.5},
+                    {"date": "2022-01-02 00:00:00"}]},
+                {"type": "FeedIn", "values": [
+                    {"date": "2022-01-01 00:00:00", "value": 400}]}
+            ]}}"#,
+        )
+        .unwrap();
+
+        let series = resp.energy_details.time_series().unwrap();
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].label(), "Production");
+        assert_eq!(series[0].unit, "Wh");
+        assert_eq!(series[0].samples.len(), 2);
+        assert_eq!(series[0].samples[1].value, None);
+        assert_eq!(
+            series[1].samples[0].timestamp.to_string(),
+            "2022-01-01 00:00:00"
+        );
+    }
+
+    #[test]
+    fn storage_time_series_unit_test() {
+        let resp: SiteStorageDataResp = serde_json::from_str(
+            r#"{"storageData": {"batteryCount": 1, "batteries": [{
+                "nameplate": 9800, "serialNumber": "BAT-1", "modelNumber": "M",
+                "telemetryCount": 1,
+                "telemetries": [{"timeStamp": "2022-01-01 12:00:00", "power": -500,
+                    "batteryPercentageState": 80.5}]
+            }]}}"#,
+        )
+        .unwrap();
+
+        let series = resp.storage_data.time_series().unwrap();
+        assert_eq!(series.len(), 7);
+        assert_eq!(series[0].label(), "power BAT-1");
+        assert_eq!(series[0].samples[0].value, Some(-500.0));
+        assert_eq!(series[1].samples[0].value, Some(80.5));
+        assert_eq!(series[2].samples[0].value, None);
+    }
+
+    #[test]
+    fn invalid_timestamp_unit_test() {
+        let power = Power {
+            values: vec![DateValue {
+                date: "yesterday".to_string(),
+                ..DateValue::default()
+            }],
+            ..Power::default()
+        };
+        let e = power.time_series().unwrap_err();
+        assert!(matches!(e.kind(), Kind::InvalidTimestamp(_)));
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<Sample>();
+        is_normal::<Series>();
+    }
+}
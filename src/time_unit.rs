@@ -5,7 +5,12 @@ use serde::Deserialize;
 /// Time units specified in SolarEdge server monitoring API requests and responses.
 /// Specifies the aggregation granularity of the data.
 #[derive(Clone, Deserialize, Debug, PartialEq, Default)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(
+    feature = "serialize",
+    derive(serde::Serialize),
+    serde(into = "String")
+)]
+#[serde(from = "String")]
 pub enum TimeUnit {
     /// 15 minutes
     QuarterOfAnHour,
@@ -25,17 +30,41 @@ pub enum TimeUnit {
 
     /// Calendar year
     Year,
+
+    /// A time unit not listed in the API documentation.
+    Unknown(String),
+}
+
+impl From<String> for TimeUnit {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "QUARTER_OF_AN_HOUR" => TimeUnit::QuarterOfAnHour,
+            "HOUR" => TimeUnit::Hour,
+            "DAY" => TimeUnit::Day,
+            "WEEK" => TimeUnit::Week,
+            "MONTH" => TimeUnit::Month,
+            "YEAR" => TimeUnit::Year,
+            _ => TimeUnit::Unknown(s),
+        }
+    }
+}
+
+impl From<TimeUnit> for String {
+    fn from(t: TimeUnit) -> Self {
+        t.to_string()
+    }
 }
 
 impl std::fmt::Display for TimeUnit {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match *self {
+        match self {
             TimeUnit::QuarterOfAnHour => write!(f, "QUARTER_OF_AN_HOUR"),
             TimeUnit::Hour => write!(f, "HOUR"),
             TimeUnit::Day => write!(f, "DAY"),
             TimeUnit::Week => write!(f, "WEEK"),
             TimeUnit::Month => write!(f, "MONTH"),
             TimeUnit::Year => write!(f, "YEAR"),
+            TimeUnit::Unknown(s) => write!(f, "{s}"),
         }
     }
 }
@@ -51,6 +80,18 @@ mod tests {
         assert_eq!(format!("{t}"), "YEAR");
     }
 
+    #[test]
+    fn time_unit_from_unit_test() {
+        assert_eq!(
+            TimeUnit::from("QUARTER_OF_AN_HOUR".to_string()),
+            TimeUnit::QuarterOfAnHour
+        );
+        assert_eq!(
+            TimeUnit::from("FIVE_MINUTES".to_string()),
+            TimeUnit::Unknown("FIVE_MINUTES".to_string())
+        );
+    }
+
     #[test]
     fn normal_types_unit_test() {
         is_normal::<TimeUnit>();
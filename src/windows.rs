@@ -0,0 +1,407 @@
+//! Module for splitting a time range into windows that the SolarEdge server
+//! monitoring API accepts in a single request.
+//!
+//! The time-series requests limit the span between their start and end, depending
+//! on the request and the aggregation granularity. For example, quarter-hour energy
+//! details can be requested for at most one month at a time.
+
+use crate::{
+    Error, SendReq, Series, SiteEnergyDetailedReq, SiteEnergyReq, SiteGetMetersDataReq,
+    SiteInverterTechnicalDataReq, SitePowerDetailedReq, SitePowerReq, SiteStorageDataReq,
+    SolaredgeCredentials, TimeSeries, TimeUnit,
+};
+use chrono::{
+    Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Weekday,
+};
+
+/// A request that returns values over a range of time.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DataEndpoint {
+    /// [SiteEnergyReq](crate::SiteEnergyReq)
+    Energy,
+
+    /// [SiteEnergyDetailedReq](crate::SiteEnergyDetailedReq)
+    EnergyDetails,
+
+    /// [SitePowerReq](crate::SitePowerReq)
+    Power,
+
+    /// [SitePowerDetailedReq](crate::SitePowerDetailedReq)
+    PowerDetails,
+
+    /// [SiteGetMetersDataReq](crate::SiteGetMetersDataReq)
+    Meters,
+
+    /// [SiteInverterTechnicalDataReq](crate::SiteInverterTechnicalDataReq) for the
+    /// inverter with the given serial number
+    InverterData(String),
+
+    /// [SiteStorageDataReq](crate::SiteStorageDataReq)
+    StorageData,
+}
+
+impl std::fmt::Display for DataEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DataEndpoint::Energy => write!(f, "energy"),
+            DataEndpoint::EnergyDetails => write!(f, "energyDetails"),
+            DataEndpoint::Power => write!(f, "power"),
+            DataEndpoint::PowerDetails => write!(f, "powerDetails"),
+            DataEndpoint::Meters => write!(f, "meters"),
+            DataEndpoint::InverterData(serial) => write!(f, "inverterData/{serial}"),
+            DataEndpoint::StorageData => write!(f, "storageData"),
+        }
+    }
+}
+
+/// Longest span of time a single request may cover.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaxSpan {
+    /// 7 days
+    Week,
+
+    /// One calendar month
+    Month,
+
+    /// One calendar year
+    Year,
+
+    /// No limit
+    Unlimited,
+}
+
+impl DataEndpoint {
+    /// The longest span of time a request to this endpoint may cover.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_unit` - aggregation granularity of the request. Ignored by
+    ///   endpoints that have a fixed resolution (power, inverter and storage data).
+    #[must_use]
+    pub fn max_span(&self, time_unit: &TimeUnit) -> MaxSpan {
+        match self {
+            DataEndpoint::Energy | DataEndpoint::EnergyDetails | DataEndpoint::Meters => {
+                match time_unit {
+                    TimeUnit::QuarterOfAnHour | TimeUnit::Hour => MaxSpan::Month,
+                    TimeUnit::Day | TimeUnit::Unknown(_) => MaxSpan::Year,
+                    TimeUnit::Week | TimeUnit::Month | TimeUnit::Year => MaxSpan::Unlimited,
+                }
+            }
+            DataEndpoint::Power | DataEndpoint::PowerDetails => MaxSpan::Month,
+            DataEndpoint::InverterData(_) | DataEndpoint::StorageData => MaxSpan::Week,
+        }
+    }
+
+    /// The granularity of the values this endpoint returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_unit` - aggregation granularity of the request. Ignored by
+    ///   endpoints that have a fixed resolution.
+    ///
+    /// # Returns
+    /// `time_unit` for the aggregating endpoints, [TimeUnit::QuarterOfAnHour] for
+    /// power and None for inverter and storage data, whose telemetries are
+    /// reported at irregular times.
+    #[must_use]
+    pub fn resolution(&self, time_unit: &TimeUnit) -> Option<TimeUnit> {
+        match self {
+            DataEndpoint::Energy | DataEndpoint::EnergyDetails | DataEndpoint::Meters => {
+                Some(time_unit.clone())
+            }
+            DataEndpoint::Power | DataEndpoint::PowerDetails => Some(TimeUnit::QuarterOfAnHour),
+            DataEndpoint::InverterData(_) | DataEndpoint::StorageData => None,
+        }
+    }
+
+    /// Send a single request to this endpoint and return the series of the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `solaredge` - SolarEdge credentials to use for sending
+    /// * `time_unit` - aggregation granularity. Ignored by endpoints that have a
+    ///   fixed resolution (power, inverter and storage data).
+    /// * `start` - start of the window
+    /// * `end` - end of the window, at most [max_span](Self::max_span) after `start`
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response dates cannot be parsed.
+    pub fn fetch(
+        &self,
+        solaredge: &SolaredgeCredentials,
+        time_unit: &TimeUnit,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<Series>, Error> {
+        let time_unit = Some(time_unit.clone());
+        match self {
+            DataEndpoint::Energy => SiteEnergyReq::new(start.date(), end.date(), time_unit)
+                .send(solaredge)?
+                .energy
+                .time_series(),
+            DataEndpoint::EnergyDetails => SiteEnergyDetailedReq::new(start, end, time_unit, None)
+                .send(solaredge)?
+                .energy_details
+                .time_series(),
+            DataEndpoint::Power => SitePowerReq::new(start, end)
+                .send(solaredge)?
+                .power
+                .time_series(),
+            DataEndpoint::PowerDetails => SitePowerDetailedReq::new(start, end, None)
+                .send(solaredge)?
+                .power_details
+                .time_series(),
+            DataEndpoint::Meters => SiteGetMetersDataReq::new(start, end, time_unit, None)
+                .send(solaredge)?
+                .meter_energy_details
+                .time_series(),
+            DataEndpoint::InverterData(serial) => {
+                SiteInverterTechnicalDataReq::new(serial, start, end)
+                    .send(solaredge)?
+                    .data
+                    .telemetries
+                    .time_series()
+            }
+            DataEndpoint::StorageData => SiteStorageDataReq::new(start, end, None)
+                .send(solaredge)?
+                .storage_data
+                .time_series(),
+        }
+    }
+
+    /// Fetch the series over a range of any length, sending one request per window
+    /// of at most [max_span](Self::max_span).
+    ///
+    /// # Arguments
+    ///
+    /// * `solaredge` - SolarEdge credentials to use for sending
+    /// * `time_unit` - aggregation granularity. Ignored by endpoints that have a
+    ///   fixed resolution (power, inverter and storage data).
+    /// * `start` - start of the range
+    /// * `end` - end of the range, inclusive
+    ///
+    /// # Returns
+    /// The series of every window, in order. A meter or device has one series per
+    /// window.
+    ///
+    /// # Errors
+    /// Returns an error if a request fails or the response dates cannot be parsed.
+    pub fn fetch_range(
+        &self,
+        solaredge: &SolaredgeCredentials,
+        time_unit: &TimeUnit,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<Series>, Error> {
+        let mut series = Vec::new();
+        for (window_start, window_end) in windows(start, end, self.max_span(time_unit)) {
+            series.extend(self.fetch(solaredge, time_unit, window_start, window_end)?);
+        }
+        Ok(series)
+    }
+}
+
+/// Split the range from `start` to `end` (both inclusive) into consecutive windows
+/// no longer than `span`. Each window ends one second before the next one starts.
+///
+/// # Arguments
+///
+/// * `start` - start of the range
+/// * `end` - end of the range
+/// * `span` - longest span of a window, e.g. from [DataEndpoint::max_span]
+///
+/// # Returns
+/// The start and end of each window, in order. Empty if `end` is before `start`.
+#[must_use]
+pub fn windows(
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    span: MaxSpan,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut windows = Vec::new();
+    let mut window_start = start;
+
+    while window_start <= end {
+        let next = match span {
+            MaxSpan::Week => window_start.checked_add_days(Days::new(7)),
+            MaxSpan::Month => window_start.checked_add_months(Months::new(1)),
+            MaxSpan::Year => window_start.checked_add_months(Months::new(12)),
+            MaxSpan::Unlimited => None,
+        };
+
+        match next {
+            Some(next) if next <= end => {
+                windows.push((window_start, next - TimeDelta::seconds(1)));
+                window_start = next;
+            }
+            _ => {
+                windows.push((window_start, end));
+                break;
+            }
+        }
+    }
+
+    windows
+}
+
+/// The start of the aggregation period containing `t`, e.g. the first day of the
+/// month for [TimeUnit::Month]. Weeks start on Monday.
+#[must_use]
+pub fn period_start(t: NaiveDateTime, time_unit: &TimeUnit) -> NaiveDateTime {
+    period_start_from(t, time_unit, Weekday::Mon)
+}
+
+/// The start of the aggregation period containing `t`, with weeks starting on
+/// `week_start`.
+pub(crate) fn period_start_from(
+    t: NaiveDateTime,
+    time_unit: &TimeUnit,
+    week_start: Weekday,
+) -> NaiveDateTime {
+    let day = t.date();
+    let start = match time_unit {
+        TimeUnit::QuarterOfAnHour => {
+            let time = NaiveTime::from_hms_opt(t.hour(), t.minute() - t.minute() % 15, 0);
+            return day.and_time(time.unwrap_or(NaiveTime::MIN));
+        }
+        TimeUnit::Hour => {
+            let time = NaiveTime::from_hms_opt(t.hour(), 0, 0);
+            return day.and_time(time.unwrap_or(NaiveTime::MIN));
+        }
+        TimeUnit::Day | TimeUnit::Unknown(_) => day,
+        TimeUnit::Week => day - Days::new(u64::from(day.weekday().days_since(week_start))),
+        TimeUnit::Month => day.with_day(1).unwrap_or(day),
+        TimeUnit::Year => NaiveDate::from_ymd_opt(day.year(), 1, 1).unwrap_or(day),
+    };
+    start.and_time(NaiveTime::MIN)
+}
+
+/// The start of the aggregation period following the one starting at `start`, or
+/// None if it is out of range.
+pub(crate) fn next_period(start: NaiveDateTime, time_unit: &TimeUnit) -> Option<NaiveDateTime> {
+    match time_unit {
+        TimeUnit::QuarterOfAnHour => start.checked_add_signed(TimeDelta::minutes(15)),
+        TimeUnit::Hour => start.checked_add_signed(TimeDelta::hours(1)),
+        TimeUnit::Day | TimeUnit::Unknown(_) => start.checked_add_days(Days::new(1)),
+        TimeUnit::Week => start.checked_add_days(Days::new(7)),
+        TimeUnit::Month => start.checked_add_months(Months::new(1)),
+        TimeUnit::Year => start.checked_add_months(Months::new(12)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_normal;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, crate::URL_DATE_TIME_FORMAT).unwrap()
+    }
+
+    #[test]
+    fn windows_month_unit_test() {
+        let w = windows(
+            at("2022-01-15 00:00:00"),
+            at("2022-03-20 23:59:59"),
+            MaxSpan::Month,
+        );
+        assert_eq!(
+            w,
+            vec![
+                (at("2022-01-15 00:00:00"), at("2022-02-14 23:59:59")),
+                (at("2022-02-15 00:00:00"), at("2022-03-14 23:59:59")),
+                (at("2022-03-15 00:00:00"), at("2022-03-20 23:59:59")),
+            ]
+        );
+    }
+
+    #[test]
+    fn windows_edges_unit_test() {
+        let start = at("2022-01-01 00:00:00");
+        assert_eq!(
+            windows(start, at("2022-01-07 23:59:59"), MaxSpan::Week),
+            vec![(start, at("2022-01-07 23:59:59"))]
+        );
+        assert_eq!(
+            windows(start, at("2022-01-08 00:00:00"), MaxSpan::Week).len(),
+            2
+        );
+        assert_eq!(
+            windows(start, at("2030-01-01 00:00:00"), MaxSpan::Unlimited).len(),
+            1
+        );
+        assert!(windows(start, at("2021-12-31 00:00:00"), MaxSpan::Year).is_empty());
+    }
+
+    #[test]
+    fn max_span_unit_test() {
+        assert_eq!(
+            DataEndpoint::EnergyDetails.max_span(&TimeUnit::QuarterOfAnHour),
+            MaxSpan::Month
+        );
+        assert_eq!(DataEndpoint::Energy.max_span(&TimeUnit::Day), MaxSpan::Year);
+        assert_eq!(
+            DataEndpoint::Meters.max_span(&TimeUnit::Month),
+            MaxSpan::Unlimited
+        );
+        assert_eq!(
+            DataEndpoint::InverterData("7E1".to_string()).max_span(&TimeUnit::Day),
+            MaxSpan::Week
+        );
+        assert_eq!(
+            DataEndpoint::InverterData("7E1".to_string()).to_string(),
+            "inverterData/7E1"
+        );
+    }
+
+    #[test]
+    fn resolution_unit_test() {
+        assert_eq!(
+            DataEndpoint::Energy.resolution(&TimeUnit::Month),
+            Some(TimeUnit::Month)
+        );
+        assert_eq!(
+            DataEndpoint::PowerDetails.resolution(&TimeUnit::Month),
+            Some(TimeUnit::QuarterOfAnHour)
+        );
+        assert_eq!(DataEndpoint::StorageData.resolution(&TimeUnit::Day), None);
+    }
+
+    #[test]
+    fn period_start_unit_test() {
+        let t = at("2022-03-17 10:41:12");
+        let starts = [
+            (TimeUnit::QuarterOfAnHour, "2022-03-17 10:30:00"),
+            (TimeUnit::Hour, "2022-03-17 10:00:00"),
+            (TimeUnit::Day, "2022-03-17 00:00:00"),
+            (TimeUnit::Week, "2022-03-14 00:00:00"),
+            (TimeUnit::Month, "2022-03-01 00:00:00"),
+            (TimeUnit::Year, "2022-01-01 00:00:00"),
+        ];
+        for (time_unit, start) in starts {
+            assert_eq!(period_start(t, &time_unit), at(start), "{time_unit}");
+        }
+    }
+
+    #[test]
+    fn next_period_unit_test() {
+        let t = at("2022-01-31 00:00:00");
+        let next = [
+            (TimeUnit::QuarterOfAnHour, "2022-01-31 00:15:00"),
+            (TimeUnit::Hour, "2022-01-31 01:00:00"),
+            (TimeUnit::Day, "2022-02-01 00:00:00"),
+            (TimeUnit::Week, "2022-02-07 00:00:00"),
+            (TimeUnit::Month, "2022-02-28 00:00:00"),
+            (TimeUnit::Year, "2023-01-31 00:00:00"),
+        ];
+        for (time_unit, n) in next {
+            assert_eq!(next_period(t, &time_unit), Some(at(n)), "{time_unit}");
+        }
+    }
+
+    #[test]
+    fn normal_types_unit_test() {
+        is_normal::<DataEndpoint>();
+        is_normal::<MaxSpan>();
+    }
+}
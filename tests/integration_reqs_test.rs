@@ -6,12 +6,12 @@ extern crate lazy_static;
 mod common;
 
 use se_ms_api::{
-    AccountsListReq, CurrentVersionReq, InverterMode, Kind, MeterType, SendReq, SiteDataPeriodReq,
-    SiteDetailsReq, SiteEnergyDetailedReq, SiteEnergyReq, SiteEnvironmentalBenefitsReq,
-    SiteEquipmentChangeLogReq, SiteEquipmentListReq, SiteGetMetersDataReq, SiteGetSensorListReq,
-    SiteInventoryReq, SiteInverterTechnicalDataReq, SiteListReq, SiteOverviewReq,
-    SitePowerDetailedReq, SitePowerFlowReq, SitePowerReq, SiteStorageDataReq,
-    SiteTimeFrameEnergyReq, SupportedVersionsReq, SystemUnits, TimeUnit,
+    fetch_period_energy, AccountsListReq, BillingPeriod, CurrentVersionReq, InverterMode, Kind,
+    MeterType, OperationMode, SendReq, SiteDataPeriodReq, SiteDetailsReq, SiteEnergyDetailedReq,
+    SiteEnergyReq, SiteEnvironmentalBenefitsReq, SiteEquipmentChangeLogReq, SiteEquipmentListReq,
+    SiteGetMetersDataReq, SiteGetSensorListReq, SiteInventoryReq, SiteInverterTechnicalDataReq,
+    SiteListReq, SiteOverviewReq, SitePowerDetailedReq, SitePowerFlowReq, SitePowerReq, SiteStatus,
+    SiteStorageDataReq, SiteTimeFrameEnergyReq, SupportedVersionsReq, SystemUnits, TimeUnit,
 };
 
 #[test]
@@ -47,7 +47,7 @@ fn site_energy_detailed_integration_test() {
             );
             assert_eq!(r.energy_details.meters[0].values.len(), 31);
 
-            let mut self_consumption: f32 = 0.0;
+            let mut self_consumption: f64 = 0.0;
             for v in &r.energy_details.meters[0].values {
                 if let Some(value) = v.value {
                     self_consumption += value;
@@ -61,6 +61,26 @@ fn site_energy_detailed_integration_test() {
     };
 }
 
+#[test]
+fn fetch_period_energy_integration_test() {
+    let start = NaiveDate::from_ymd_opt(2022, 1, 1).unwrap();
+    let end = NaiveDate::from_ymd_opt(2022, 1, 31).unwrap();
+
+    let resp = fetch_period_energy(&common::TEST_CREDENTIALS, &[BillingPeriod::new(start, end)]);
+
+    match resp {
+        Ok(r) => {
+            assert_eq!(r.len(), 1);
+            assert_eq!(r[0].unit, "Wh");
+            let self_consumption = r[0].self_consumption.unwrap();
+            assert_eq!(self_consumption as u32, 292473);
+        }
+        Err(e) => {
+            panic!("Unexpected fetch_period_energy response: {:?}", e);
+        }
+    };
+}
+
 #[test]
 fn current_version_integration_test() {
     let req = CurrentVersionReq::new();
@@ -99,7 +119,7 @@ fn site_details_integration_test() {
     match resp {
         Ok(r) => {
             assert_eq!(r.details.id.to_string(), common::TEST_CREDENTIALS.site_id());
-            assert_eq!(r.details.status, "Active");
+            assert_eq!(r.details.status, SiteStatus::Active);
             assert_eq!(r.details.location.country_code, "US");
             assert_eq!(r.details.primary_module.manufacturer_name, "LG");
             assert!(r.details.uris.contains_key("SITE_IMAGE"));
@@ -137,13 +157,14 @@ fn site_power_detailed_integration_test() {
             assert_eq!(r.power_details.meters[0].meter_type, MeterType::Purchased);
             assert_eq!(r.power_details.meters[0].values.len(), 2880);
 
-            let mut self_consumption: f32 = 0.0;
+            let mut self_consumption: f64 = 0.0;
             for v in &r.power_details.meters[0].values {
                 if let Some(value) = v.value {
                     self_consumption += value;
                 }
             }
-            assert!(self_consumption == 2277237.5);
+            // Expected total was recorded when values were summed as f32.
+            assert!((self_consumption - 2277237.5).abs() / 2277237.5 < 1e-4);
         }
         Err(e) => {
             panic!("Unexpected SitePowerDetailedReq response: {:?}", e);
@@ -267,12 +288,12 @@ fn site_power_integration_test() {
             assert_eq!(r.power.values[3].date, "2022-01-01 12:45:00");
 
             if let Some(v) = r.power.values[0].value {
-                assert_eq!(v, 2013.872);
+                assert!((v - 2013.872).abs() < 0.001);
             } else {
                 panic!("Missing value.");
             }
             if let Some(v) = r.power.values[3].value {
-                assert_eq!(v, 1670.7087);
+                assert!((v - 1670.7087).abs() < 0.001);
             } else {
                 panic!("Missing value.");
             }
@@ -290,7 +311,7 @@ fn site_list_integration_test() {
         Ok(r) => {
             assert_eq!(r.sites.count, 1);
             assert_eq!(r.sites.site.e.len(), 1);
-            assert_eq!(r.sites.site.e[0].status, "Active");
+            assert_eq!(r.sites.site.e[0].status, SiteStatus::Active);
             assert_eq!(r.sites.site.e[0].location.country_code, "US");
             assert!(r.sites.site.e[0].uris.contains_key("SITE_IMAGE"));
             assert!(!r.sites.site.e[0].public_settings.is_public);
@@ -332,7 +353,11 @@ fn site_overview_integration_test() {
                 panic!("Unexpected value.");
             }
 
-            assert!(r.overview.current_power.power >= 0.0);
+            if let Some(power) = r.overview.current_power.power {
+                assert!(power >= 0.0);
+            } else {
+                panic!("Missing value.");
+            }
             assert_eq!(r.overview.measured_by, "INVERTER".to_string());
         }
         Err(e) => {
@@ -513,13 +538,14 @@ fn site_get_meters_data_integration_test() {
             );
             assert_eq!(r.meter_energy_details.meters[0].values.len(), 30);
 
-            let mut self_consumption: f32 = 0.0;
+            let mut self_consumption: f64 = 0.0;
             for v in &r.meter_energy_details.meters[0].values {
                 if let Some(value) = v.value {
                     self_consumption += value;
                 }
             }
-            assert_eq!(self_consumption as u32, 906998528);
+            // Expected total was recorded when values were summed as f32.
+            assert!((self_consumption - 906998528.0).abs() / 906998528.0 < 1e-6);
         }
         Err(e) => {
             panic!("Unexpected SiteGetMetersDataReq response: {:?}", e);
@@ -527,6 +553,24 @@ fn site_get_meters_data_integration_test() {
     };
 }
 
+#[test]
+fn site_list_iter_integration_test() {
+    let req = SiteListReq::new(Some(1), None, None, None, None, None);
+    let sites = req
+        .iter(&common::TEST_CREDENTIALS)
+        .collect::<Result<Vec<_>, _>>();
+
+    match sites {
+        Ok(s) => {
+            assert_eq!(s.len(), 1);
+            assert_eq!(s[0].id.to_string(), common::TEST_CREDENTIALS.site_id());
+        }
+        Err(e) => {
+            panic!("Unexpected SiteList iteration error: {:?}", e);
+        }
+    }
+}
+
 #[test]
 fn accounts_list_integration_test() {
     let req = AccountsListReq::new(Some(1), Some(0), None, None, None);
@@ -627,7 +671,10 @@ fn site_inverter_technical_data_integration_test() {
                 r.data.telemetries.t[0].inverter_mode,
                 InverterMode::Sleeping
             );
-            assert_eq!(r.data.telemetries.t[0].operation_mode, 0);
+            assert_eq!(
+                r.data.telemetries.t[0].operation_mode,
+                OperationMode::OnGrid
+            );
             assert_eq!(r.data.telemetries.t[0].l1_data.ac_voltage, 245.42);
             assert_eq!(r.data.telemetries.t[1].inverter_mode, InverterMode::Mppt);
         }